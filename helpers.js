@@ -15,4 +15,19 @@ function repeat(text, times) {
 // {{wrap text}} → "[hello world]"
 function wrap(text) {
     return "[" + String(text || '').trim() + "]";
-}
\ No newline at end of file
+}
+// {{join7 a b c d e f g}} → "a-b-c-d-e-f-g": every param arrives in position
+function join7(a, b, c, d, e, f, g) {
+    return [a, b, c, d, e, f, g].join('-');
+}
+
+// {{argCount a b c d e f g h}} → "8": params before the trailing options object
+function argCount() {
+    return String(arguments.length - 1);
+}
+
+// {{sumAll 1 2 3 4 5 6 7 8 9}} → "45"
+function sumAll(...args) {
+    args.pop(); // the options object
+    return String(args.reduce((total, n) => total + Number(n), 0));
+}
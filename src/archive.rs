@@ -0,0 +1,127 @@
+//! Archive output: generated files go into a .zip or .tar.gz instead of the filesystem.
+//!
+//! Enabled with --features archive
+//! Usage: --archive docs.zip (or docs.tar.gz / docs.tgz)
+
+use anyhow::Result;
+use std::path::Path;
+
+#[cfg(feature = "archive")]
+use anyhow::Context;
+#[cfg(feature = "archive")]
+use std::fs::File;
+#[cfg(feature = "archive")]
+use std::io::Write;
+
+/// Open archive being written
+pub struct ArchiveWriter {
+    #[cfg(feature = "archive")]
+    inner: Inner,
+    /// Number of entries added so far
+    pub entries: usize,
+}
+
+#[cfg(feature = "archive")]
+enum Inner {
+    Zip(zip::ZipWriter<File>),
+    TarGz(tar::Builder<flate2::write::GzEncoder<File>>),
+}
+
+impl ArchiveWriter {
+    /// Stub implementation when archive feature is disabled
+    #[cfg(not(feature = "archive"))]
+    pub fn create(_path: &Path) -> Result<Self> {
+        anyhow::bail!("Archive output requires: cargo build --features archive")
+    }
+
+    /// Stub implementation when archive feature is disabled
+    #[cfg(not(feature = "archive"))]
+    pub fn add(&mut self, _name: &Path, _body: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stub implementation when archive feature is disabled
+    #[cfg(not(feature = "archive"))]
+    pub fn finish(self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Create the archive; the format follows the extension (.zip, .tar.gz or .tgz)
+    #[cfg(feature = "archive")]
+    pub fn create(path: &Path) -> Result<Self> {
+        let name = path.to_string_lossy().to_lowercase();
+        let inner = if name.ends_with(".zip") {
+            let file = create_file(path)?;
+            Inner::Zip(zip::ZipWriter::new(file))
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            let file = create_file(path)?;
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            Inner::TarGz(tar::Builder::new(encoder))
+        } else {
+            anyhow::bail!(
+                "Unsupported archive type: {} (use .zip, .tar.gz or .tgz)",
+                path.display()
+            );
+        };
+        Ok(Self { inner, entries: 0 })
+    }
+
+    /// Add a file at a path relative to the archive root
+    #[cfg(feature = "archive")]
+    pub fn add(&mut self, name: &Path, body: &[u8]) -> Result<()> {
+        // Archive paths always use forward slashes
+        let entry = name
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        match &mut self.inner {
+            Inner::Zip(zip) => {
+                let options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                zip.start_file(entry.as_str(), options)
+                    .with_context(|| format!("Failed to add {} to archive", entry))?;
+                zip.write_all(body)?;
+            }
+            Inner::TarGz(tar) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(body.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs()),
+                );
+                header.set_cksum();
+                tar.append_data(&mut header, &entry, body)
+                    .with_context(|| format!("Failed to add {} to archive", entry))?;
+            }
+        }
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Write the archive trailer and close the file
+    #[cfg(feature = "archive")]
+    pub fn finish(self) -> Result<()> {
+        match self.inner {
+            Inner::Zip(zip) => {
+                zip.finish().context("Failed to finish zip archive")?;
+            }
+            Inner::TarGz(tar) => {
+                tar.into_inner()
+                    .and_then(|gz| gz.finish())
+                    .context("Failed to finish tar.gz archive")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "archive")]
+fn create_file(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    File::create(path).with_context(|| format!("Failed to create archive: {}", path.display()))
+}
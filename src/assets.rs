@@ -0,0 +1,127 @@
+//! Copying files referenced by items next to the generated notes (`assets` setting).
+//!
+//! Settings: "assets": {"fields": ["cover", "attachments"], "dir": "assets"}
+//!
+//! Each listed field holds a path (or an array of paths), relative to the data file it
+//! came from. The files are copied into `dir` below the output directory and
+//! `{{assetPath cover}}` gives the link to the copy relative to the note being
+//! rendered (`../assets/cover.png` for a note one folder down). URLs are left alone.
+
+use crate::{input, path};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Asset copying configuration
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AssetSettings {
+    /// Item fields (dot paths) naming files to copy
+    pub fields: Vec<String>,
+    /// Directory for the copies, relative to the output directory
+    pub dir: String,
+}
+
+impl Default for AssetSettings {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            dir: "assets".to_string(),
+        }
+    }
+}
+
+/// File name of a reference inside the assets directory (None for URLs and empty values)
+fn asset_name(reference: &str) -> Option<String> {
+    let reference = reference.trim();
+    if reference.is_empty() || input::is_url(reference) {
+        return None;
+    }
+    Path::new(reference)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Link from a note in `note_dir` (relative to the output directory) to an asset
+pub fn link(settings: &AssetSettings, note_dir: &str, reference: &str) -> String {
+    let Some(name) = asset_name(reference) else {
+        return reference.to_string();
+    };
+    let depth = Path::new(note_dir)
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count();
+    let mut link = "../".repeat(depth);
+    for segment in settings.dir.split(['/', '\\']).filter(|s| !s.is_empty()) {
+        link.push_str(segment);
+        link.push('/');
+    }
+    link.push_str(&name);
+    link
+}
+
+/// A file to copy: its source and its path relative to the output directory
+pub struct AssetCopy {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// Tracks copied assets across items so each file is copied once
+#[derive(Default)]
+pub struct AssetPlanner {
+    /// Source already copied under each asset name
+    copied: HashMap<String, PathBuf>,
+}
+
+impl AssetPlanner {
+    /// New copies needed for an item, plus warnings for name clashes (two different
+    /// files with the same name: the first one wins)
+    pub fn plan(
+        &mut self,
+        settings: &AssetSettings,
+        item: &Value,
+        source_dir: Option<&Path>,
+    ) -> (Vec<AssetCopy>, Vec<String>) {
+        let mut copies = Vec::new();
+        let mut warnings = Vec::new();
+        for field in &settings.fields {
+            let references = match path::select(item, field) {
+                Some(Value::String(s)) => vec![s],
+                Some(Value::Array(items)) => items
+                    .into_iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect(),
+                _ => continue,
+            };
+            for reference in references {
+                let Some(name) = asset_name(&reference) else {
+                    continue;
+                };
+                let source = match source_dir {
+                    Some(dir) if Path::new(reference.trim()).is_relative() => {
+                        dir.join(reference.trim())
+                    }
+                    _ => PathBuf::from(reference.trim()),
+                };
+                match self.copied.get(&name) {
+                    Some(first) if *first == source => {}
+                    Some(first) => warnings.push(format!(
+                        "Asset {} clashes with {} (both named {}); keeping the first",
+                        source.display(),
+                        first.display(),
+                        name
+                    )),
+                    None => {
+                        self.copied.insert(name.clone(), source.clone());
+                        copies.push(AssetCopy {
+                            source,
+                            target: Path::new(&settings.dir).join(&name),
+                        });
+                    }
+                }
+            }
+        }
+        (copies, warnings)
+    }
+}
@@ -0,0 +1,1542 @@
+//! The `json2md` command line: arguments, settings layering and the conversion run.
+
+use crate::flavor::Flavor;
+use crate::input::{self, CsvTypes, DataFormat, InputOptions};
+use crate::js_helpers::{DynamicHelperRegistry, js_helper_files};
+use crate::lua_helpers::LuaHelperRegistry;
+use crate::python_helpers::PythonHelperRegistry;
+use crate::rhai_helpers::RhaiHelperRegistry;
+use crate::{
+    ArchiveSink, DataRootMode, DedupeKeep, FileSink, HelperInfo, IfExists, JsonImportSettings,
+    OnError, OutputSink, OutputStrategy, RESERVED_CONTEXT_KEYS, RunError, RunOptions, SourceInfo,
+    begin_run, builtin_helper_info, collect_items, config, determine_output_strategy, filter,
+    generate_notes, i18n, lint, logging, merge_sources, nav, new_handlebars, path, resolve_target,
+    site, watch,
+};
+use anyhow::{Context, Result};
+use clap::Parser;
+use handlebars::Handlebars;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ============================================================================
+// CLI Arguments
+// ============================================================================
+
+#[derive(Parser, Debug)]
+#[command(name = "json-to-md")]
+#[command(about = "Convert JSON/CSV to Markdown with Handlebars templates and dynamic helpers")]
+#[command(version)]
+#[command(
+    after_help = "Exit codes: 0 success, 1 fatal error, 2 completed with failed items or \
+                  helpers that did not load, 3 validation failed (--check, --lint-template --strict)"
+)]
+#[command(
+    override_usage = "json-to-md [OPTIONS] <DATA_FILE>... <TEMPLATE_FILE>\n       \
+                            json-to-md [OPTIONS] --glob <PATTERN> <TEMPLATE_FILE>\n       \
+                            json-to-md [OPTIONS] --template <NAME=FILE>... <DATA_FILE>...\n       \
+                            json-to-md [OPTIONS] --template-str <TEMPLATE> <DATA_FILE>...\n       \
+                            json-to-md [OPTIONS] --list-helpers"
+)]
+struct Args {
+    /// Input data file(s) followed by the Handlebars template file (.md).
+    /// Data: .json, .json5/.jsonc, .csv, .tsv, .fwf, .yaml/.yml, .toml, .xml (plus .xlsx, .sqlite/.db and
+    /// .parquet with the xlsx/sqlite/parquet features), a directory of Markdown notes,
+    /// or an http(s):// URL with the http feature. Several files (or a quoted glob such as
+    /// "data/*.json") are merged into one item list; they may be omitted when --glob is given.
+    /// With --template or --template-str every FILE is a data file
+    #[arg(
+        value_name = "FILE",
+        required_unless_present_any = ["templates", "template_str", "list_helpers"],
+        num_args = 1..
+    )]
+    files: Vec<PathBuf>,
+
+    /// Template file given as NAME=FILE instead of the last FILE. Repeat it to produce
+    /// several files per item in one pass: each template then writes into its own NAME/
+    /// subdirectory of the output directory (needs multi-file output)
+    #[arg(long = "template", value_name = "NAME=FILE")]
+    templates: Vec<String>,
+
+    /// Template text given inline instead of a template file, e.g. '{{name}}: {{value}}'
+    #[arg(
+        long = "template-str",
+        value_name = "TEMPLATE",
+        conflicts_with = "templates"
+    )]
+    template_str: Option<String>,
+
+    /// Discover data files matching a glob, e.g. "exports/**/*.csv" (repeatable)
+    #[arg(long = "glob", value_name = "PATTERN")]
+    globs: Vec<String>,
+
+    /// jq expression applied to the parsed data before rendering,
+    /// e.g. '.posts[] | select(.status == "published")'
+    #[arg(long = "filter", value_name = "EXPR")]
+    filter: Option<String>,
+
+    /// Stream a top-level JSON array item by item with bounded memory (no `dataRoot`;
+    /// incompatible with options that need every item at once, such as sorting, grouping,
+    /// --check or --lint-template)
+    #[arg(long = "stream")]
+    stream: bool,
+
+    /// Character encoding of text input, e.g. utf-16le, windows-1252, latin1
+    /// (default: detect from BOM, else UTF-8, UTF-16 or Windows-1252)
+    #[arg(long = "encoding", value_name = "LABEL")]
+    encoding: Option<String>,
+
+    /// Input format, overriding extension-based detection
+    #[arg(long = "format", value_name = "FORMAT")]
+    format: Option<DataFormat>,
+
+    /// Parse JSON leniently (comments, trailing commas, unquoted keys); same as --format json5
+    #[arg(long = "json5", conflicts_with = "format")]
+    json5: bool,
+
+    /// CSV cell typing: "string" keeps every cell as text, "auto" converts numbers,
+    /// booleans and empty cells (null); see csv_column_types in settings for per-column types
+    #[arg(long = "csv-types", value_name = "MODE", default_value = "string")]
+    csv_types: CsvTypes,
+
+    /// CSV field delimiter, e.g. ';' or "tab"
+    #[arg(long = "delimiter", value_name = "CHAR", value_parser = input::parse_csv_char)]
+    delimiter: Option<u8>,
+
+    /// CSV quote character (default '"')
+    #[arg(long = "quote", value_name = "CHAR", value_parser = input::parse_csv_char)]
+    quote: Option<u8>,
+
+    /// CSV escape character inside quoted fields (default: doubled quotes)
+    #[arg(long = "escape", value_name = "CHAR", value_parser = input::parse_csv_char)]
+    escape: Option<u8>,
+
+    /// CSV has no header row: keys are col0, col1, ... unless --column-names is given
+    #[arg(long = "no-headers")]
+    no_headers: bool,
+
+    /// Expand dotted CSV headers ("user.name") into nested objects, like the JSON they
+    /// were flattened from; same as csv_nested in settings
+    #[arg(long = "csv-nested")]
+    csv_nested: bool,
+
+    /// Comma-separated CSV column names, replacing the header row
+    #[arg(long = "column-names", value_name = "NAMES", value_delimiter = ',')]
+    column_names: Vec<String>,
+
+    /// Worksheet name for Excel input (defaults to the first sheet)
+    #[arg(long = "sheet", value_name = "NAME")]
+    sheet: Option<String>,
+
+    /// SQL query producing the items when DATA_FILE is a SQLite database
+    #[arg(long = "query", value_name = "SQL")]
+    query: Option<String>,
+
+    /// Comma-separated columns to read from Parquet input (default: all)
+    #[arg(long = "columns", value_name = "COLS", value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// HTTP header for URL data sources, e.g. "Authorization: Bearer TOKEN" (repeatable)
+    #[arg(long = "header", value_name = "HEADER")]
+    headers: Vec<String>,
+
+    /// Cache URL responses in DIR (default .json2md-cache); delete it to refetch
+    #[arg(long = "cache", value_name = "DIR", num_args = 0..=1)]
+    cache: Option<Option<PathBuf>>,
+
+    /// Follow pagination for URL sources (Link rel="next" header or JSON `next` field),
+    /// concatenating pages. Optional value: items field in object pages, e.g. "results"
+    #[arg(long = "paginate", value_name = "ITEMS_FIELD", num_args = 0..=1)]
+    paginate: Option<Option<String>>,
+
+    /// Output file path (single file mode). If omitted, generates multiple files in folder_name.
+    /// "-" writes the rendered result to stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Write the rendered single-file result to stdout (same as `-o -`); all logging goes
+    /// to stderr
+    #[arg(long = "stdout", conflicts_with_all = ["output", "out_dir"])]
+    stdout: bool,
+
+    /// Output directory: always multi-file mode, overriding -o heuristics and folder_name
+    #[arg(long = "out-dir", value_name = "DIR", conflicts_with = "output")]
+    out_dir: Option<PathBuf>,
+
+    /// Directory of partial templates, usable as {{> name}}; files in subdirectories
+    /// are {{> sub/name}} (the name is the path without extension). Repeatable
+    #[arg(long = "partials", value_name = "DIR")]
+    partials: Vec<PathBuf>,
+
+    /// JavaScript helper file to load dynamically: a script, an ES module (.mjs, or using
+    /// import/export) whose exported functions become helpers, or a directory of them
+    #[arg(long = "js-helpers", value_name = "PATH")]
+    js_helpers: Option<PathBuf>,
+
+    /// Lua helper file: its global functions become helpers (needs the lua-helpers feature)
+    #[arg(long = "lua-helpers", value_name = "FILE")]
+    lua_helpers: Option<PathBuf>,
+
+    /// Python helper file: its top-level functions become helpers (needs the
+    /// python-helpers feature)
+    #[arg(long = "python-helpers", value_name = "FILE")]
+    python_helpers: Option<PathBuf>,
+
+    /// Rhai helper script: its functions become helpers, also in filename templates
+    /// (pure Rust, in the default build)
+    #[arg(long = "rhai-helpers", value_name = "FILE")]
+    rhai_helpers: Option<PathBuf>,
+
+    /// Fail rendering when a JS helper reports problems via console.error
+    #[arg(long = "strict-helpers")]
+    strict_helpers: bool,
+
+    /// Memory limit of each JS helper runtime, in MiB
+    #[arg(long = "js-max-memory", value_name = "MIB", value_parser = clap::value_parser!(u64).range(1..))]
+    js_max_memory: Option<u64>,
+
+    /// Time limit for loading a JS helper file and for each JS helper call, in
+    /// milliseconds; a helper that runs longer fails like one that throws
+    #[arg(long = "js-timeout-ms", value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    js_timeout_ms: Option<u64>,
+
+    /// Rust plugin library to load (.so/.dll/.dylib)
+    #[arg(long = "rs-plugin", value_name = "FILE")]
+    rs_plugin: Option<PathBuf>,
+
+    /// JSON object handed to helpers: JS files see it as `globalThis.helperConfig`,
+    /// plugins get it while registering (API keys, locale options and the like)
+    #[arg(long = "helper-config", value_name = "FILE")]
+    helper_config: Option<PathBuf>,
+
+    /// Directory of helpers: every .js/.mjs, .lua, .py and .rhai file and .so/.dll/.dylib plugin in it is loaded,
+    /// in name order. Repeatable; adds to the `plugin_dir` setting
+    #[arg(long = "plugin-dir", value_name = "DIR")]
+    plugin_dirs: Vec<PathBuf>,
+
+    /// Stop when a --js-helpers, --lua-helpers, --python-helpers or --rhai-helpers file,
+    /// --rs-plugin library or --plugin-dir entry fails to load or register, instead of
+    /// rendering without its helpers
+    #[arg(long = "require-helpers")]
+    require_helpers: bool,
+
+    /// List every helper templates can call and stop: built-in, script and plugin helpers
+    /// (loaded from the helper options and settings as for a run) with their source, and
+    /// their signature and description where the source shows them
+    #[arg(long = "list-helpers")]
+    list_helpers: bool,
+
+    /// Settings file (JSON, TOML or YAML), layered over a json2md.toml found in the
+    /// current directory or its ancestors
+    #[arg(short, long, value_name = "FILE")]
+    settings: Option<PathBuf>,
+
+    /// Do not look for a json2md.toml project settings file
+    #[arg(long = "no-config")]
+    no_config: bool,
+
+    /// Enable verbose debug output (same as --log-level debug)
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Show messages of this level and above on stderr
+    #[arg(long = "log-level", value_name = "LEVEL", value_enum)]
+    log_level: Option<logging::Level>,
+
+    /// Write diagnostics as plain text or as JSON lines
+    #[arg(long = "log-format", value_name = "FORMAT", value_enum, default_value_t = logging::Format::Text)]
+    log_format: logging::Format,
+
+    /// Only show errors (same as --log-level error)
+    #[arg(short, long, conflicts_with_all = ["verbose", "log_level"])]
+    quiet: bool,
+
+    /// Split output: generate one file per array entry.
+    /// - Without arg: append index (output_0.md, output_1.md)
+    /// - With field path: use JSON field value (output_{value}.md)
+    /// - With Handlebars: use template syntax (output_{{upper title}}.md)
+    ///
+    /// Note: The CLI uses Option<Option<String>> to distinguish:
+    /// - No flag: None
+    /// - `-s` (no value): Some(None)
+    /// - `-s value`: Some(Some("value"))
+    #[arg(short = 'x', long = "split", value_name = "TEMPLATE", num_args = 0..=1)]
+    split: Option<Option<String>>,
+
+    /// Render the template once with the whole input as context (index/summary pages).
+    /// Object roots expose their fields directly; iterate the dataset via `{{#each dataRoot}}`
+    #[arg(long = "single-context")]
+    single_context: bool,
+
+    /// Obsidian vault preset: Obsidian-safe filenames, `tags`/`aliases` properties;
+    /// same as the obsidian setting
+    #[arg(long = "obsidian")]
+    obsidian: bool,
+
+    /// Precompute aggregates injected into every context as `aggregates.<name>`.
+    /// Example: "total=sum:price,count=length:items".
+    /// sum/avg/min/max/count read the field from each item; length measures a path from the data root
+    #[arg(long = "aggregate", value_name = "SPEC")]
+    aggregate: Option<String>,
+
+    /// Sort items before rendering: "field[:desc]", optionally with a comparison mode
+    /// (auto, num, str, date), e.g. "published:desc:date,title"; overrides settings sort_by
+    #[arg(long = "sort-by", value_name = "SPEC")]
+    sort_by: Option<String>,
+
+    /// Skip the first N items (after filtering and sorting)
+    #[arg(long = "skip", value_name = "N", default_value_t = 0)]
+    skip: usize,
+
+    /// Render at most N items (after --skip)
+    #[arg(long = "limit", value_name = "N")]
+    limit: Option<usize>,
+
+    /// Split items into pages of N, writing <output>_page_1.md, <output>_page_2.md, ...
+    /// Pages get `items`, `page`, `totalPages`, `prevPage` and `nextPage` (file names)
+    #[arg(long = "chunk", value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    chunk: Option<u64>,
+
+    /// Only rewrite outputs whose rendered content changed, leaving identical files (and
+    /// their timestamps) untouched; prints a created/updated/unchanged summary
+    #[arg(long = "incremental")]
+    incremental: bool,
+
+    /// Parse, name and render everything but write nothing: lists the files that would
+    /// be created or overwritten and reports every render error
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// What to do when an output file already exists
+    #[arg(long = "if-exists", value_name = "POLICY", value_enum, default_value_t = IfExists::Overwrite)]
+    if_exists: IfExists,
+
+    /// Update existing files by merging the rendered YAML frontmatter keys into their
+    /// frontmatter (new values win, other keys are kept) and leaving the body untouched
+    #[arg(long = "merge-frontmatter")]
+    merge_frontmatter: bool,
+
+    /// After multi-file generation, render this template once into an index file. Its
+    /// context has `files` (each with `file`, `name`, `index` and the rendered `item`)
+    /// and `count`
+    #[arg(long = "index-template", value_name = "FILE")]
+    index_template: Option<PathBuf>,
+
+    /// File name of the index written with --index-template
+    #[arg(long = "index-name", value_name = "NAME", default_value = "_index.md")]
+    index_name: String,
+
+    /// After multi-file generation, update site navigation with the generated pages:
+    /// the `nav:` of mkdocs.yml (next to the output directory) or mdBook's SUMMARY.md
+    /// (inside it). Pages are titled by their `title` field and grouped by folder
+    #[arg(long = "nav", value_name = "KIND", value_enum)]
+    nav: Option<nav::NavKind>,
+
+    /// Navigation file to update instead of the default location
+    #[arg(long = "nav-file", value_name = "FILE", requires = "nav")]
+    nav_file: Option<PathBuf>,
+
+    /// Write all outputs into a .zip, .tar.gz or .tgz archive instead of loose files,
+    /// keeping the directory structure below the output directory
+    #[arg(long = "archive", value_name = "FILE", conflicts_with = "stdout")]
+    archive: Option<PathBuf>,
+
+    /// Output markup flavor: item separator, default extension and the `escape` helper
+    #[arg(long = "flavor", value_name = "FLAVOR", value_enum)]
+    flavor: Option<Flavor>,
+
+    /// Escape markup characters (`| * _ [` …) in `{{value}}` output so field values
+    /// cannot break tables or emphasis; `{{{value}}}` writes a value raw. Same as the
+    /// auto_escape setting
+    #[arg(long = "auto-escape")]
+    auto_escape: bool,
+
+    /// Locale for translations, dates and numbers, e.g. "de" (same as the locale setting)
+    #[arg(long = "locale", value_name = "TAG")]
+    locale: Option<String>,
+
+    /// Translations (JSON, YAML or TOML) with one table per locale, used by
+    /// {{t "key"}}: {"de": {"title": "Bericht"}, "en": {"title": "Report"}}
+    #[arg(long = "translations", value_name = "FILE")]
+    translations: Option<PathBuf>,
+
+    /// Static site preset: content folder, slug filenames and title/date/draft/slug
+    /// frontmatter for Hugo, Jekyll or Zola (--out-dir is the site root); keys in the
+    /// settings file override the preset
+    #[arg(long = "site", value_name = "SITE", value_enum)]
+    site: Option<site::Site>,
+
+    /// Convert the rendered Markdown to HTML; multi-file outputs get a .html extension
+    /// unless output_extension is set
+    #[arg(long = "render-html")]
+    render_html: bool,
+
+    /// Handlebars layout wrapping the HTML (implies --render-html): `{{content}}` is the
+    /// converted HTML, `title` the output name, plus the item fields
+    #[arg(long = "html-layout", value_name = "FILE")]
+    html_layout: Option<PathBuf>,
+
+    /// Re-run whenever the data files, templates, settings, helpers, vars or lookups change
+    #[arg(long = "watch")]
+    watch: bool,
+
+    /// Object of shared template variables (JSON, YAML, TOML, ...), available as
+    /// `{{vars.name}}` in every context. Always injected under `vars`, replacing any item
+    /// field named `vars`
+    #[arg(long = "vars", value_name = "FILE")]
+    vars: Option<PathBuf>,
+
+    /// Override a setting, e.g. --set unique_names=true or --set assets.dir=media
+    /// (repeatable, applied over the settings file); values are JSON when the setting
+    /// is not text
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Site-wide values (JSON, YAML or TOML object: author, base URL, footer text…),
+    /// available as `{{globals.name}}` in every context
+    #[arg(long = "context", value_name = "FILE")]
+    globals: Option<PathBuf>,
+
+    /// Template parameter "KEY=VALUE", available as `{{params.KEY}}` in every context
+    /// (repeatable), e.g. --param version=1.4.2
+    #[arg(long = "param", value_name = "KEY=VALUE")]
+    params: Vec<String>,
+
+    /// Expose the environment variables whose names start with PREFIX as `{{env.NAME}}`
+    /// (prefix removed): with J2MD_, J2MD_BUILD_ID is {{env.BUILD_ID}}
+    #[arg(long = "env-prefix", value_name = "PREFIX")]
+    env_prefix: Option<String>,
+
+    /// Secondary dataset exposed as `lookup.NAME`, e.g. "users=users.json" (repeatable);
+    /// join foreign keys with {{lookupJoin "users" userId}} or {{crossref "users" "id" userId}}
+    #[arg(long = "lookup", value_name = "NAME=FILE")]
+    lookups: Vec<String>,
+
+    /// Lint the template before rendering (reserved-name shadowing, unknown block
+    /// helpers, unbalanced blocks, empty expressions)
+    #[arg(long = "lint-template")]
+    lint_template: bool,
+
+    /// Strict rendering: a missing field fails the item (reported with the item index,
+    /// the field path and the output file), and lint findings are fatal
+    #[arg(long = "strict")]
+    strict: bool,
+
+    /// What to do when an item fails to render: stop the run, skip the item, or write a
+    /// placeholder file in its place; with skip and placeholder the run continues and
+    /// exits non-zero when any item failed
+    #[arg(long = "on-error", value_name = "POLICY", value_enum, default_value_t = OnError::Abort)]
+    on_error: OnError,
+
+    /// Same as --on-error skip
+    #[arg(long = "keep-going")]
+    keep_going: bool,
+
+    /// Write the failed items (index, output file, field and reason) to FILE as JSON
+    #[arg(long = "errors-report", value_name = "FILE")]
+    errors_report: Option<PathBuf>,
+
+    /// Write a JSON summary of the run to FILE: files created, updated, unchanged and
+    /// skipped, bytes written, render time per item, warnings, resolved name collisions
+    /// and failed items
+    #[arg(long = "report", value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Validate without rendering: the templates compile, every partial and helper they
+    /// use exists (JS and plugin helpers included) and the fields they reference occur
+    /// in the data; exits non-zero on findings
+    #[arg(long = "check")]
+    check: bool,
+}
+
+// ============================================================================
+// Entry Point
+// ============================================================================
+
+/// Run the command line: parse the arguments, convert (or watch) and exit with the
+/// run's exit code
+pub fn main() {
+    let args = Args::parse();
+    let level = match args.log_level {
+        _ if args.quiet => logging::Level::Error,
+        Some(level) => level,
+        None if args.verbose => logging::Level::Debug,
+        None => logging::Level::Info,
+    };
+    logging::init(level, args.log_format);
+    // Fatal errors go through the logger so --log-format applies to them too
+    if let Err(e) = run(&args) {
+        error_log!("{:#}", e);
+        std::process::exit(e.downcast_ref::<RunError>().map_or(1, RunError::exit_code));
+    }
+}
+
+/// Convert once, or keep converting on changes with --watch
+fn run(args: &Args) -> Result<()> {
+    if args.list_helpers {
+        return list_helpers(args);
+    }
+    if !args.watch {
+        return convert(args);
+    }
+    // Watch mode keeps going after failed runs so the inputs can be fixed
+    if let Err(e) = convert(args) {
+        error_log!("{:#}", e);
+    }
+    watch::watch(&watched_files(args)?, || {
+        if let Err(e) = convert(args) {
+            error_log!("{:#}", e);
+        }
+    })
+}
+
+/// Partial templates in a directory (recursively), named by their relative path
+/// without extension; hidden files are skipped
+fn partial_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    fn collect(root: &Path, dir: &Path, out: &mut Vec<(String, PathBuf)>) -> Result<()> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to list partials: {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            {
+                continue;
+            }
+            if path.is_dir() {
+                collect(root, &path, out)?;
+            } else {
+                let rel = path.strip_prefix(root).unwrap_or(&path).with_extension("");
+                let name = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                out.push((name, path));
+            }
+        }
+        Ok(())
+    }
+    let mut partials = Vec::new();
+    collect(dir, dir, &mut partials)?;
+    partials.sort();
+    Ok(partials)
+}
+
+/// Parse a --template spec "NAME=FILE"; the name becomes the output subdirectory
+fn parse_template_spec(spec: &str) -> Result<(String, PathBuf)> {
+    let Some((name, path)) = spec.split_once('=') else {
+        anyhow::bail!("Invalid --template '{}': expected NAME=FILE", spec);
+    };
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        anyhow::bail!(
+            "Invalid --template name '{}': use letters, digits, '_' and '-'",
+            name
+        );
+    }
+    Ok((name.to_string(), PathBuf::from(path.trim())))
+}
+
+/// Whether a file has one of the extensions (case-insensitive)
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Helper files in a plugin directory: .js/.mjs, .lua, .py and .rhai files and
+/// .so/.dll/.dylib libraries, by name
+fn plugin_dir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read plugin directory: {}", dir.display()))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let helper = has_extension(
+            &path,
+            &["js", "mjs", "lua", "py", "rhai", "so", "dll", "dylib"],
+        );
+        if helper && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Project settings file found by discovery, unless --no-config
+fn discovered_settings(args: &Args) -> Option<PathBuf> {
+    if args.no_config {
+        None
+    } else {
+        config::discover()
+    }
+}
+
+/// Local input files that trigger a re-run in watch mode
+fn watched_files(args: &Args) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = args.files.clone();
+    for pattern in &args.globs {
+        files.extend(input::glob_sources(pattern)?);
+    }
+    files.extend(discovered_settings(args));
+    files.extend(
+        [
+            &args.settings,
+            &args.lua_helpers,
+            &args.python_helpers,
+            &args.rhai_helpers,
+            &args.rs_plugin,
+            &args.helper_config,
+            &args.vars,
+            &args.globals,
+            &args.translations,
+            &args.index_template,
+            &args.html_layout,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned(),
+    );
+    if let Some(path) = &args.js_helpers {
+        files.extend(js_helper_files(path).unwrap_or_default());
+    }
+    for dir in &args.plugin_dirs {
+        files.extend(plugin_dir_files(dir).unwrap_or_default());
+    }
+    for spec in &args.lookups {
+        if let Some((_, path)) = spec.split_once('=') {
+            files.push(PathBuf::from(path.trim()));
+        }
+    }
+    for spec in &args.templates {
+        files.push(parse_template_spec(spec)?.1);
+    }
+    for dir in &args.partials {
+        files.extend(partial_files(dir)?.into_iter().map(|(_, path)| path));
+    }
+    files.retain(|file| !input::is_url(&file.to_string_lossy()));
+    Ok(files)
+}
+
+/// Apply --set overrides to the configured settings: the key is a field name or dot
+/// path into one, the value stays text for text settings and is parsed as JSON otherwise
+fn apply_setting_overrides(
+    configured: &mut serde_yaml::Mapping,
+    overrides: &[String],
+) -> Result<()> {
+    let defaults = serde_json::to_value(JsonImportSettings::default())?;
+    for spec in overrides {
+        let (key, raw) = spec
+            .split_once('=')
+            .with_context(|| format!("Invalid --set '{}': expected KEY=VALUE", spec))?;
+        let steps: Vec<&str> = key.trim().split('.').collect();
+        if steps.iter().any(|step| step.is_empty()) || defaults.get(steps[0]).is_none() {
+            anyhow::bail!("Invalid --set '{}': unknown setting '{}'", spec, key.trim());
+        }
+        let value = match path::select(&defaults, key.trim()) {
+            Some(Value::String(_)) => Value::String(raw.to_string()),
+            _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+        };
+        let (last, parents) = steps.split_last().expect("split yields a key");
+        let mut target = &mut *configured;
+        for step in parents {
+            let entry = target
+                .entry(serde_yaml::Value::from(*step))
+                .or_insert(serde_yaml::Value::Null);
+            if !entry.is_mapping() {
+                *entry = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+            }
+            target = entry.as_mapping_mut().expect("mapping ensured above");
+        }
+        target.insert(serde_yaml::Value::from(*last), serde_yaml::to_value(value)?);
+    }
+    Ok(())
+}
+
+/// The kinds of helper files a run loads, in registration order: a later kind's helper
+/// replaces an earlier one of the same name
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HelperKind {
+    Lua,
+    Python,
+    Rhai,
+    RustPlugin,
+    Js,
+}
+
+impl HelperKind {
+    const ALL: [HelperKind; 5] = [
+        HelperKind::Lua,
+        HelperKind::Python,
+        HelperKind::Rhai,
+        HelperKind::RustPlugin,
+        HelperKind::Js,
+    ];
+
+    /// What the files of this kind are called in messages
+    fn label(self) -> &'static str {
+        match self {
+            HelperKind::Lua => "Lua helpers",
+            HelperKind::Python => "Python helpers",
+            HelperKind::Rhai => "Rhai helpers",
+            HelperKind::RustPlugin => "Rust plugin",
+            HelperKind::Js => "JS helpers",
+        }
+    }
+
+    /// The kind of a file found in a plugin directory; anything that is not a script is
+    /// taken for a plugin library
+    fn of_file(path: &Path) -> HelperKind {
+        if has_extension(path, &["js", "mjs"]) {
+            HelperKind::Js
+        } else if has_extension(path, &["lua"]) {
+            HelperKind::Lua
+        } else if has_extension(path, &["py"]) {
+            HelperKind::Python
+        } else if has_extension(path, &["rhai"]) {
+            HelperKind::Rhai
+        } else {
+            HelperKind::RustPlugin
+        }
+    }
+
+    /// The files of this kind named on the command line
+    fn explicit_files(self, args: &Args) -> &[PathBuf] {
+        match self {
+            HelperKind::Lua => args.lua_helpers.as_slice(),
+            HelperKind::Python => args.python_helpers.as_slice(),
+            HelperKind::Rhai => args.rhai_helpers.as_slice(),
+            HelperKind::RustPlugin => args.rs_plugin.as_slice(),
+            HelperKind::Js => args.js_helpers.as_slice(),
+        }
+    }
+}
+
+/// The helper registries of a run. They own the interpreters and plugin libraries the
+/// registered helpers call into, so they must outlive the Handlebars instance.
+struct LoadedHelpers {
+    dynamic: DynamicHelperRegistry,
+    lua: LuaHelperRegistry,
+    python: PythonHelperRegistry,
+    rhai: RhaiHelperRegistry,
+}
+
+impl LoadedHelpers {
+    /// Every loaded helper in registration order
+    fn describe(&self) -> Vec<HelperInfo> {
+        let mut list = self.lua.describe_helpers();
+        list.extend(self.python.describe_helpers());
+        list.extend(self.rhai.describe_helpers());
+        list.extend(self.dynamic.describe_helpers());
+        list
+    }
+
+    /// Load one file of `kind`, returning the names of its helpers; Rust plugins register
+    /// theirs right away, scripts wait for `register`
+    fn load(
+        &mut self,
+        kind: HelperKind,
+        path: &Path,
+        hb: &mut Handlebars<'static>,
+    ) -> Result<Vec<String>> {
+        match kind {
+            HelperKind::Lua => self.lua.load_lua_helpers(path),
+            HelperKind::Python => self.python.load_python_helpers(path),
+            HelperKind::Rhai => self.rhai.load_rhai_helpers(path),
+            HelperKind::RustPlugin => self.dynamic.load_rust_plugin(path, hb),
+            HelperKind::Js => self.dynamic.load_js_helpers(path),
+        }
+    }
+
+    /// Register the loaded helpers of `kind` with Handlebars
+    fn register(&mut self, kind: HelperKind, hb: &mut Handlebars<'static>) -> Result<()> {
+        match kind {
+            HelperKind::Lua => self.lua.register_with_handlebars(hb),
+            HelperKind::Python => self.python.register_with_handlebars(hb),
+            HelperKind::Rhai => self.rhai.register_with_handlebars(hb),
+            HelperKind::RustPlugin => {}
+            HelperKind::Js => return self.dynamic.register_with_handlebars(hb),
+        }
+        Ok(())
+    }
+}
+
+/// Load the helper files and plugin libraries of the arguments and settings and register
+/// their helpers; helpers that fail to load are added to `problems` unless
+/// --require-helpers makes that fatal
+fn load_helpers(
+    args: &Args,
+    settings: &mut JsonImportSettings,
+    hb: &mut Handlebars<'static>,
+    known_helpers: &mut HashSet<String>,
+    problems: &mut Vec<String>,
+) -> Result<LoadedHelpers> {
+    let mut helpers = LoadedHelpers {
+        dynamic: DynamicHelperRegistry::new(),
+        lua: LuaHelperRegistry::new(),
+        python: PythonHelperRegistry::new(),
+        rhai: RhaiHelperRegistry::new(),
+    };
+    helpers.dynamic.set_strict(args.strict_helpers);
+    helpers.dynamic.set_limits(
+        args.js_max_memory.map(|mib| (mib as usize) << 20),
+        args.js_timeout_ms.map(Duration::from_millis),
+    );
+    if let Some(path) = &args.helper_config {
+        let config: Value = serde_json::from_str(
+            &fs::read_to_string(path)
+                .with_context(|| format!("Failed to read helper config: {}", path.display()))?,
+        )
+        .with_context(|| format!("Helper config is not valid JSON: {}", path.display()))?;
+        if !config.is_object() {
+            anyhow::bail!("Helper config must contain an object: {}", path.display());
+        }
+        helpers.lua.set_config(config.clone());
+        helpers.python.set_config(config.clone());
+        helpers.rhai.set_config(config.clone());
+        helpers.dynamic.set_config(config);
+    }
+
+    // Helper files and plugin libraries: the explicit ones, then the plugin directories
+    let mut dir_files = Vec::new();
+    let plugin_dirs = (!settings.plugin_dir.is_empty())
+        .then(|| PathBuf::from(&settings.plugin_dir))
+        .into_iter()
+        .chain(args.plugin_dirs.iter().cloned());
+    for dir in plugin_dirs {
+        dir_files.extend(plugin_dir_files(&dir)?);
+    }
+
+    for kind in HelperKind::ALL {
+        let label = kind.label();
+        let dir_files = dir_files
+            .iter()
+            .filter(|path| HelperKind::of_file(path) == kind);
+        for path in kind.explicit_files(args).iter().chain(dir_files) {
+            debug_log!("🔌 Loading {} from: {}", label, path.display());
+            match helpers.load(kind, path, hb) {
+                Ok(names) => {
+                    debug_log!("✅ Loaded {} {}: {:?}", names.len(), label, names);
+                    known_helpers.extend(names);
+                }
+                Err(e) if args.require_helpers => {
+                    return Err(e.context(format!("Failed to load {}: {}", label, path.display())));
+                }
+                Err(e) => {
+                    error_log!("Failed to load {}: {}: {:#}", label, path.display(), e);
+                    // Continue without these helpers rather than failing entirely
+                    problems.push(format!("{} did not load ({})", label, path.display()));
+                }
+            }
+        }
+
+        if let Err(e) = helpers.register(kind, hb) {
+            if args.require_helpers {
+                return Err(e.context(format!("Failed to register {}", label)));
+            }
+            error_log!("Failed to register {}: {}", label, e);
+            // Continue with the helpers registered so far
+            problems.push(format!("{} were not registered", label));
+        }
+    }
+
+    // JS helpers reading dataRoot get it through options.context
+    if settings.data_root == DataRootMode::Auto && helpers.dynamic.uses_data_root() {
+        debug_log!("🌳 JS helpers mention dataRoot, injecting it");
+        settings.data_root = DataRootMode::Always;
+    }
+
+    Ok(helpers)
+}
+
+/// Print the helpers a run with these arguments would have, one per line. A helper
+/// registered again under the same name is listed once, as the one that wins.
+fn list_helpers(args: &Args) -> Result<()> {
+    let mut settings = load_settings(args)?;
+    let locale = Arc::new(i18n::Locale::new(&settings.locale, None)?);
+    let (mut hb, mut known_helpers) = new_handlebars(&settings, &locale, args.strict);
+    let mut problems = Vec::new();
+    let helpers = load_helpers(
+        args,
+        &mut settings,
+        &mut hb,
+        &mut known_helpers,
+        &mut problems,
+    )?;
+
+    let mut list: Vec<HelperInfo> = Vec::new();
+    for info in builtin_helper_info(&locale)
+        .into_iter()
+        .chain(helpers.describe())
+    {
+        match list.iter().position(|listed| listed.name == info.name) {
+            Some(index) => {
+                let replaced = list.remove(index);
+                list.push(HelperInfo {
+                    source: format!("{}, replacing {}", info.source, replaced.source),
+                    ..info
+                });
+            }
+            None => list.push(info),
+        }
+    }
+
+    let name_width = list.iter().map(|info| info.name.chars().count()).max();
+    let source_width = list.iter().map(|info| info.source.chars().count()).max();
+    let mut out = std::io::stdout().lock();
+    for info in &list {
+        let details: Vec<&str> = [info.signature.as_deref(), info.description.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        let line = format!(
+            "{:<name_width$}  {:<source_width$}  {}",
+            info.name,
+            info.source,
+            details.join("  "),
+            name_width = name_width.unwrap_or(0),
+            source_width = source_width.unwrap_or(0),
+        );
+        match writeln!(out, "{}", line.trim_end()) {
+            // Piped into a pager or `head` that has seen enough
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => break,
+            written => written?,
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(
+            RunError::Partial(format!("Listed with problems: {}", problems.join("; "))).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Settings for a run: project settings, --settings and --set, then the options that
+/// override them
+fn load_settings(args: &Args) -> Result<JsonImportSettings> {
+    // Load settings (file or defaults)
+    let mut configured = serde_yaml::Mapping::new();
+    if let Some(path) = discovered_settings(args) {
+        debug_log!("⚙️ Project settings: {}", path.display());
+        config::merge(&mut configured, config::read(&path)?);
+    }
+    if let Some(path) = &args.settings {
+        config::merge(&mut configured, config::read(path)?);
+    }
+    apply_setting_overrides(&mut configured, &args.set)?;
+    let mut settings: JsonImportSettings =
+        serde_yaml::from_value(serde_yaml::Value::Mapping(configured.clone()))
+            .context("Invalid settings")?;
+    let configured = match serde_json::to_value(&configured)? {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    if let Some(site) = args.site {
+        site.apply(&mut settings, &configured);
+    }
+    if args.single_context {
+        settings.single_context = true;
+    }
+    if args.obsidian {
+        settings.obsidian = true;
+    }
+    if let Some(spec) = &args.aggregate {
+        settings.aggregate = spec.clone();
+    }
+    if let Some(spec) = &args.sort_by {
+        settings.sort_by = spec.clone();
+    }
+    if let Some(flavor) = args.flavor {
+        settings.flavor = flavor;
+    }
+    if args.auto_escape {
+        settings.auto_escape = true;
+    }
+    if let Some(locale) = &args.locale {
+        settings.locale = locale.clone();
+    }
+    settings.prepare()?;
+    Ok(settings)
+}
+
+/// Run one conversion with the parsed arguments
+fn convert(args: &Args) -> Result<()> {
+    begin_run();
+    let mut settings = load_settings(args)?;
+
+    // Expand globs and validate input data sources
+    let artifacts = template_specs(args)?;
+    let (template_file, data_paths) = data_sources(args, &artifacts)?;
+
+    // Format override (URLs detect from extension or Content-Type when absent)
+    let format_override = match args.format {
+        Some(format) => Some(format),
+        None if args.json5 => Some(DataFormat::Json5),
+        None => None,
+    };
+    let stream_path = stream_source(args, &data_paths, format_override, &settings)?;
+
+    // Parse input data
+    let input_opts = input_options(args, &settings);
+    let mut loaded = load_sources(&data_paths, format_override, &input_opts, args.stream)?;
+    let sources: Vec<SourceInfo> = data_paths.iter().map(|p| SourceInfo::new(p)).collect();
+
+    // Several sources are merged into one item array (top_field already applied)
+    let mut item_sources = Vec::new();
+    let data = if loaded.len() == 1 {
+        loaded.pop().unwrap()
+    } else {
+        let (merged, origins) = merge_sources(loaded, &settings)?;
+        settings.top_field.clear();
+        item_sources = origins;
+        merged
+    };
+    if let Value::Array(rows) = &data {
+        debug_log!("✅ Parsed {} items", rows.len());
+    }
+
+    // Pre-filter the data with a jq expression
+    let data = match &args.filter {
+        Some(expr) => {
+            let filtered = filter::apply_filter(data, expr)?;
+            if let Value::Array(rows) = &filtered {
+                debug_log!("🔎 Filter kept {} items", rows.len());
+            }
+            filtered
+        }
+        None => data,
+    };
+
+    // Load template
+    let (template, template_label) = match (&args.template_str, template_file) {
+        (Some(src), _) => (src.clone(), "--template-str".to_string()),
+        (None, Some(path)) => (
+            fs::read_to_string(path).context("Read template")?,
+            path.display().to_string(),
+        ),
+        (None, None) => unreachable!("a template file or --template-str is required"),
+    };
+
+    // Locale for the translation, date and number helpers
+    let translations = match &args.translations {
+        Some(path) => Some(
+            input::load_data(path, DataFormat::from_path(path), &input_opts)
+                .with_context(|| format!("Failed to load translations: {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let locale = Arc::new(i18n::Locale::new(&settings.locale, translations.as_ref())?);
+    if !locale.tag.is_empty() {
+        debug_log!("🌐 Locale: {}", locale.tag);
+    }
+
+    // Initialize Handlebars with built-in helpers
+    let (mut hb, mut known_helpers) = new_handlebars(&settings, &locale, args.strict);
+
+    // Partials, available to the main, index and layout templates
+    for dir in &args.partials {
+        for (name, path) in partial_files(dir)? {
+            let src = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read partial: {}", path.display()))?;
+            hb.register_partial(&name, src)
+                .with_context(|| format!("Invalid partial: {}", path.display()))?;
+            debug_log!("🧩 Partial {{{{> {}}}}}: {}", name, path.display());
+        }
+    }
+
+    // Problems the run continues with; they make it exit with code 2
+    let mut problems = Vec::new();
+
+    // Load dynamic helpers if requested
+    let _helpers = load_helpers(
+        args,
+        &mut settings,
+        &mut hb,
+        &mut known_helpers,
+        &mut problems,
+    )?;
+
+    let mut run = run_options(args, &artifacts)?;
+    run.template = template;
+    if preflight(
+        args,
+        &hb,
+        &known_helpers,
+        &data,
+        &settings,
+        &run,
+        &template_label,
+    )? {
+        return Ok(());
+    }
+
+    run.source_label = match sources.as_slice() {
+        [single] => single.filename.clone(),
+        many => format!("{} data files", many.len()),
+    };
+    run.sources = sources;
+    run.item_sources = item_sources;
+    run.stream = stream_path;
+    run.stream_encoding = args.encoding.clone();
+    load_context_values(args, &input_opts, &mut run)?;
+
+    write_notes(args, &mut hb, data, &settings, &run, problems)
+}
+
+/// Load every data source (a placeholder for the streamed one)
+fn load_sources(
+    data_paths: &[PathBuf],
+    format_override: Option<DataFormat>,
+    input_opts: &InputOptions,
+    streaming: bool,
+) -> Result<Vec<Value>> {
+    let mut loaded = Vec::new();
+    for data_path in data_paths {
+        let source = data_path.to_string_lossy();
+        if streaming {
+            debug_log!("🌊 Streaming: {}", source);
+            loaded.push(Value::Null);
+            continue;
+        }
+        debug_log!("📄 Reading: {}", source);
+        let data = if input::is_url(&source) {
+            input::load_url(&source, format_override, input_opts)?
+        } else {
+            // Detect format by extension unless overridden
+            let format = format_override.unwrap_or_else(|| DataFormat::from_path(data_path));
+            debug_log!("📋 Format detected: {:?}", format);
+            input::load_data(data_path, format, input_opts)
+                .with_context(|| format!("Failed to load {}", data_path.display()))?
+        };
+        loaded.push(data);
+    }
+    Ok(loaded)
+}
+
+/// The --template NAME=FILE specs, checked for duplicate names
+fn template_specs(args: &Args) -> Result<Vec<(String, PathBuf)>> {
+    let artifacts = args
+        .templates
+        .iter()
+        .map(|spec| parse_template_spec(spec))
+        .collect::<Result<Vec<_>>>()?;
+    let mut template_names = HashSet::new();
+    for (name, _) in &artifacts {
+        if !template_names.insert(name) {
+            anyhow::bail!("Duplicate --template name '{}'", name);
+        }
+    }
+    Ok(artifacts)
+}
+
+/// The template file among the FILE arguments and the data sources, globs expanded;
+/// named templates (--template) leave every FILE to data
+fn data_sources<'a>(
+    args: &'a Args,
+    artifacts: &'a [(String, PathBuf)],
+) -> Result<(Option<&'a PathBuf>, Vec<PathBuf>)> {
+    let (template_file, data_files) = match artifacts.first() {
+        Some((_, path)) => (Some(path), args.files.as_slice()),
+        None if args.template_str.is_some() => (None, args.files.as_slice()),
+        None => {
+            let (template_file, data_files) = args.files.split_last().expect("FILE is required");
+            (Some(template_file), data_files)
+        }
+    };
+    let mut data_paths = input::expand_sources(data_files)?;
+    for pattern in &args.globs {
+        data_paths.extend(input::glob_sources(pattern)?);
+    }
+    if data_paths.is_empty() {
+        anyhow::bail!("No data file given: pass DATA_FILE before TEMPLATE_FILE or use --glob");
+    }
+    for data_path in &data_paths {
+        if !input::is_url(&data_path.to_string_lossy()) && !data_path.exists() {
+            anyhow::bail!("Data file not found: {}", data_path.display());
+        }
+    }
+
+    Ok((template_file, data_paths))
+}
+
+/// The JSON file to stream item by item with --stream, after checking that nothing in
+/// the run needs every item in memory
+fn stream_source(
+    args: &Args,
+    data_paths: &[PathBuf],
+    format_override: Option<DataFormat>,
+    settings: &JsonImportSettings,
+) -> Result<Option<PathBuf>> {
+    if !args.stream {
+        return Ok(None);
+    }
+    let [path] = data_paths else {
+        anyhow::bail!("--stream needs exactly one data file");
+    };
+    let format = format_override.unwrap_or_else(|| DataFormat::from_path(path));
+    if input::is_url(&path.to_string_lossy()) || format != DataFormat::Json {
+        anyhow::bail!("--stream only supports local JSON files");
+    }
+    let conflicts = [
+        (!settings.top_field.is_empty(), "top_field"),
+        (args.filter.is_some(), "--filter"),
+        (settings.single_context, "single_context"),
+        (!settings.sort_by.is_empty(), "sort_by"),
+        (!settings.group_by.is_empty(), "group_by"),
+        (!settings.aggregate.is_empty(), "aggregate"),
+        (
+            !settings.dedupe_on.is_empty() && settings.dedupe_keep == DedupeKeep::Last,
+            "dedupe_keep \"last\"",
+        ),
+        (args.chunk.is_some(), "--chunk"),
+        (args.check, "--check"),
+        (args.lint_template, "--lint-template"),
+    ];
+    if let Some((_, name)) = conflicts.iter().find(|(on, _)| *on) {
+        anyhow::bail!(
+            "--stream cannot be combined with {} (it needs every item in memory)",
+            name
+        );
+    }
+    Ok(Some(path.clone()))
+}
+
+/// Input options from the command line and the settings
+fn input_options(args: &Args, settings: &JsonImportSettings) -> InputOptions {
+    InputOptions {
+        sheet: args.sheet.clone(),
+        query: args.query.clone(),
+        columns: args.columns.clone(),
+        headers: args.headers.clone(),
+        cache_dir: args
+            .cache
+            .clone()
+            .map(|dir| dir.unwrap_or_else(|| ".json2md-cache".into())),
+        paginate: args.paginate.clone().map(|field| field.unwrap_or_default()),
+        csv_types: args.csv_types,
+        csv_column_types: settings.csv_column_types.clone(),
+        csv_delimiter: args.delimiter,
+        csv_quote: args.quote,
+        csv_escape: args.escape,
+        csv_no_headers: args.no_headers,
+        csv_column_names: args.column_names.clone(),
+        fixed_width_columns: settings.fixed_width_columns.clone(),
+        csv_nested: args.csv_nested || settings.csv_nested,
+        encoding: args.encoding.clone(),
+    }
+}
+
+/// Lint (--lint-template) and check (--check) the templates; true when the run stops
+/// after checking
+fn preflight(
+    args: &Args,
+    hb: &Handlebars<'static>,
+    known_helpers: &HashSet<String>,
+    data: &Value,
+    settings: &JsonImportSettings,
+    run: &RunOptions,
+    template_label: &str,
+) -> Result<bool> {
+    // Preflight template lint
+    if args.lint_template {
+        let target = resolve_target(data, settings)?;
+        let items = collect_items(&target, settings.force_array);
+        let findings =
+            lint::lint_template(&run.template, known_helpers, RESERVED_CONTEXT_KEYS, &items);
+        for finding in &findings {
+            warn_log!("{}:{}", template_label, finding);
+        }
+        if findings.is_empty() {
+            debug_log!("✅ Template lint passed");
+        } else if args.strict {
+            return Err(RunError::Invalid(format!(
+                "Template lint failed with {} finding(s)",
+                findings.len()
+            ))
+            .into());
+        }
+    }
+
+    // Validate the templates and stop
+    if args.check {
+        let target = resolve_target(data, settings)?;
+        let items = collect_items(&target, settings.force_array);
+        let partials: HashSet<String> = hb.get_templates().keys().cloned().collect();
+        // Item templates see the item fields; the index and layout get their own context
+        let mut templates = vec![(template_label.to_string(), run.template.clone(), true)];
+        for (name, src) in run.artifacts.iter().skip(1) {
+            templates.push((name.clone(), src.clone(), true));
+        }
+        for path in [&args.index_template, &args.html_layout]
+            .into_iter()
+            .flatten()
+        {
+            let src = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template: {}", path.display()))?;
+            templates.push((path.display().to_string(), src, false));
+        }
+        let mut count = 0;
+        for (label, src, item_context) in &templates {
+            let findings = lint::check_template(
+                src,
+                known_helpers,
+                &partials,
+                RESERVED_CONTEXT_KEYS,
+                item_context.then_some(items.as_slice()),
+            );
+            for finding in &findings {
+                warn_log!("{}:{}", label, finding);
+            }
+            count += findings.len();
+        }
+        if count > 0 {
+            return Err(RunError::Invalid(format!(
+                "Template check failed with {} finding(s)",
+                count
+            ))
+            .into());
+        }
+        success_log!("Template check passed ({} template(s))", templates.len());
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Run options from the command line: item selection, output policies and the extra
+/// templates; the data-dependent parts are filled in by the caller
+fn run_options(args: &Args, artifacts: &[(String, PathBuf)]) -> Result<RunOptions> {
+    let mut run = RunOptions {
+        skip: args.skip,
+        limit: args.limit,
+        chunk: args.chunk.map(|n| n as usize),
+        incremental: args.incremental,
+        dry_run: args.dry_run,
+        on_error: if args.keep_going {
+            OnError::Skip
+        } else {
+            args.on_error
+        },
+        errors_report: args.errors_report.clone(),
+        report: args.report.clone(),
+        if_exists: args.if_exists,
+        merge_frontmatter: args.merge_frontmatter,
+        index_name: args.index_name.clone(),
+        nav: args.nav,
+        nav_file: args.nav_file.clone(),
+        archive: args.archive.clone(),
+        render_html: args.render_html || args.html_layout.is_some(),
+        ..Default::default()
+    };
+    if artifacts.len() > 1 {
+        for (name, path) in artifacts {
+            let src = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template: {}", path.display()))?;
+            run.artifacts.push((name.clone(), src));
+        }
+    }
+    if let Some(layout_path) = &args.html_layout {
+        run.html_layout =
+            Some(fs::read_to_string(layout_path).with_context(|| {
+                format!("Failed to read HTML layout: {}", layout_path.display())
+            })?);
+    }
+    if let Some(index_path) = &args.index_template {
+        run.index_template =
+            Some(fs::read_to_string(index_path).with_context(|| {
+                format!("Failed to read index template: {}", index_path.display())
+            })?);
+    }
+    Ok(run)
+}
+
+/// Values injected into every context: --vars, --context, --param, --env-prefix and
+/// the --lookup datasets
+fn load_context_values(args: &Args, input_opts: &InputOptions, run: &mut RunOptions) -> Result<()> {
+    if let Some(path) = &args.vars {
+        let vars = input::load_data(path, DataFormat::from_path(path), input_opts)
+            .with_context(|| format!("Failed to load vars file: {}", path.display()))?;
+        if !vars.is_object() {
+            anyhow::bail!("Vars file must contain an object: {}", path.display());
+        }
+        run.vars = Some(vars);
+    }
+    if let Some(path) = &args.globals {
+        let globals = input::load_data(path, DataFormat::from_path(path), input_opts)
+            .with_context(|| format!("Failed to load context file: {}", path.display()))?;
+        if !globals.is_object() {
+            anyhow::bail!("Context file must contain an object: {}", path.display());
+        }
+        run.globals = Some(globals);
+    }
+    if !args.params.is_empty() {
+        let mut params = serde_json::Map::new();
+        for spec in &args.params {
+            let (key, value) = spec
+                .split_once('=')
+                .filter(|(key, _)| !key.trim().is_empty())
+                .with_context(|| format!("Invalid --param '{}': expected KEY=VALUE", spec))?;
+            params.insert(key.trim().to_string(), Value::String(value.to_string()));
+        }
+        run.params = Some(Value::Object(params));
+    }
+    if let Some(prefix) = &args.env_prefix {
+        let env: serde_json::Map<String, Value> = std::env::vars()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix(prefix.as_str())?;
+                (!name.is_empty()).then(|| (name.to_string(), Value::String(value)))
+            })
+            .collect();
+        debug_log!("🌱 {} environment variables exposed as env", env.len());
+        run.env = Some(Value::Object(env));
+    }
+
+    // Load secondary lookup datasets
+    if !args.lookups.is_empty() {
+        let mut lookup = serde_json::Map::new();
+        for spec in &args.lookups {
+            let (name, path) = spec
+                .split_once('=')
+                .with_context(|| format!("Invalid --lookup '{}': expected NAME=FILE", spec))?;
+            let path = PathBuf::from(path.trim());
+            let source = path.to_string_lossy();
+            let dataset = if input::is_url(&source) {
+                input::load_url(&source, None, input_opts)?
+            } else {
+                input::load_data(&path, DataFormat::from_path(&path), input_opts)
+                    .with_context(|| format!("Failed to load lookup '{}'", name))?
+            };
+            debug_log!("🔗 Lookup '{}' loaded from {}", name, source);
+            lookup.insert(name.trim().to_string(), dataset);
+        }
+        run.lookup = Some(Value::Object(lookup));
+    }
+    Ok(())
+}
+
+/// Render and write the notes of a prepared run; `problems` the run continued with
+/// make it a partial failure
+fn write_notes(
+    args: &Args,
+    hb: &mut Handlebars<'static>,
+    data: Value,
+    settings: &JsonImportSettings,
+    run: &RunOptions,
+    mut problems: Vec<String>,
+) -> Result<()> {
+    // Determine output strategy
+    let stdout_path = PathBuf::from("-");
+    let output_strategy = determine_output_strategy(
+        args.output.as_ref().or(args.stdout.then_some(&stdout_path)),
+        args.out_dir.as_ref(),
+        args.split.as_ref().map(|opt| opt.as_deref()), // Convert Option<Option<String>> → Option<Option<&str>>
+        &data,
+        settings,
+        !args.dry_run && args.archive.is_none(),
+    )?;
+    // With --archive, files go into the archive, named relative to the output directory
+    let mut sink: Box<dyn OutputSink> = match &args.archive {
+        Some(path) if !args.dry_run => {
+            let base = match &output_strategy {
+                OutputStrategy::MultiFile { directory, .. } => directory.clone(),
+                OutputStrategy::SingleFile(file) => {
+                    file.parent().map(Path::to_path_buf).unwrap_or_default()
+                }
+            };
+            Box::new(ArchiveSink::create(path, &base)?)
+        }
+        _ => Box::new(FileSink),
+    };
+    // Generate notes with the determined strategy
+    generate_notes(
+        hb,
+        data,
+        settings,
+        output_strategy.clone(), // ← Pass the strategy
+        run,
+        sink.as_mut(),
+    )
+    .or_else(|e| match e.downcast::<RunError>() {
+        Ok(RunError::Partial(message)) => {
+            problems.push(message);
+            Ok(())
+        }
+        Ok(other) => Err(other.into()),
+        Err(e) => Err(e),
+    })?;
+
+    // Only print generic "Import Finished" for multi-file mode (single-file already logged)
+    if matches!(output_strategy, OutputStrategy::MultiFile { .. }) {
+        success_log!("Import Finished.");
+    }
+
+    if !problems.is_empty() {
+        return Err(
+            RunError::Partial(format!("Completed with problems: {}", problems.join("; "))).into(),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overridden(overrides: &[&str]) -> Result<serde_yaml::Mapping> {
+        let mut configured: serde_yaml::Mapping =
+            serde_yaml::from_str("folder_name: Notes\nassets:\n  fields: [cover]\n").unwrap();
+        let overrides: Vec<String> = overrides.iter().map(|s| s.to_string()).collect();
+        apply_setting_overrides(&mut configured, &overrides)?;
+        Ok(configured)
+    }
+
+    #[test]
+    fn setting_overrides_keep_text_settings_as_text() {
+        let configured =
+            overridden(&["folder_name=2024", "force_array=false", "sort_by=n:desc"]).unwrap();
+        let expected: serde_yaml::Mapping = serde_yaml::from_str(
+            "folder_name: '2024'\nassets:\n  fields: [cover]\nforce_array: false\nsort_by: n:desc\n",
+        )
+        .unwrap();
+        assert_eq!(configured, expected);
+    }
+
+    #[test]
+    fn setting_overrides_follow_dot_paths_into_nested_settings() {
+        let configured =
+            overridden(&["assets.dir=media", "csv_column_types.price=number"]).unwrap();
+        let assets = &configured["assets"];
+        assert_eq!(assets["dir"], serde_yaml::Value::from("media"));
+        assert_eq!(
+            assets["fields"],
+            serde_yaml::from_str::<serde_yaml::Value>("[cover]").unwrap()
+        );
+        assert_eq!(
+            configured["csv_column_types"]["price"],
+            serde_yaml::Value::from("number")
+        );
+    }
+
+    #[test]
+    fn setting_overrides_reject_unknown_keys_and_missing_values() {
+        assert!(overridden(&["no_such_setting=1"]).is_err());
+        assert!(overridden(&["assets..dir=x"]).is_err());
+        assert!(overridden(&["folder_name"]).is_err());
+    }
+}
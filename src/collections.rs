@@ -0,0 +1,415 @@
+//! Helpers reshaping arrays: sort, filter, group, slice and project them.
+//!
+//! ```text
+//! {{#each (sortBy items "price" "desc")}}…{{/each}}
+//! {{#filterBy items "status" "open"}}{{name}}{{else}}Nothing open{{/filterBy}}
+//! {{#each (groupBy items "category")}}## {{key}} ({{length items}}){{/each}}
+//! {{join (pluck items "name") " · "}}   {{first items 3}}   {{unique tags}}
+//! ```
+//!
+//! Every helper returns a value, so it works inline and inside subexpressions. Used as a
+//! block, an array result is iterated like `#each` (with `@index`, `@first`, `@last` and
+//! `as |item|`), any other result becomes the block's context, and `{{else}}` renders
+//! when the result is empty. Fields are dot paths ("owner.name").
+
+use crate::{logic, path, re_err};
+use handlebars::{
+    BlockContext, BlockParams, Context, Handlebars, Helper, HelperDef, HelperResult, JsonRender,
+    JsonTruthy, Output, RenderContext, RenderError, Renderable, ScopedJson, to_json,
+};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+/// The array argument at `idx` (empty for anything else)
+fn array<'a>(h: &'a Helper<'_>, idx: usize) -> &'a [Value] {
+    match h.param(idx).map(|p| p.value()) {
+        Some(Value::Array(items)) => items,
+        _ => &[],
+    }
+}
+
+/// String argument at `idx`
+fn str_param<'a>(h: &'a Helper<'_>, idx: usize) -> Option<&'a str> {
+    h.param(idx).and_then(|p| p.value().as_str())
+}
+
+/// Field of an item, or the item itself without a field
+fn field(item: &Value, field: Option<&str>) -> Value {
+    match field {
+        Some(field) => path::select(item, field).unwrap_or(Value::Null),
+        None => item.clone(),
+    }
+}
+
+/// Count argument at `idx`
+fn count(h: &Helper<'_>, idx: usize) -> Option<usize> {
+    h.param(idx).and_then(|p| match p.value() {
+        Value::Number(n) => n.as_u64().map(|n| n as usize),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+/// Sort order of two values: numbers (and numeric strings) numerically, other values
+/// as text, nulls last
+fn order(a: &Value, b: &Value) -> Ordering {
+    let num = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    match (a.is_null(), b.is_null()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        _ => {}
+    }
+    match (num(a), num(b)) {
+        (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.render().cmp(&b.render()),
+    }
+}
+
+/// {{sortBy array [field] ["desc"]}} (or desc=true); the sort is stable and nulls
+/// stay last either way
+fn sort_by(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let mut key = str_param(h, 1);
+    let mut desc = h
+        .hash_get("desc")
+        .is_some_and(|p| p.value().is_truthy(false));
+    if let Some(direction) = str_param(h, 2).or(key.filter(|k| matches!(*k, "asc" | "desc"))) {
+        desc |= direction == "desc";
+        if key == Some(direction) {
+            key = None;
+        }
+    }
+    let mut keyed: Vec<(Value, &Value)> = array(h, 0)
+        .iter()
+        .map(|item| (field(item, key), item))
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| match (a.is_null(), b.is_null(), desc) {
+        (false, false, true) => order(b, a),
+        _ => order(a, b),
+    });
+    Ok(Value::Array(
+        keyed.into_iter().map(|(_, item)| item.clone()).collect(),
+    ))
+}
+
+/// {{filterBy array field [value]}}: items whose field equals the value (numbers match
+/// numeric strings), or is truthy when no value is given
+fn filter_by(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let key = str_param(h, 1).ok_or_else(|| re_err("filterBy: expected a field name"))?;
+    let wanted = h.param(2).map(|p| p.value());
+    Ok(Value::Array(
+        array(h, 0)
+            .iter()
+            .filter(|item| {
+                let value = field(item, Some(key));
+                match wanted {
+                    Some(wanted) => logic::loose_eq(&value, wanted),
+                    None => value.is_truthy(false),
+                }
+            })
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// {{groupBy array field}}: `[{key, items}]` in order of first appearance
+fn group_by(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let key = str_param(h, 1).ok_or_else(|| re_err("groupBy: expected a field name"))?;
+    let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+    for item in array(h, 0) {
+        let value = field(item, Some(key));
+        match groups.iter_mut().find(|(k, _)| *k == value) {
+            Some((_, items)) => items.push(item.clone()),
+            None => groups.push((value, vec![item.clone()])),
+        }
+    }
+    Ok(Value::Array(
+        groups
+            .into_iter()
+            .map(|(key, items)| {
+                let mut group = Map::new();
+                group.insert("key".to_string(), key);
+                group.insert("items".to_string(), Value::Array(items));
+                Value::Object(group)
+            })
+            .collect(),
+    ))
+}
+
+/// {{first array [n]}}: the first item, or an array of the first n
+fn first(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let items = array(h, 0);
+    Ok(match count(h, 1) {
+        Some(n) => Value::Array(items.iter().take(n).cloned().collect()),
+        None => items.first().cloned().unwrap_or(Value::Null),
+    })
+}
+
+/// {{last array [n]}}: the last item, or an array of the last n
+fn last(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let items = array(h, 0);
+    Ok(match count(h, 1) {
+        Some(n) => Value::Array(items[items.len().saturating_sub(n)..].to_vec()),
+        None => items.last().cloned().unwrap_or(Value::Null),
+    })
+}
+
+/// {{pluck array field}}: the field of every item (missing fields are skipped)
+fn pluck(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let key = str_param(h, 1).ok_or_else(|| re_err("pluck: expected a field name"))?;
+    Ok(Value::Array(
+        array(h, 0)
+            .iter()
+            .filter_map(|item| path::select(item, key))
+            .collect(),
+    ))
+}
+
+/// {{join array [separator]}} (", " by default); nulls are skipped
+fn join(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let separator = str_param(h, 1).unwrap_or(", ");
+    Ok(Value::String(
+        array(h, 0)
+            .iter()
+            .filter(|v| !v.is_null())
+            .map(JsonRender::render)
+            .collect::<Vec<_>>()
+            .join(separator),
+    ))
+}
+
+/// {{unique array [field]}}: items without repeats (by a field when given), first kept
+fn unique(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let key = str_param(h, 1);
+    let mut seen: Vec<Value> = Vec::new();
+    let mut items = Vec::new();
+    for item in array(h, 0) {
+        let value = field(item, key);
+        if !seen.contains(&value) {
+            seen.push(value);
+            items.push(item.clone());
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+/// {{length value}}: items of an array, characters of a string, keys of an object
+fn length(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let len = match h.param(0).map(|p| p.value()) {
+        Some(Value::Array(items)) => items.len(),
+        Some(Value::String(text)) => text.chars().count(),
+        Some(Value::Object(map)) => map.len(),
+        _ => 0,
+    };
+    Ok(Value::from(len))
+}
+
+/// Render the block once per item, like `#each`
+fn render_items<'reg: 'rc, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+    items: &[Value],
+) -> HelperResult {
+    let Some(template) = h.template() else {
+        return Ok(());
+    };
+    for (i, item) in items.iter().enumerate() {
+        let mut block = BlockContext::new();
+        block.set_base_value(item.clone());
+        block.set_local_var("index", to_json(i));
+        block.set_local_var("first", to_json(i == 0));
+        block.set_local_var("last", to_json(i + 1 == items.len()));
+        if let Some(name) = h.block_param() {
+            let mut params = BlockParams::new();
+            params.add_value(name, item.clone())?;
+            block.set_block_params(params);
+        }
+        rc.push_block(block);
+        template.render(r, ctx, rc, out)?;
+        rc.pop_block();
+    }
+    Ok(())
+}
+
+/// Collection helper computing a value from its arguments
+struct CollectionHelper(fn(&Helper<'_>) -> Result<Value, RenderError>);
+
+impl HelperDef for CollectionHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived((self.0)(h)?))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = (self.0)(h)?;
+        if !h.is_block() {
+            let text = value.render();
+            let text = if rc.is_disable_escape() {
+                text
+            } else {
+                r.get_escape_fn()(&text)
+            };
+            return Ok(out.write(&text)?);
+        }
+        if !value.is_truthy(false) {
+            return match h.inverse() {
+                Some(inverse) => inverse.render(r, ctx, rc, out),
+                None => Ok(()),
+            };
+        }
+        match value {
+            Value::Array(items) => render_items(h, r, ctx, rc, out, &items),
+            other => render_items(h, r, ctx, rc, out, std::slice::from_ref(&other)),
+        }
+    }
+}
+
+/// The collection helpers with their names
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![
+        ("sortBy", Box::new(CollectionHelper(sort_by))),
+        ("filterBy", Box::new(CollectionHelper(filter_by))),
+        ("groupBy", Box::new(CollectionHelper(group_by))),
+        ("first", Box::new(CollectionHelper(first))),
+        ("last", Box::new(CollectionHelper(last))),
+        ("pluck", Box::new(CollectionHelper(pluck))),
+        ("join", Box::new(CollectionHelper(join))),
+        ("unique", Box::new(CollectionHelper(unique))),
+        ("length", Box::new(CollectionHelper(length))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(handlebars::no_escape);
+        for (name, helper) in helpers() {
+            hb.register_helper(name, helper);
+        }
+        let data = json!({
+            "items": [
+                {"name": "b", "price": "10", "kind": "tool", "open": true},
+                {"name": "a", "price": 9, "kind": "book"},
+                {"name": "c", "kind": "tool", "open": false},
+                {"name": "d", "price": 2.5, "kind": "book", "open": true}
+            ],
+            "tags": ["x", "y", "x", null],
+            "text": "héllo"
+        });
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn sort_by_orders_numbers_numerically_and_keeps_nulls_last() {
+        let names = |spec: &str| {
+            render(&format!(
+                "{{{{join (pluck (sortBy items {}) \"name\") \"\"}}}}",
+                spec
+            ))
+            .unwrap()
+        };
+        assert_eq!(names(r#""price""#), "dabc");
+        assert_eq!(names(r#""price" "desc""#), "badc");
+        assert_eq!(names(r#""price" desc=true"#), "badc");
+        assert_eq!(names(r#""name""#), "abcd");
+        assert_eq!(
+            render(r#"{{join (sortBy tags "desc") ""}}"#).unwrap(),
+            "yxx"
+        );
+        assert_eq!(render("{{length (sortBy text)}}").unwrap(), "0");
+    }
+
+    #[test]
+    fn filter_group_and_slice() {
+        assert_eq!(
+            render(r#"{{#filterBy items "kind" "book"}}{{@index}}{{name}}{{/filterBy}}"#).unwrap(),
+            "0a1d"
+        );
+        assert_eq!(
+            render(
+                r#"{{#filterBy items "open" as |it|}}{{it.name}}{{#if @last}}.{{/if}}{{/filterBy}}"#
+            )
+            .unwrap(),
+            "bd."
+        );
+        assert_eq!(
+            render(r#"{{#filterBy items "price" 9}}{{name}}{{/filterBy}}"#).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            render(r#"{{#filterBy items "kind" "toy"}}x{{else}}none{{/filterBy}}"#).unwrap(),
+            "none"
+        );
+        assert_eq!(
+            render(r#"{{#each (groupBy items "kind")}}{{key}}={{length items}};{{/each}}"#)
+                .unwrap(),
+            "tool=2;book=2;"
+        );
+        let template = concat!(
+            r#"{{#first items}}{{name}}{{/first}}|{{join (pluck (first items 2) "name")}}|"#,
+            r#"{{join (pluck (last items 9) "name") ""}}|{{#last items}}{{name}}{{/last}}"#
+        );
+        assert_eq!(render(template).unwrap(), "b|b, a|bacd|d");
+        assert_eq!(
+            render("{{first items 0}}|{{first missing}}|{{last tags -1}}").unwrap(),
+            "[]||"
+        );
+    }
+
+    #[test]
+    fn join_unique_and_length() {
+        assert_eq!(
+            render(r#"{{join tags}}|{{join (unique tags) "+"}}"#).unwrap(),
+            "x, y, x|x+y"
+        );
+        assert_eq!(render(r#"{{length (unique items "kind")}}"#).unwrap(), "2");
+        assert_eq!(
+            render("{{length items}} {{length text}} {{length items.[0]}} {{length missing}}")
+                .unwrap(),
+            "4 5 4 0"
+        );
+        assert_eq!(
+            render(r#"{{join (pluck items "price") "/"}}"#).unwrap(),
+            "10/9/2.5"
+        );
+    }
+
+    #[test]
+    fn field_helpers_need_a_field_name() {
+        for template in [
+            "{{filterBy items}}",
+            "{{groupBy items 3}}",
+            "{{pluck items}}",
+        ] {
+            assert!(
+                render(template)
+                    .unwrap_err()
+                    .to_string()
+                    .contains("expected a field name")
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Settings files: JSON, TOML or YAML (by extension), layered from a discovered project
+//! file up to the command line.
+//!
+//! Layers, each overriding the one before (objects are merged key by key):
+//! 1. `json2md.toml` in the current directory or its nearest ancestor that has one
+//!    (skipped with --no-config)
+//! 2. the file given with --settings
+//! 3. --set overrides and the CLI flags mirrored into the settings
+//!
+//! Layers are held as YAML mappings, which keep the key order of the files (frontmatter
+//! keys are written in the order they were configured).
+
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name looked up by discovery
+pub const PROJECT_FILE: &str = "json2md.toml";
+
+/// Nearest `json2md.toml` from the current directory upwards
+pub fn discover() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Read a settings file into a mapping: .toml and .yaml/.yml by extension, else JSON
+pub fn read(path: &Path) -> Result<Mapping> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read settings: {}", path.display()))?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let value: Value = match ext.as_deref() {
+        Some("toml") => toml::from_str(&text).map_err(anyhow::Error::from),
+        Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(anyhow::Error::from),
+        _ => serde_json::from_str(&text).map_err(anyhow::Error::from),
+    }
+    .with_context(|| format!("Invalid settings file: {}", path.display()))?;
+    match value {
+        Value::Mapping(map) => Ok(map),
+        // An empty YAML file is null
+        Value::Null => Ok(Mapping::new()),
+        _ => anyhow::bail!("Settings must be an object: {}", path.display()),
+    }
+}
+
+/// Merge `over` into `base`: nested objects key by key, everything else replaced
+pub fn merge(base: &mut Mapping, over: Mapping) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(inner)), Value::Mapping(value)) => merge(inner, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,1199 @@
+//! `Converter`: the conversion as a library call, without the command line.
+//!
+//! ```
+//! use json2md::{Converter, Destination, MemorySink};
+//! use serde_json::json;
+//!
+//! let mut notes = MemorySink::default();
+//! Converter::new()
+//!     .data(json!([
+//!         {"name": "Dune", "summary": "Spice."},
+//!         {"name": "Emma", "summary": "Matchmaking."}
+//!     ]))
+//!     .template("# {{name}}\n\n{{summary}}\n")
+//!     .output(Destination::Directory("notes".into()))
+//!     .run_into(&mut notes)?;
+//! assert_eq!(notes.text("notes/Dune.md"), Some("# Dune\n\nSpice.\n"));
+//! assert_eq!(notes.files.len(), 2);
+//! # anyhow::Ok(())
+//! ```
+//!
+//! `.input("books.json")` reads the data from a file instead, and `run()` writes the
+//! notes to disk.
+//!
+//! Settings are those of a settings file ([`JsonImportSettings`], e.g. deserialized
+//! from one); data comes from files in any format the CLI reads, or is passed as a
+//! value. All built-in helpers are registered; an application adds its own in process
+//! with `register_helper` or `register_helper_set`, no plugin library needed.
+//!
+//! The stages `generate_notes` runs items through live here as well: choosing and
+//! ordering the items, paging and grouping them, aggregates and the closing report.
+
+use crate::input::{self, DataFormat, InputOptions};
+use crate::{
+    DataRootMode, DedupeKeep, FileSink, ItemFailure, JsonImportSettings, OutputSink,
+    OutputStrategy, RunError, RunOptions, SourceInfo, StdoutSink, WriteOutcome, WriteStats,
+    begin_run, collect_items, determine_output_strategy, generate_notes, i18n, is_stdout, logging,
+    merge_sources, nav, new_handlebars, objfield, parse_date, uses_data_root_value, write_output,
+};
+use anyhow::{Context, Result};
+use handlebars::{Context as HbContext, Handlebars, HelperDef};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Where the rendered output goes
+#[derive(Clone, Debug)]
+pub enum Destination {
+    /// Every item into one file ("-" for stdout)
+    File(PathBuf),
+    /// One file per item, named by the settings (`json_name`, prefix and suffix)
+    Directory(PathBuf),
+}
+
+/// Template given as source or as a file to read
+enum TemplateSource {
+    Inline(String),
+    File(PathBuf),
+}
+
+/// Builder for a conversion: data, template, settings, output and extra helpers
+#[derive(Default)]
+pub struct Converter {
+    inputs: Vec<PathBuf>,
+    data: Option<Value>,
+    template: Option<TemplateSource>,
+    settings: JsonImportSettings,
+    output: Option<Destination>,
+    strict: bool,
+    partials: Vec<(String, String)>,
+    helpers: Vec<(String, Box<dyn HelperDef + Send + Sync>)>,
+}
+
+impl Converter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Data file to read, its format taken from the extension; several files are merged
+    /// into one item list
+    pub fn input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.inputs.push(path.into());
+        self
+    }
+
+    /// Data to convert instead of input files
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Handlebars template source
+    pub fn template(mut self, src: impl Into<String>) -> Self {
+        self.template = Some(TemplateSource::Inline(src.into()));
+        self
+    }
+
+    /// Handlebars template read from a file when the conversion runs
+    pub fn template_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.template = Some(TemplateSource::File(path.into()));
+        self
+    }
+
+    pub fn settings(mut self, settings: JsonImportSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Output file or directory; without one it is chosen like the CLI does without -o
+    /// (one file for a single item, else a directory per `folder_name`)
+    pub fn output(mut self, output: Destination) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    /// Fail an item that references a missing field (like --strict)
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Partial template, usable as {{> name}}
+    pub fn partial(mut self, name: impl Into<String>, src: impl Into<String>) -> Self {
+        self.partials.push((name.into(), src.into()));
+        self
+    }
+
+    /// Helper next to the built-in ones (a helper of the same name replaces it); any
+    /// `fn(&Helper, &Handlebars, &Context, &mut RenderContext, &mut dyn Output) -> HelperResult`
+    /// or `HelperDef` implementation
+    pub fn register_helper(
+        mut self,
+        name: impl Into<String>,
+        helper: impl HelperDef + Send + Sync + 'static,
+    ) -> Self {
+        self.helpers.push((name.into(), Box::new(helper)));
+        self
+    }
+
+    /// Several helpers at once, as (name, helper) pairs; the shape the built-in helper
+    /// modules use, so an application can keep its own helpers in a `helpers()` list
+    pub fn register_helper_set<N: Into<String>>(
+        mut self,
+        helpers: impl IntoIterator<Item = (N, Box<dyn HelperDef + Send + Sync>)>,
+    ) -> Self {
+        self.helpers.extend(
+            helpers
+                .into_iter()
+                .map(|(name, helper)| (name.into(), helper)),
+        );
+        self
+    }
+
+    /// Load the data, render every item and write the output files
+    pub fn run(self) -> Result<()> {
+        self.run_into(&mut FileSink)
+    }
+
+    /// Like `run`, with the output files going to a sink (memory, an archive, a callback)
+    /// instead of the filesystem; paths are those the files would have on disk
+    pub fn run_into(self, sink: &mut dyn OutputSink) -> Result<()> {
+        begin_run();
+        let mut settings = self.settings;
+        settings.prepare()?;
+
+        let (data, sources, item_sources) = match self.data {
+            Some(data) => (data, Vec::new(), Vec::new()),
+            None if self.inputs.is_empty() => {
+                anyhow::bail!("No data: add an input file or pass the data")
+            }
+            None => {
+                let opts = InputOptions {
+                    csv_column_types: settings.csv_column_types.clone(),
+                    fixed_width_columns: settings.fixed_width_columns.clone(),
+                    csv_nested: settings.csv_nested,
+                    ..Default::default()
+                };
+                let mut loaded = Vec::with_capacity(self.inputs.len());
+                for path in &self.inputs {
+                    loaded.push(
+                        input::load_data(path, DataFormat::from_path(path), &opts)
+                            .with_context(|| format!("Failed to load {}", path.display()))?,
+                    );
+                }
+                let sources: Vec<SourceInfo> =
+                    self.inputs.iter().map(|p| SourceInfo::new(p)).collect();
+                if loaded.len() == 1 {
+                    (loaded.pop().unwrap(), sources, Vec::new())
+                } else {
+                    let (merged, origins) = merge_sources(loaded, &settings)?;
+                    settings.top_field.clear();
+                    (merged, sources, origins)
+                }
+            }
+        };
+
+        let template = match self.template {
+            Some(TemplateSource::Inline(src)) => src,
+            Some(TemplateSource::File(path)) => fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template: {}", path.display()))?,
+            None => anyhow::bail!("No template given"),
+        };
+
+        let locale = Arc::new(i18n::Locale::new(&settings.locale, None)?);
+        let (mut hb, _) = new_handlebars(&settings, &locale, self.strict);
+        for (name, src) in self.partials {
+            hb.register_partial(&name, src)
+                .with_context(|| format!("Invalid partial: {}", name))?;
+        }
+        for (name, helper) in self.helpers {
+            hb.register_helper(&name, helper);
+        }
+
+        let (file, dir) = match &self.output {
+            Some(Destination::File(path)) => (Some(path), None),
+            Some(Destination::Directory(path)) => (None, Some(path)),
+            None => (None, None),
+        };
+        let output_strategy = determine_output_strategy(file, dir, None, &data, &settings, false)?;
+        let source_label = match sources.as_slice() {
+            [] => "data".to_string(),
+            [single] => single.filename.clone(),
+            many => format!("{} data files", many.len()),
+        };
+        let run = RunOptions {
+            template,
+            source_label,
+            sources,
+            item_sources,
+            ..Default::default()
+        };
+        generate_notes(&mut hb, data, &settings, output_strategy, &run, sink)
+    }
+}
+
+/// How the items of a run become renders
+pub enum RenderMode<'a> {
+    /// Array elements rendered as they are parsed from this file (--stream)
+    Stream(&'a Path),
+    /// One render with the whole dataset as root (single_context)
+    SingleContext,
+    /// One render per page of `size` items, written as "<stem>_page_<n>" (--chunk)
+    Pages { size: usize, stem: &'a str },
+    /// One render per distinct value of the field (group_by)
+    Groups(&'a str),
+    /// One render per item
+    Items,
+}
+
+impl<'a> RenderMode<'a> {
+    /// The mode of a run; `page_stem` is set when the output was paged (`paged_output`)
+    pub fn of(
+        settings: &'a JsonImportSettings,
+        run: &'a RunOptions,
+        page_stem: Option<&'a str>,
+    ) -> Self {
+        match (&run.stream, run.chunk, page_stem) {
+            (Some(path), _, _) => Self::Stream(path),
+            _ if settings.single_context => Self::SingleContext,
+            (None, Some(size), Some(stem)) => Self::Pages { size, stem },
+            _ if !settings.group_by.is_empty() => Self::Groups(&settings.group_by),
+            _ => Self::Items,
+        }
+    }
+}
+
+/// A value to render, with the position of the item it is when it is one
+pub type RenderUnit<'a> = (Option<usize>, &'a Value);
+
+/// Streaming mode: render each array element as soon as it is parsed, applying the
+/// filter rules, dedupe_on and --skip/--limit on the way; `render` gets the position
+/// among the rendered items and the item
+pub fn stream_items(
+    path: &Path,
+    settings: &JsonImportSettings,
+    run: &RunOptions,
+    mut render: impl FnMut(usize, &Value) -> Result<()>,
+) -> Result<()> {
+    let limit = run.limit.unwrap_or(usize::MAX);
+    let mut seen_keys = HashSet::new();
+    let (mut matched, mut rendered) = (0, 0);
+    input::stream_json_array(path, run.stream_encoding.as_deref(), |item| {
+        if !settings.filter.iter().all(|rule| rule.keeps(&item)) {
+            return Ok(true);
+        }
+        if !settings.dedupe_on.is_empty()
+            && let Some(key) = objfield(&item, &settings.dedupe_on, None)
+            && !key.is_null()
+            && !seen_keys.insert(key.to_string())
+        {
+            return Ok(true);
+        }
+        matched += 1;
+        if matched <= run.skip {
+            return Ok(true);
+        }
+        if rendered >= limit {
+            return Ok(false);
+        }
+        render(rendered, &item)?;
+        rendered += 1;
+        Ok(rendered < limit)
+    })?;
+    debug_log!("🌊 Streamed {} items", rendered);
+    Ok(())
+}
+
+/// Chunk mode: one render per page of items (see `paginate_items`)
+pub fn render_pages(
+    values: &[&Value],
+    size: usize,
+    stem: &str,
+    render_all: impl FnOnce(&[RenderUnit]) -> Result<()>,
+    extension: impl Fn(&Value) -> Result<String>,
+) -> Result<()> {
+    let pages = paginate_items(values, size, stem, extension)?;
+    debug_log!("📑 Split {} items into {} pages", values.len(), pages.len());
+    render_all(&pages.iter().map(|page| (None, page)).collect::<Vec<_>>())
+}
+
+/// Group mode: one render per distinct group key (see `group_items`)
+pub fn render_groups(
+    values: &[&Value],
+    field: &str,
+    render_all: impl FnOnce(&[RenderUnit]) -> Result<()>,
+) -> Result<()> {
+    let groups = group_items(values, field);
+    debug_log!(
+        "🗂️ Grouped {} items into {} groups",
+        values.len(),
+        groups.len()
+    );
+    render_all(&groups.iter().map(|group| (None, group)).collect::<Vec<_>>())
+}
+
+/// Plain mode: one render per item, keeping its original position
+pub fn render_items(
+    items: &[(usize, &Value)],
+    render_all: impl FnOnce(&[RenderUnit]) -> Result<()>,
+) -> Result<()> {
+    let units: Vec<RenderUnit> = items
+        .iter()
+        .map(|&(origin, item)| (Some(origin), item))
+        .collect();
+    render_all(&units)
+}
+
+/// Output of a chunked run: pages are written as "<stem>_page_<n>" into the output
+/// directory, or next to the requested single file; unchanged without chunking
+pub fn paged_output(
+    output: OutputStrategy,
+    chunked: bool,
+) -> Result<(OutputStrategy, Option<String>)> {
+    let stem_of = |path: &std::path::Path| {
+        path.file_stem()
+            .map_or_else(|| "output".to_string(), |s| s.to_string_lossy().to_string())
+    };
+    Ok(match output {
+        strategy if !chunked => (strategy, None),
+        OutputStrategy::SingleFile(file) if is_stdout(&file) => {
+            anyhow::bail!("--chunk writes one file per page and cannot write to stdout")
+        }
+        OutputStrategy::SingleFile(file) => {
+            let directory = file
+                .parent()
+                .map_or_else(|| PathBuf::from("."), PathBuf::from);
+            let strategy = OutputStrategy::MultiFile {
+                directory,
+                split_config: None,
+            };
+            (strategy, Some(stem_of(&file)))
+        }
+        OutputStrategy::MultiFile { directory, .. } => {
+            let stem = stem_of(&directory);
+            let strategy = OutputStrategy::MultiFile {
+                directory,
+                split_config: None,
+            };
+            (strategy, Some(stem))
+        }
+    })
+}
+
+/// Whether item contexts get the whole dataset as `dataRoot`: per data_root, or in
+/// auto mode when a template or templated setting reads it as a value
+pub fn wants_data_root(settings: &JsonImportSettings, run: &RunOptions) -> bool {
+    run.stream.is_none()
+        && match settings.data_root {
+            DataRootMode::Always => true,
+            DataRootMode::Never => false,
+            DataRootMode::Auto => {
+                [
+                    &run.template,
+                    &settings.json_name,
+                    &settings.folder_template,
+                    &settings.output_extension,
+                ]
+                .iter()
+                .any(|src| uses_data_root_value(src))
+                    || run
+                        .artifacts
+                        .iter()
+                        .any(|(_, src)| uses_data_root_value(src))
+                    || settings
+                        .frontmatter
+                        .values()
+                        .any(|v| uses_data_root_value(&v.to_string()))
+            }
+        }
+}
+
+/// The items to render with their original position: filtered, deduplicated, sorted,
+/// then cut to --skip/--limit
+pub fn select_items<'a>(
+    target: &'a Value,
+    settings: &JsonImportSettings,
+    run: &RunOptions,
+) -> Result<Vec<(usize, &'a Value)>> {
+    let mut items: Vec<(usize, &Value)> = collect_items(target, settings.force_array)
+        .into_iter()
+        .enumerate()
+        .collect();
+    if !settings.filter.is_empty() {
+        let before = items.len();
+        items.retain(|(_, item)| settings.filter.iter().all(|rule| rule.keeps(item)));
+        debug_log!("🔎 Filter rules kept {}/{} items", items.len(), before);
+    }
+    if !settings.dedupe_on.is_empty() {
+        let before = items.len();
+        dedupe_items(&mut items, &settings.dedupe_on, settings.dedupe_keep);
+        debug_log!(
+            "🧹 Dropped {} duplicate items on '{}'",
+            before - items.len(),
+            settings.dedupe_on
+        );
+    }
+    sort_items(&mut items, &settings.sort_by)?;
+    if run.skip > 0 || run.limit.is_some() {
+        let limit = run.limit.unwrap_or(usize::MAX);
+        items = items.into_iter().skip(run.skip).take(limit).collect();
+        debug_log!("✂️ Rendering {} items after --skip/--limit", items.len());
+    }
+    Ok(items)
+}
+
+/// Split items into page contexts of `size` items with `page` (1-based), `totalPages`,
+/// and `prevPage`/`nextPage` file names (null at either end); `extension` gives the
+/// extension a page is written with
+pub fn paginate_items(
+    items: &[&Value],
+    size: usize,
+    stem: &str,
+    extension: impl Fn(&Value) -> Result<String>,
+) -> Result<Vec<Value>> {
+    let chunks: Vec<&[&Value]> = items.chunks(size.max(1)).collect();
+    let total = chunks.len();
+    let mut pages: Vec<Value> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            serde_json::json!({
+                "items": chunk.iter().map(|item| (*item).clone()).collect::<Vec<_>>(),
+                "page": i + 1,
+                "totalPages": total,
+            })
+        })
+        .collect();
+    let files = pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| Ok(format!("{}_page_{}.{}", stem, i + 1, extension(page)?)))
+        .collect::<Result<Vec<_>>>()?;
+    for (i, page) in pages.iter_mut().enumerate() {
+        let link = |n: Option<usize>| {
+            n.and_then(|n| files.get(n))
+                .map_or(Value::Null, |f| f.as_str().into())
+        };
+        page["prevPage"] = link(i.checked_sub(1));
+        page["nextPage"] = link(Some(i + 1));
+    }
+    Ok(pages)
+}
+
+/// Drop items repeating an earlier (or, keeping the last, a later) value of `field`
+fn dedupe_items(items: &mut Vec<(usize, &Value)>, field: &str, keep: DedupeKeep) {
+    let mut seen = HashSet::new();
+    let mut is_new = |(_, item): &(usize, &Value)| match objfield(item, field, None) {
+        Some(Value::Null) | None => true,
+        Some(value) => seen.insert(value.to_string()),
+    };
+    match keep {
+        DedupeKeep::First => items.retain(|item| is_new(item)),
+        DedupeKeep::Last => {
+            items.reverse();
+            items.retain(|item| is_new(item));
+            items.reverse();
+        }
+    }
+}
+
+/// Bucket items on a field in first-seen order; items whose field is an array join one group
+/// per element, items without the field go to "ungrouped"
+pub fn group_items(items: &[&Value], field: &str) -> Vec<Value> {
+    let mut groups: Vec<(String, Vec<Value>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let keys = match objfield(item, field, None) {
+            Some(Value::Array(values)) => values,
+            Some(Value::Null) | None => vec![Value::from("ungrouped")],
+            Some(value) => vec![value],
+        };
+        for key in keys {
+            let key = key.as_str().map_or_else(|| key.to_string(), String::from);
+            let slot = *index.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[slot].1.push((*item).clone());
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(key, members)| {
+            serde_json::json!({
+                "GroupKey": key,
+                "GroupSize": members.len(),
+                "GroupItems": members,
+            })
+        })
+        .collect()
+}
+
+/// Comparison mode for a sort key
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SortMode {
+    /// Numeric when both values are numbers (or numeric strings), text otherwise
+    Auto,
+    Numeric,
+    Text,
+    Date,
+}
+
+/// Sort items by a spec like "published:desc:date,title" (stable; missing values last)
+fn sort_items(items: &mut [(usize, &Value)], spec: &str) -> Result<()> {
+    let mut keys = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let mut parts = entry.split(':').map(str::trim);
+        let field = parts.next().unwrap_or_default();
+        let (mut desc, mut mode) = (false, SortMode::Auto);
+        for part in parts {
+            match part {
+                "asc" => desc = false,
+                "desc" => desc = true,
+                "auto" => mode = SortMode::Auto,
+                "num" | "numeric" => mode = SortMode::Numeric,
+                "str" | "text" => mode = SortMode::Text,
+                "date" => mode = SortMode::Date,
+                _ => anyhow::bail!("Sort key '{}': unknown option '{}'", entry, part),
+            }
+        }
+        keys.push((field, desc, mode));
+    }
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    items.sort_by(|(_, a), (_, b)| {
+        keys.iter()
+            .map(|&(field, desc, mode)| {
+                let va = objfield(a, field, None).filter(|v| !v.is_null());
+                let vb = objfield(b, field, None).filter(|v| !v.is_null());
+                match (va, vb) {
+                    (Some(x), Some(y)) => compare_sort_values(&x, &y, mode, desc),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(())
+}
+
+/// Compare two present values under a sort mode; values that fail to parse as numbers or
+/// dates sort after the others in either direction
+fn compare_sort_values(a: &Value, b: &Value, mode: SortMode, desc: bool) -> std::cmp::Ordering {
+    let text = |v: &Value| v.as_str().map_or_else(|| v.to_string(), String::from);
+    let directed = |ord: std::cmp::Ordering| if desc { ord.reverse() } else { ord };
+    let by_key = |ka: Option<f64>, kb: Option<f64>| match (ka, kb) {
+        (Some(x), Some(y)) => directed(x.total_cmp(&y)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => directed(text(a).cmp(&text(b))),
+    };
+    match mode {
+        SortMode::Numeric => by_key(as_number(a), as_number(b)),
+        SortMode::Date => {
+            let ts = |v: &Value| parse_date(v).map(|d| d.timestamp_millis() as f64);
+            by_key(ts(a), ts(b))
+        }
+        SortMode::Text => directed(text(a).cmp(&text(b))),
+        SortMode::Auto => match (as_number(a), as_number(b)) {
+            (Some(x), Some(y)) => directed(x.total_cmp(&y)),
+            _ => directed(text(a).cmp(&text(b))),
+        },
+    }
+}
+
+/// Read a value as a number, accepting numeric strings (e.g. from CSV)
+fn as_number(val: &Value) -> Option<f64> {
+    match val {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Convert f64 to a JSON number, keeping integral values as integers
+fn number_value(f: f64) -> Value {
+    if f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+        Value::from(f as i64)
+    } else {
+        Value::from(f)
+    }
+}
+
+/// Compute named aggregates from a spec like "total=sum:price,count=length:items"
+pub fn compute_aggregates(
+    spec: &str,
+    data: &Value,
+    items: &[&Value],
+) -> Result<serde_json::Map<String, Value>> {
+    let mut result = serde_json::Map::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, expr) = entry
+            .split_once('=')
+            .with_context(|| format!("Aggregate '{}': expected name=op:field", entry))?;
+        let (op, field) = expr.split_once(':').unwrap_or((expr, ""));
+        let (name, op, field) = (name.trim(), op.trim(), field.trim());
+
+        let numbers = || -> Vec<f64> {
+            items
+                .iter()
+                .filter_map(|item| objfield(item, field, Some(data)))
+                .filter_map(|v| as_number(&v))
+                .collect()
+        };
+
+        let value = match op {
+            "sum" => number_value(numbers().iter().sum()),
+            "avg" => {
+                let nums = numbers();
+                if nums.is_empty() {
+                    Value::Null
+                } else {
+                    number_value(nums.iter().sum::<f64>() / nums.len() as f64)
+                }
+            }
+            "min" => numbers()
+                .into_iter()
+                .reduce(f64::min)
+                .map_or(Value::Null, number_value),
+            "max" => numbers()
+                .into_iter()
+                .reduce(f64::max)
+                .map_or(Value::Null, number_value),
+            "count" if field.is_empty() => Value::from(items.len()),
+            "count" => Value::from(
+                items
+                    .iter()
+                    .filter_map(|item| objfield(item, field, Some(data)))
+                    .filter(|v| !v.is_null())
+                    .count(),
+            ),
+            "length" => match objfield(data, field, None) {
+                Some(Value::Array(arr)) => Value::from(arr.len()),
+                Some(Value::Object(obj)) => Value::from(obj.len()),
+                Some(Value::String(s)) => Value::from(s.chars().count()),
+                _ => Value::from(0),
+            },
+            _ => anyhow::bail!("Aggregate '{}': unknown operation '{}'", name, op),
+        };
+        result.insert(name.to_string(), value);
+    }
+    Ok(result)
+}
+
+/// Write the accumulated single-file output (to stdout directly for "-"); an empty file
+/// when no item was rendered
+pub fn write_single_file(
+    output_file: &Path,
+    content: &str,
+    item_count: usize,
+    run: &RunOptions,
+    sink: &mut dyn OutputSink,
+    stats: &mut WriteStats,
+) -> Result<()> {
+    if is_stdout(output_file) {
+        if !run.dry_run {
+            StdoutSink.write(output_file, content.as_bytes())?;
+        }
+        debug_log!("✅ Wrote {} items to stdout", item_count);
+    } else if item_count == 0 {
+        debug_log!("⚠️ No items rendered to output file");
+        // Write empty file to indicate success
+        stats.record(write_output(sink, output_file, "", run)?, output_file, 0);
+    } else {
+        let outcome = write_output(sink, output_file, content, run)?;
+        stats.record(outcome, output_file, content.len());
+        if run.dry_run {
+            success_log!(
+                "Would {}: {} ({} items, {} bytes)",
+                outcome.planned(),
+                output_file.display(),
+                item_count,
+                content.len()
+            );
+        } else {
+            success_log!(
+                "{}: {} ({} items, {} bytes)",
+                outcome,
+                output_file.display(),
+                item_count,
+                content.len()
+            );
+        }
+        debug_log!("✅ Wrote {} items to {}", item_count, output_file.display());
+    }
+    Ok(())
+}
+
+/// Render the --index-template over the generated files and write it next to them
+pub fn write_index(
+    hb: &Handlebars<'_>,
+    output: &OutputStrategy,
+    entries: Vec<Value>,
+    aggregates: &serde_json::Map<String, Value>,
+    run: &RunOptions,
+    sink: &mut dyn OutputSink,
+    stats: &mut WriteStats,
+) -> Result<()> {
+    let OutputStrategy::MultiFile { directory, .. } = output else {
+        warn_log!("--index-template only applies to multi-file output; skipped");
+        return Ok(());
+    };
+    let mut ctx_map = serde_json::Map::new();
+    ctx_map.insert("count".into(), entries.len().into());
+    ctx_map.insert("files".into(), Value::Array(entries));
+    ctx_map.insert("SourceFilename".into(), run.source_label.as_str().into());
+    run.inject_context(&mut ctx_map, aggregates);
+    let body = hb
+        .render_with_context("index", &HbContext::from(Value::Object(ctx_map)))
+        .context("Index template render failed")?;
+    let path = directory.join(&run.index_name);
+    let outcome = write_output(sink, &path, &body, run)?;
+    stats.record(outcome, &path, body.len());
+    if run.dry_run {
+        success_log!("Would {}: {}", outcome.planned(), path.display());
+    } else if outcome != WriteOutcome::Unchanged {
+        success_log!("{}: {}", outcome, path.display());
+    }
+    Ok(())
+}
+
+/// Bring the --nav navigation in line with the generated pages
+pub fn write_nav(
+    kind: nav::NavKind,
+    output: &OutputStrategy,
+    entries: &[nav::NavEntry],
+    run: &RunOptions,
+    sink: &mut dyn OutputSink,
+) -> Result<()> {
+    match output {
+        OutputStrategy::MultiFile { .. } if run.archive.is_some() => {
+            warn_log!("--nav does not apply to archive output; skipped");
+        }
+        OutputStrategy::MultiFile { directory, .. } => {
+            let path = run
+                .nav_file
+                .clone()
+                .unwrap_or_else(|| kind.default_file(directory));
+            let existing = sink
+                .read(&path)
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+            let updated = kind.update(existing.as_deref(), entries)?;
+            if existing.as_deref() == Some(updated.as_str()) {
+                debug_log!("⏭️ Unchanged: {}", path.display());
+            } else if run.dry_run {
+                success_log!("Would update navigation: {}", path.display());
+            } else {
+                sink.write(&path, updated.as_bytes())?;
+                success_log!("Updated navigation: {}", path.display());
+            }
+        }
+        OutputStrategy::SingleFile(_) => {
+            warn_log!("--nav only applies to multi-file output; skipped");
+        }
+    }
+    Ok(())
+}
+
+/// Write the --errors-report and --report files and log the summary; a partial-run
+/// error when items failed to render (or would, in a dry run)
+pub fn report_run(
+    run: &RunOptions,
+    stats: &WriteStats,
+    failures: &[ItemFailure],
+    timings: Vec<Value>,
+    collisions: Vec<Value>,
+    started: Instant,
+) -> Result<()> {
+    let render_errors = failures.len();
+    if let Some(path) = &run.errors_report {
+        let report = serde_json::json!({ "failed": render_errors, "errors": failures });
+        fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+            .with_context(|| format!("Failed to write errors report: {}", path.display()))?;
+        debug_log!("📋 Wrote errors report to {}", path.display());
+    }
+    if let Some(path) = &run.report {
+        let warnings = logging::take_warnings();
+        let report = serde_json::json!({
+            "source": run.source_label,
+            "dry_run": run.dry_run,
+            "created": stats.created,
+            "updated": stats.updated,
+            "unchanged": stats.unchanged,
+            "skipped": stats.skipped,
+            "bytes_written": if run.dry_run { 0 } else { stats.bytes },
+            "files": &stats.files,
+            "items": timings,
+            "collisions": collisions,
+            "warnings": warnings,
+            "failed": failures,
+            "elapsed_ms": started.elapsed().as_secs_f64() * 1000.0,
+        });
+        fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+            .with_context(|| format!("Failed to write report: {}", path.display()))?;
+        debug_log!("📋 Wrote run report to {}", path.display());
+    }
+
+    if run.dry_run {
+        info_log!(
+            "Dry run: {} to create, {} to overwrite, {} unchanged, {} skipped, {} render error(s); nothing written",
+            stats.created,
+            stats.updated,
+            stats.unchanged,
+            stats.skipped,
+            render_errors
+        );
+        if render_errors > 0 {
+            return Err(RunError::Partial(format!(
+                "Dry run found {} render error(s)",
+                render_errors
+            ))
+            .into());
+        }
+    } else if run.incremental {
+        info_log!(
+            "{} created, {} updated, {} unchanged, {} skipped",
+            stats.created,
+            stats.updated,
+            stats.unchanged,
+            stats.skipped
+        );
+    } else if stats.skipped > 0 {
+        info_log!("{} existing file(s) skipped", stats.skipped);
+    }
+    if render_errors > 0 {
+        return Err(
+            RunError::Partial(format!("{} item(s) failed to render", render_errors)).into(),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemorySink;
+    use handlebars::{Helper, HelperResult, Output, RenderContext};
+    use serde_json::json;
+
+    fn shout(
+        h: &Helper<'_>,
+        _: &Handlebars<'_>,
+        _: &HbContext,
+        _: &mut RenderContext<'_, '_>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let text = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .unwrap_or_default();
+        out.write(&text.to_uppercase())?;
+        Ok(())
+    }
+
+    #[test]
+    fn converter_renders_inline_data_into_a_sink_with_registered_helpers() {
+        let mut sink = MemorySink::default();
+        let set: Vec<(&str, Box<dyn HelperDef + Send + Sync>)> = vec![("loud", Box::new(shout))];
+        Converter::new()
+            .data(json!({"books": [
+                {"name": "dune", "author": "herbert"},
+                {"name": "emma", "author": "austen"}
+            ]}))
+            .settings(JsonImportSettings {
+                top_field: "books".to_string(),
+                note_prefix: "book-".to_string(),
+                ..Default::default()
+            })
+            .template("{{> by}} {{loud name}}")
+            .partial("by", "{{shout author}}:")
+            .register_helper("shout", shout)
+            .register_helper_set(set)
+            .output(Destination::Directory("out".into()))
+            .run_into(&mut sink)
+            .unwrap();
+        let files: Vec<(String, &str)> = sink
+            .files
+            .keys()
+            .map(|path| (path.display().to_string(), sink.text(path).unwrap()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("out/book-dune.md".to_string(), "HERBERT: DUNE"),
+                ("out/book-emma.md".to_string(), "AUSTEN: EMMA"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_rendering_names_and_orders_files_like_a_sequential_run() {
+        // More items than one render batch, with clashing file names across batches
+        let items: Vec<Value> = (0..1100)
+            .map(|i| {
+                let name = ["alpha", "beta", "gamma"][i % 3];
+                json!({"name": name, "n": i})
+            })
+            .collect();
+        let convert = || {
+            let mut sink = MemorySink::default();
+            Converter::new()
+                .data(Value::Array(items.clone()))
+                .template("{{n}}")
+                .output(Destination::Directory("out".into()))
+                .run_into(&mut sink)
+                .unwrap();
+            sink.files
+                .keys()
+                .map(|path| {
+                    (
+                        path.display().to_string(),
+                        sink.text(path).unwrap().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let sequential = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(convert);
+        let parallel = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap()
+            .install(convert);
+        assert_eq!(sequential.len(), 1100);
+        assert_eq!(parallel, sequential);
+        // The first item of a name keeps it, later ones get suffixes in item order
+        let text = |name: &str| {
+            let path = format!("out/{}.md", name);
+            sequential
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map(|(_, t)| t.as_str())
+        };
+        assert_eq!(text("alpha"), Some("0"));
+        assert_eq!(text("alpha1"), Some("3"));
+        assert_eq!(text("beta366"), Some("1099"));
+    }
+
+    #[test]
+    fn render_mode_prefers_stream_then_single_context_then_pages_then_groups() {
+        let grouped = JsonImportSettings {
+            group_by: "kind".to_string(),
+            ..Default::default()
+        };
+        let single = JsonImportSettings {
+            single_context: true,
+            ..grouped.clone()
+        };
+        let plain = JsonImportSettings::default();
+        let chunked = RunOptions {
+            chunk: Some(2),
+            ..Default::default()
+        };
+        let streamed = RunOptions {
+            stream: Some("big.json".into()),
+            ..Default::default()
+        };
+        let mode = |settings, run, stem| match RenderMode::of(settings, run, stem) {
+            RenderMode::Stream(_) => "stream",
+            RenderMode::SingleContext => "single",
+            RenderMode::Pages { .. } => "pages",
+            RenderMode::Groups(_) => "groups",
+            RenderMode::Items => "items",
+        };
+        let none = RunOptions::default();
+        assert_eq!(mode(&single, &streamed, None), "stream");
+        assert_eq!(mode(&single, &chunked, Some("out")), "single");
+        assert_eq!(mode(&plain, &chunked, Some("out")), "pages");
+        assert_eq!(mode(&grouped, &none, None), "groups");
+        assert_eq!(mode(&plain, &none, None), "items");
+    }
+
+    #[test]
+    fn converter_needs_data_and_a_template() {
+        let mut sink = MemorySink::default();
+        let no_data = Converter::new().template("x").run_into(&mut sink);
+        assert!(no_data.unwrap_err().to_string().contains("No data"));
+        let no_template = Converter::new().data(json!({})).run_into(&mut sink);
+        assert!(no_template.unwrap_err().to_string().contains("No template"));
+        assert!(sink.files.is_empty());
+    }
+
+    #[test]
+    fn paginate_items_links_pages_with_their_extension() {
+        let data = json!([1, 2, 3, 4, 5]);
+        let items: Vec<&Value> = data.as_array().unwrap().iter().collect();
+        let pages = paginate_items(&items, 2, "out", |page| {
+            Ok(if page["page"] == 2 { "html" } else { "adoc" }.to_string())
+        })
+        .unwrap();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0]["items"], json!([1, 2]));
+        assert_eq!(pages[2]["items"], json!([5]));
+        assert_eq!(pages[0]["totalPages"], 3);
+        assert_eq!(pages[0]["prevPage"], Value::Null);
+        assert_eq!(pages[0]["nextPage"], "out_page_2.html");
+        assert_eq!(pages[1]["prevPage"], "out_page_1.adoc");
+        assert_eq!(pages[1]["nextPage"], "out_page_3.adoc");
+        assert_eq!(pages[2]["nextPage"], Value::Null);
+    }
+
+    #[test]
+    fn sort_and_dedupe_keep_original_positions() {
+        let data = json!([
+            {"id": "a", "n": 3},
+            {"id": "b", "n": 1},
+            {"id": "a", "n": 2},
+            {"id": "c", "n": 2}
+        ]);
+        let mut items: Vec<(usize, &Value)> = data.as_array().unwrap().iter().enumerate().collect();
+        dedupe_items(&mut items, "id", DedupeKeep::Last);
+        sort_items(&mut items, "n").unwrap();
+        let order: Vec<(usize, &str)> = items
+            .iter()
+            .map(|(origin, item)| (*origin, item["id"].as_str().unwrap()))
+            .collect();
+        assert_eq!(order, vec![(1, "b"), (2, "a"), (3, "c")]);
+    }
+
+    #[test]
+    fn select_items_filters_dedupes_and_sorts_before_skip_and_limit() {
+        let data = json!([
+            {"id": 1, "n": 4, "slug": "a"},
+            {"id": 2, "n": 3, "slug": "a", "draft": true},
+            {"id": 3, "n": 2, "slug": "a"},
+            {"id": 4, "n": 1, "slug": "b"},
+            {"id": 5, "n": 0, "slug": "c"}
+        ]);
+        let settings = JsonImportSettings {
+            filter: vec![
+                serde_json::from_value(json!({"field": "draft", "op": "missing"})).unwrap(),
+            ],
+            dedupe_on: "slug".to_string(),
+            sort_by: "n".to_string(),
+            ..Default::default()
+        };
+        let run = RunOptions {
+            skip: 1,
+            limit: Some(1),
+            ..Default::default()
+        };
+        let items = select_items(&data, &settings, &run).unwrap();
+        assert_eq!(items, vec![(3, &data[3])]);
+    }
+
+    /// Ids of the items in their current order
+    fn ids(items: &[(usize, &Value)]) -> Vec<String> {
+        items
+            .iter()
+            .map(|(_, item)| item["id"].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn sort_items_applies_keys_in_turn_with_missing_values_last() {
+        let data = json!([
+            {"id": 1, "kind": "b", "n": "10"},
+            {"id": 2, "kind": "a", "n": 9},
+            {"id": 3, "kind": "b", "n": 2},
+            {"id": 4, "kind": "a"},
+            {"id": 5, "n": 1}
+        ]);
+        let mut items: Vec<(usize, &Value)> = data.as_array().unwrap().iter().enumerate().collect();
+        sort_items(&mut items, "kind, n:desc").unwrap();
+        assert_eq!(ids(&items), ["2", "4", "1", "3", "5"]);
+        sort_items(&mut items, "n:text").unwrap();
+        assert_eq!(ids(&items), ["5", "1", "3", "2", "4"]);
+        sort_items(&mut items, "").unwrap();
+        assert_eq!(ids(&items), ["5", "1", "3", "2", "4"]);
+        assert!(sort_items(&mut items, "n:sideways").is_err());
+    }
+
+    #[test]
+    fn sort_items_orders_dates_and_puts_unparseable_ones_after() {
+        let data = json!([
+            {"id": 1, "at": "2024-03-01"},
+            {"id": 2, "at": "someday"},
+            {"id": 3, "at": "2023-12-31"},
+            {"id": 4, "at": "2024-01-15T10:00:00Z"}
+        ]);
+        let mut items: Vec<(usize, &Value)> = data.as_array().unwrap().iter().enumerate().collect();
+        sort_items(&mut items, "at:date").unwrap();
+        assert_eq!(ids(&items), ["3", "4", "1", "2"]);
+        sort_items(&mut items, "at:desc:date").unwrap();
+        assert_eq!(ids(&items), ["1", "4", "3", "2"]);
+    }
+
+    #[test]
+    fn dedupe_items_keeps_first_and_every_item_without_the_field() {
+        let data = json!([
+            {"id": 1, "slug": "x"},
+            {"id": 2},
+            {"id": 3, "slug": "x"},
+            {"id": 4, "slug": null},
+            {"id": 5, "slug": "y"}
+        ]);
+        let mut items: Vec<(usize, &Value)> = data.as_array().unwrap().iter().enumerate().collect();
+        dedupe_items(&mut items, "slug", DedupeKeep::First);
+        assert_eq!(ids(&items), ["1", "2", "4", "5"]);
+    }
+
+    #[test]
+    fn group_items_buckets_in_first_seen_order() {
+        let data = json!([
+            {"id": 1, "tags": ["rust", "cli"]},
+            {"id": 2, "tags": "cli"},
+            {"id": 3},
+            {"id": 4, "tags": 7}
+        ]);
+        let items: Vec<&Value> = data.as_array().unwrap().iter().collect();
+        let groups = group_items(&items, "tags");
+        let summary: Vec<(&str, u64)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g["GroupKey"].as_str().unwrap(),
+                    g["GroupSize"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("rust", 1), ("cli", 2), ("ungrouped", 1), ("7", 1)]
+        );
+        assert_eq!(groups[1]["GroupItems"][1]["id"], 2);
+    }
+
+    #[test]
+    fn compute_aggregates_reads_numbers_and_lengths() {
+        let data = json!({
+            "books": [{"price": 10}, {"price": "2.5"}, {"price": null}, {"title": "x"}],
+            "owner": "anna"
+        });
+        let items: Vec<&Value> = data["books"].as_array().unwrap().iter().collect();
+        let spec = "total=sum:price, avg=avg:price, low=min:price, high=max:price, \
+                    all=count, priced=count:price, books=length:books, name=length:owner, \
+                    none=avg:missing";
+        let result = compute_aggregates(spec, &data, &items).unwrap();
+        assert_eq!(
+            Value::Object(result),
+            json!({
+                "total": 12.5, "avg": 6.25, "low": 2.5, "high": 10, "all": 4, "priced": 2,
+                "books": 4, "name": 4, "none": null
+            })
+        );
+        assert!(compute_aggregates("total", &data, &items).is_err());
+        assert!(compute_aggregates("x=median:price", &data, &items).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Built-in date/time helpers (chrono, with chrono-tz for time zones).
+//!
+//! ```text
+//! {{dateFormat published "%d %B %Y"}}         05 January 2024
+//! {{dateFormat published "%H:%M" tz="Asia/Tokyo"}}
+//! {{now "%Y-%m-%d"}}                          run date (the same for every item)
+//! {{fromUnix created_ms "%Y-%m-%d %H:%M" unit="ms"}}
+//! {{dateAdd due 2 "weeks"}}                   2024-01-19
+//! ```
+//!
+//! Dates are RFC 3339, "YYYY-MM-DD[ HH:MM[:SS]]" (local time) or epoch seconds/millis.
+//! Formats are chrono strftime patterns, or "rfc3339"/"rfc2822"; `tz` is an IANA zone
+//! name, "UTC" or "local" (the default). Values that are not dates render empty. With
+//! --locale, month and weekday names are in the locale's language.
+
+use crate::i18n::Locale;
+use crate::{parse_date, re_err, run_now};
+use chrono::{DateTime, Datelike, Duration, Local, Months, TimeZone, Utc};
+use chrono_tz::Tz;
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+/// Format a date in a time zone
+fn format_date(
+    date: DateTime<Local>,
+    format: &str,
+    tz: Option<&str>,
+    locale: &Locale,
+) -> Result<String, RenderError> {
+    match tz.map(str::trim) {
+        None | Some("") => render(&date, format, locale),
+        Some(zone) if zone.eq_ignore_ascii_case("local") => render(&date, format, locale),
+        Some(zone) if zone.eq_ignore_ascii_case("utc") => {
+            render(&date.with_timezone(&Utc), format, locale)
+        }
+        Some(zone) => {
+            let tz: Tz = zone
+                .parse()
+                .map_err(|_| re_err(format!("unknown time zone '{}'", zone)))?;
+            render(&date.with_timezone(&tz), format, locale)
+        }
+    }
+}
+
+fn render<T: TimeZone>(
+    date: &DateTime<T>,
+    format: &str,
+    locale: &Locale,
+) -> Result<String, RenderError>
+where
+    T::Offset: std::fmt::Display,
+{
+    match format {
+        "rfc3339" => Ok(date.to_rfc3339()),
+        "rfc2822" => Ok(date.to_rfc2822()),
+        _ => {
+            let format = locale.localize_format(
+                format,
+                date.month0() as usize,
+                date.weekday().num_days_from_monday() as usize,
+            );
+            let format = format.as_str();
+            let mut out = String::new();
+            // An invalid pattern is an error in Display, not a panic
+            write!(out, "{}", date.format(format))
+                .map_err(|_| re_err(format!("invalid date format '{}'", format)))?;
+            Ok(out)
+        }
+    }
+}
+
+/// Positional string argument
+fn str_param<'a>(h: &'a Helper<'_>, idx: usize) -> Option<&'a str> {
+    h.param(idx).and_then(|p| p.value().as_str())
+}
+
+/// Hash string argument
+fn str_hash<'a>(h: &'a Helper<'_>, key: &str) -> Option<&'a str> {
+    h.hash_get(key).and_then(|p| p.value().as_str())
+}
+
+/// {{dateFormat date [format] [tz=zone]}} (format defaults to %Y-%m-%d)
+fn date_format(h: &Helper<'_>, locale: &Locale) -> Result<String, RenderError> {
+    let Some(date) = h.param(0).and_then(|p| parse_date(p.value())) else {
+        return Ok(String::new());
+    };
+    format_date(
+        date,
+        str_param(h, 1).unwrap_or("%Y-%m-%d"),
+        str_hash(h, "tz"),
+        locale,
+    )
+}
+
+/// {{now [format] [tz=zone]}} (format defaults to RFC 3339)
+fn now(h: &Helper<'_>, locale: &Locale) -> Result<String, RenderError> {
+    format_date(
+        run_now(),
+        str_param(h, 0).unwrap_or("rfc3339"),
+        str_hash(h, "tz"),
+        locale,
+    )
+}
+
+/// {{fromUnix epoch [format] [unit="s"|"ms"] [tz=zone]}}; without `unit` large values
+/// are taken as milliseconds
+fn from_unix(h: &Helper<'_>, locale: &Locale) -> Result<String, RenderError> {
+    let value = h.param(0).map(|p| p.value());
+    let epoch = match value {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    let Some(epoch) = epoch else {
+        return Ok(String::new());
+    };
+    let millis = match str_hash(h, "unit") {
+        Some("ms") => epoch,
+        Some("s") => epoch * 1000.0,
+        Some(other) => return Err(re_err(format!("fromUnix: unknown unit '{}'", other))),
+        None if epoch.abs() > 100_000_000_000.0 => epoch,
+        None => epoch * 1000.0,
+    };
+    let Some(date) = Local.timestamp_millis_opt(millis.round() as i64).single() else {
+        return Ok(String::new());
+    };
+    format_date(
+        date,
+        str_param(h, 1).unwrap_or("rfc3339"),
+        str_hash(h, "tz"),
+        locale,
+    )
+}
+
+/// {{dateAdd date amount unit [format] [tz=zone]}} with unit seconds, minutes, hours,
+/// days, weeks, months or years (singular and s/m/h/d/w/M/y work too); the result keeps
+/// a date-only input date-only unless a format is given
+fn date_add(h: &Helper<'_>, locale: &Locale) -> Result<String, RenderError> {
+    let value = h.param(0).map(|p| p.value());
+    let Some(date) = value.and_then(parse_date) else {
+        return Ok(String::new());
+    };
+    let amount = h
+        .param(1)
+        .and_then(|p| match p.value() {
+            Value::Number(n) => n.as_i64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+        .ok_or_else(|| re_err("dateAdd: amount must be an integer"))?;
+    let unit = str_param(h, 2).unwrap_or("days");
+    let shifted = match unit {
+        "s" | "second" | "seconds" => Some(date + Duration::seconds(amount)),
+        "m" | "minute" | "minutes" => Some(date + Duration::minutes(amount)),
+        "h" | "hour" | "hours" => Some(date + Duration::hours(amount)),
+        "d" | "day" | "days" => Some(date + Duration::days(amount)),
+        "w" | "week" | "weeks" => Some(date + Duration::weeks(amount)),
+        "M" | "month" | "months" => add_months(date, amount),
+        "y" | "year" | "years" => add_months(date, amount.saturating_mul(12)),
+        other => return Err(re_err(format!("dateAdd: unknown unit '{}'", other))),
+    };
+    let Some(shifted) = shifted else {
+        return Ok(String::new());
+    };
+    let date_only = matches!(value, Some(Value::String(s)) if s.trim().len() == 10);
+    let format = str_param(h, 3).unwrap_or(if date_only { "%Y-%m-%d" } else { "rfc3339" });
+    format_date(shifted, format, str_hash(h, "tz"), locale)
+}
+
+/// Calendar months forward or back (the day is clamped to the month's length)
+fn add_months(date: DateTime<Local>, months: i64) -> Option<DateTime<Local>> {
+    let count = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months >= 0 {
+        date.checked_add_months(count)
+    } else {
+        date.checked_sub_months(count)
+    }
+}
+
+/// Date helper computing a string from its arguments
+struct DateHelper(
+    fn(&Helper<'_>, &Locale) -> Result<String, RenderError>,
+    Arc<Locale>,
+);
+
+impl HelperDef for DateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived(Value::String((self.0)(h, &self.1)?)))
+    }
+}
+
+/// The date helpers with their names, formatting for a locale
+pub fn helpers(locale: &Arc<Locale>) -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    let helper = |f| Box::new(DateHelper(f, Arc::clone(locale)));
+    vec![
+        ("dateFormat", helper(date_format)),
+        ("now", helper(now)),
+        ("fromUnix", helper(from_unix)),
+        ("dateAdd", helper(date_add)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, locale: Locale) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        for (name, helper) in helpers(&Arc::new(locale)) {
+            hb.register_helper(name, helper);
+        }
+        let data = json!({"published": "2024-01-05T10:00:00Z", "ms": 1_700_000_000_000_i64});
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn dates_format_in_zones_and_locales() {
+        let en = || Locale::default();
+        assert_eq!(
+            render(
+                r#"{{dateFormat published "%d %B %Y %H:%M" tz="UTC"}}"#,
+                en()
+            )
+            .unwrap(),
+            "05 January 2024 10:00"
+        );
+        assert_eq!(
+            render(
+                r#"{{dateFormat published "%H:%M %a" tz="Asia/Tokyo"}}"#,
+                en()
+            )
+            .unwrap(),
+            "19:00 Fri"
+        );
+        let de = Locale::new("de-AT", None).unwrap();
+        assert_eq!(
+            render(r#"{{dateFormat published "%A, %d. %B" tz="UTC"}}"#, de).unwrap(),
+            "Freitag, 05. Januar"
+        );
+        assert_eq!(
+            render(
+                r#"{{fromUnix ms "%Y-%m-%d" tz="UTC"}}|{{fromUnix 0 "%Y" unit="s" tz="utc"}}"#,
+                en()
+            )
+            .unwrap(),
+            "2023-11-14|1970"
+        );
+        assert_eq!(render(r#"{{now "%Y"}}"#, en()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn date_add_moves_by_units_and_clamps_month_ends() {
+        let en = || Locale::default();
+        assert_eq!(
+            render(r#"{{dateAdd "2024-01-05" 2 "weeks"}}"#, en()).unwrap(),
+            "2024-01-19"
+        );
+        assert_eq!(
+            render(r#"{{dateAdd "2024-01-31" 1 "M"}}"#, en()).unwrap(),
+            "2024-02-29"
+        );
+        assert_eq!(
+            render(r#"{{dateAdd "2024-02-29" -1 "years"}}"#, en()).unwrap(),
+            "2023-02-28"
+        );
+        assert_eq!(
+            render(r#"{{dateAdd published "-3" "h" "%H" tz="UTC"}}"#, en()).unwrap(),
+            "07"
+        );
+    }
+
+    #[test]
+    fn non_dates_render_empty_and_bad_params_fail() {
+        let en = || Locale::default();
+        let template = concat!(
+            r#"{{dateFormat "soon"}}|{{dateFormat missing}}|"#,
+            r#"{{fromUnix "x"}}|{{dateAdd "later" 1}}"#
+        );
+        assert_eq!(render(template, en()).unwrap(), "|||");
+        assert!(render(r#"{{dateFormat published "%Y" tz="Mars/Base"}}"#, en()).is_err());
+        assert!(render(r#"{{dateFormat published "%Q"}}"#, en()).is_err());
+        assert!(render(r#"{{fromUnix 0 unit="days"}}"#, en()).is_err());
+        assert!(render(r#"{{dateAdd published "two" "days"}}"#, en()).is_err());
+        assert!(render(r#"{{dateAdd published 2 "fortnights"}}"#, en()).is_err());
+        assert!(render(r#"{{dateAdd published}}"#, en()).is_err());
+    }
+}
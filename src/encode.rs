@@ -0,0 +1,146 @@
+//! Helpers embedding values as JSON or YAML text.
+//!
+//! ```text
+//! {{json this}}                compact JSON (also valid YAML: `tags: {{json tags}}`)
+//! {{jsonPretty config}}        indented JSON, 2 spaces (indent=4 for more)
+//! {{yaml meta}}                YAML block, without the leading "---"
+//! {{yaml meta indent=2}}       every line but the first indented, for nesting
+//! ```
+
+use crate::re_err;
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Indent every line after the first
+fn indent_rest(text: &str, width: usize) -> String {
+    let pad = " ".repeat(width);
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.is_empty() {
+                line.to_string()
+            } else {
+                format!("{}{}", pad, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indentation width from the `indent` hash argument
+fn indent(h: &Helper<'_>, default: usize) -> usize {
+    h.hash_get("indent")
+        .and_then(|p| p.value().as_u64())
+        .map_or(default, |n| n as usize)
+}
+
+/// {{json value}}
+fn json(h: &Helper<'_>, value: &Value) -> Result<String, RenderError> {
+    match h.hash_get("indent").and_then(|p| p.value().as_u64()) {
+        Some(width) => pretty(value, width as usize),
+        None => serde_json::to_string(value).map_err(re_err),
+    }
+}
+
+/// {{jsonPretty value [indent=2]}}
+fn json_pretty(h: &Helper<'_>, value: &Value) -> Result<String, RenderError> {
+    pretty(value, indent(h, 2))
+}
+
+fn pretty(value: &Value, width: usize) -> Result<String, RenderError> {
+    let pad = " ".repeat(width);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(pad.as_bytes());
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer).map_err(re_err)?;
+    String::from_utf8(out).map_err(re_err)
+}
+
+/// {{yaml value [indent=N]}}
+fn yaml(h: &Helper<'_>, value: &Value) -> Result<String, RenderError> {
+    let text = serde_yaml::to_string(value).map_err(re_err)?;
+    let text = text
+        .strip_prefix("---\n")
+        .unwrap_or(&text)
+        .trim_end_matches('\n');
+    Ok(indent_rest(text, indent(h, 0)))
+}
+
+/// Helper serializing its first argument
+struct EncodeHelper(fn(&Helper<'_>, &Value) -> Result<String, RenderError>);
+
+impl HelperDef for EncodeHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h.param(0).map_or(&Value::Null, |p| p.value());
+        Ok(ScopedJson::Derived(Value::String((self.0)(h, value)?)))
+    }
+}
+
+/// The JSON/YAML helpers with their names
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![
+        ("json", Box::new(EncodeHelper(json))),
+        ("jsonPretty", Box::new(EncodeHelper(json_pretty))),
+        ("yaml", Box::new(EncodeHelper(yaml))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(handlebars::no_escape);
+        for (name, helper) in helpers() {
+            hb.register_helper(name, helper);
+        }
+        let data =
+            json!({"meta": {"tags": ["x", "y"], "title": "A \"quoted\" title"}, "empty": {}});
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn json_helpers_compact_or_indent() {
+        assert_eq!(
+            render("{{json meta}}").unwrap(),
+            r#"{"tags":["x","y"],"title":"A \"quoted\" title"}"#
+        );
+        assert_eq!(
+            render("{{jsonPretty meta.tags}}|{{json meta.tags indent=1}}").unwrap(),
+            "[\n  \"x\",\n  \"y\"\n]|[\n \"x\",\n \"y\"\n]"
+        );
+        assert_eq!(render("{{jsonPretty empty indent=4}}").unwrap(), "{}");
+        // Missing values and a non-numeric indent fall back to null and the default
+        assert_eq!(render("{{json missing}}|{{json}}").unwrap(), "null|null");
+        assert_eq!(
+            render(r#"{{jsonPretty meta.tags indent="wide"}}"#).unwrap(),
+            "[\n  \"x\",\n  \"y\"\n]"
+        );
+    }
+
+    #[test]
+    fn yaml_drops_the_document_marker_and_indents_nested_lines() {
+        assert_eq!(
+            render("{{yaml meta}}").unwrap(),
+            "tags:\n- x\n- y\ntitle: A \"quoted\" title"
+        );
+        assert_eq!(
+            render("meta:\n  {{yaml meta indent=2}}").unwrap(),
+            "meta:\n  tags:\n  - x\n  - y\n  title: A \"quoted\" title"
+        );
+        assert_eq!(
+            render("{{yaml missing}}|{{yaml empty}}").unwrap(),
+            "null|{}"
+        );
+        assert_eq!(indent_rest("a\n\nb", 2), "a\n\n  b");
+    }
+}
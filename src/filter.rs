@@ -0,0 +1,52 @@
+//! jq-style pre-filter over the parsed data (--filter), powered by jaq.
+//!
+//! Usage: --filter '.posts[] | select(.status == "published")'
+//!
+//! A filter producing exactly one value replaces the data with it; several outputs
+//! (e.g. from `.[]`) are collected into an array of items, and no output yields an
+//! empty array.
+
+use anyhow::Result;
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+
+/// Run a jq expression over the data
+pub fn apply_filter(data: Value, expr: &str) -> Result<Value> {
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader
+        .load(
+            &arena,
+            File {
+                code: expr,
+                path: (),
+            },
+        )
+        .map_err(|errs| {
+            let errs: Vec<_> = errs.into_iter().map(|(_, err)| err).collect();
+            anyhow::anyhow!("Invalid --filter expression '{}': {:?}", expr, errs)
+        })?;
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errs| {
+            let errs: Vec<_> = errs.into_iter().flat_map(|(_, err)| err).collect();
+            anyhow::anyhow!("Invalid --filter expression '{}': {:?}", expr, errs)
+        })?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut outputs = filter
+        .run((Ctx::new([], &inputs), Val::from(data)))
+        .map(|out| {
+            out.map(Value::from)
+                .map_err(|err| anyhow::anyhow!("--filter failed: {}", err))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(match outputs.len() {
+        1 => outputs.pop().unwrap(),
+        _ => Value::Array(outputs),
+    })
+}
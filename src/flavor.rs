@@ -0,0 +1,101 @@
+//! Output markup flavor (--flavor or the `flavor` setting).
+//!
+//! The templates decide the markup; the flavor only adjusts what the tool adds itself:
+//! the separator between items in single-file output, the default file extension, and
+//! the `escape` helper and `auto_escape` setting, which neutralize the flavor's markup
+//! characters.
+
+use serde::{Deserialize, Serialize};
+
+/// Markup language of the rendered output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Flavor {
+    #[default]
+    Markdown,
+    Asciidoc,
+    Rst,
+}
+
+impl Flavor {
+    /// Separator between items in single-file output (a thematic break / transition)
+    pub fn separator(self) -> &'static str {
+        match self {
+            Flavor::Markdown => "\n\n---\n\n",
+            Flavor::Asciidoc => "\n\n'''\n\n",
+            Flavor::Rst => "\n\n----\n\n",
+        }
+    }
+
+    /// Default extension of generated files
+    pub fn extension(self) -> &'static str {
+        match self {
+            Flavor::Markdown => "md",
+            Flavor::Asciidoc => "adoc",
+            Flavor::Rst => "rst",
+        }
+    }
+
+    /// Escape text so it renders literally
+    pub fn escape(self, text: &str) -> String {
+        if self == Flavor::Markdown {
+            return markdown_escape(text);
+        }
+        let mut out = String::with_capacity(text.len() + 8);
+        for c in text.chars() {
+            match (self, c) {
+                (Flavor::Rst, '\\' | '`' | '*' | '_' | '|' | '[' | ']' | ':') => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                // AsciiDoc has no general backslash escape: use built-in attributes and
+                // character references instead
+                (Flavor::Asciidoc, _) => match asciidoc_replacement(c) {
+                    Some(replacement) => out.push_str(replacement),
+                    None => out.push(c),
+                },
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+}
+
+/// Markdown: backslash-escape inline markup anywhere, and the characters that would start
+/// a heading, list or setext underline at the beginning of a line
+fn markdown_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 8);
+    // At a line start (only spaces so far) / inside a leading number ("1." lists)
+    let mut line_start = true;
+    let mut number = false;
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' => out.push('\\'),
+            '#' | '-' | '+' | '=' if line_start => out.push('\\'),
+            '.' | ')' if number => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+        number = c.is_ascii_digit() && (line_start || number);
+        line_start = c == '\n' || (line_start && c == ' ');
+    }
+    out
+}
+
+fn asciidoc_replacement(c: char) -> Option<&'static str> {
+    Some(match c {
+        '*' => "{asterisk}",
+        '|' => "{vbar}",
+        '[' => "{startsb}",
+        ']' => "{endsb}",
+        '^' => "{caret}",
+        '~' => "{tilde}",
+        '`' => "{backtick}",
+        '+' => "{plus}",
+        '\\' => "{backslash}",
+        '_' => "&#95;",
+        '#' => "&#35;",
+        '{' => "&#123;",
+        _ => return None,
+    })
+}
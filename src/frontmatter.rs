@@ -0,0 +1,203 @@
+//! YAML frontmatter from the `frontmatter` setting.
+//!
+//! Settings: "frontmatter": {"title": "{{name}}", "tags": "{{tags}}", "draft": false}
+//!
+//! String values are Handlebars templates rendered with the item context. A value that
+//! is exactly one field reference (`{{tags}}`) keeps the field's JSON type, so arrays
+//! become YAML lists and numbers stay numbers; missing fields are left out. The block is
+//! serialized with serde_yaml, so quoting and escaping are always valid YAML. A null
+//! value leaves the key out.
+
+use crate::input::split_frontmatter;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use regex::Regex;
+use serde_json::Value;
+use serde_yaml::Mapping;
+use std::sync::OnceLock;
+
+/// Build the frontmatter mapping for one item
+pub fn build(
+    spec: &IndexMap<String, Value>,
+    render: &dyn Fn(&str) -> Result<String>,
+    field: &dyn Fn(&str) -> Option<Value>,
+) -> Result<Mapping> {
+    let mut mapping = Mapping::new();
+    for (key, value) in spec {
+        // null drops a key (e.g. one a --site preset adds)
+        if value.is_null() {
+            continue;
+        }
+        let resolved =
+            resolve(value, render, field).with_context(|| format!("frontmatter key '{}'", key))?;
+        if let Some(resolved) = resolved {
+            mapping.insert(key.as_str().into(), serde_yaml::to_value(resolved)?);
+        }
+    }
+    Ok(mapping)
+}
+
+/// Resolve templates in a value (recursively through arrays and objects)
+fn resolve(
+    value: &Value,
+    render: &dyn Fn(&str) -> Result<String>,
+    field: &dyn Fn(&str) -> Option<Value>,
+) -> Result<Option<Value>> {
+    Ok(Some(match value {
+        Value::String(text) if !text.contains("{{") => value.clone(),
+        Value::String(text) => match single_reference(text) {
+            Some(path) => match field(path) {
+                Some(v) if !v.is_null() => v,
+                _ => return Ok(None),
+            },
+            None => Value::String(render(text)?),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .filter_map(|item| resolve(item, render, field).transpose())
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(obj) => {
+            let mut out = serde_json::Map::new();
+            for (key, item) in obj {
+                if let Some(resolved) = resolve(item, render, field)? {
+                    out.insert(key.clone(), resolved);
+                }
+            }
+            Value::Object(out)
+        }
+        other => other.clone(),
+    }))
+}
+
+/// The field path when a template is just `{{path}}`
+fn single_reference(text: &str) -> Option<&str> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"^\{\{\s*([A-Za-z_@][\w.\-\[\]@/]*)\s*\}\}$").expect("frontmatter regex")
+    });
+    re.captures(text.trim())
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+}
+
+/// Prepend the mapping as a YAML block; keys already in the body's own frontmatter win
+pub fn prepend(body: &str, mapping: Mapping) -> Result<String> {
+    if mapping.is_empty() {
+        return Ok(body.to_string());
+    }
+    let (mut fields, rest) = match split_frontmatter(body) {
+        Some((yaml, rest)) if !yaml.trim().is_empty() => (
+            serde_yaml::from_str::<Mapping>(yaml)
+                .context("Invalid frontmatter in template output")?,
+            rest,
+        ),
+        Some((_, rest)) => (Mapping::new(), rest),
+        None => (Mapping::new(), body),
+    };
+    for (key, value) in mapping {
+        if !fields.contains_key(&key) {
+            fields.insert(key, value);
+        }
+    }
+    Ok(format!(
+        "---\n{}---\n{}",
+        serde_yaml::to_string(&fields)?,
+        rest
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(pairs: &[(&str, Value)]) -> IndexMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    /// Build with a stand-in renderer that marks what it rendered
+    fn build_for(pairs: &[(&str, Value)], item: Value) -> Result<String> {
+        let render = |text: &str| -> Result<String> {
+            if text.contains("{{fail}}") {
+                anyhow::bail!("render failed");
+            }
+            Ok(format!("<{}>", text))
+        };
+        let field = |path: &str| crate::path::select(&item, path);
+        let mapping = build(&spec(pairs), &render, &field)?;
+        Ok(serde_yaml::to_string(&mapping)?)
+    }
+
+    #[test]
+    fn single_references_keep_their_type_and_missing_ones_drop() {
+        let item = json!({"tags": ["a", "b"], "n": 3, "owner": {"name": "Ada"}, "none": null});
+        assert_eq!(
+            build_for(
+                &[
+                    ("tags", json!("{{tags}}")),
+                    ("n", json!("{{ n }}")),
+                    ("owner", json!("{{owner.name}}")),
+                    ("title", json!("{{n}} items")),
+                    ("draft", json!(false)),
+                    ("gone", json!("{{missing}}")),
+                    ("empty", json!("{{none}}")),
+                    ("dropped", Value::Null),
+                    ("plain", json!("text")),
+                    (
+                        "nested",
+                        json!({"label": "x", "list": ["{{n}}", "{{missing}}"]})
+                    ),
+                ],
+                item
+            )
+            .unwrap(),
+            concat!(
+                "tags:\n- a\n- b\n",
+                "n: 3\n",
+                "owner: Ada\n",
+                "title: <{{n}} items>\n",
+                "draft: false\n",
+                "plain: text\n",
+                "nested:\n  label: x\n  list:\n  - 3\n"
+            )
+        );
+        assert_eq!(single_reference(" {{ a.b-c.[0] }} "), Some("a.b-c.[0]"));
+        assert_eq!(single_reference("{{a}} {{b}}"), None);
+        assert_eq!(single_reference("{{#if a}}x{{/if}}"), None);
+    }
+
+    #[test]
+    fn render_errors_name_the_key() {
+        let err = build_for(&[("summary", json!("{{fail}}!"))], json!({})).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "frontmatter key 'summary': render failed"
+        );
+    }
+
+    #[test]
+    fn prepend_merges_with_frontmatter_the_template_wrote() {
+        let mut mapping = Mapping::new();
+        mapping.insert("title".into(), "From settings".into());
+        mapping.insert("id".into(), 7.into());
+        assert_eq!(
+            prepend("---\ntitle: Own\n---\nBody", mapping.clone()).unwrap(),
+            "---\ntitle: Own\nid: 7\n---\nBody"
+        );
+        assert_eq!(
+            prepend("Body", mapping.clone()).unwrap(),
+            "---\ntitle: From settings\nid: 7\n---\nBody"
+        );
+        assert_eq!(
+            prepend("---\n---\nBody", mapping.clone()).unwrap(),
+            "---\ntitle: From settings\nid: 7\n---\nBody"
+        );
+        assert_eq!(prepend("Body", Mapping::new()).unwrap(), "Body");
+        assert!(prepend("---\n[1]\n---\n", mapping).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! HTML output: rendered Markdown is converted with pulldown-cmark.
+//!
+//! Usage: --render-html [--html-layout layout.hbs]
+//!
+//! The layout is a Handlebars template receiving the item fields plus `content` (the
+//! converted HTML) and `title` (the output name); without a layout the bare HTML
+//! fragment is written.
+
+use pulldown_cmark::{Options, Parser, html};
+
+/// Convert Markdown (with tables, footnotes, strikethrough and task lists) to HTML
+pub fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, Parser::new_ext(markdown, options));
+    out
+}
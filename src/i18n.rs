@@ -0,0 +1,492 @@
+//! Localization (--locale, --translations): the `t` helper and locale-aware dates and
+//! numbers.
+//!
+//! The translations file (JSON, YAML or TOML) holds one table per locale:
+//!
+//! ```text
+//! {"en": {"title": "Report", "items": {"one": "{count} item", "other": "{count} items"}},
+//!  "de": {"title": "Bericht", "items": {"one": "{count} Eintrag", "other": "{count} Einträge"}}}
+//! ```
+//!
+//! ```text
+//! {{t "title"}}                     Bericht
+//! {{t "nav.home"}}                  nested keys are dot paths
+//! {{t "greeting" name=author}}      "{name}" placeholders are filled from the hash
+//! {{t "items" count=n}}             plural forms "zero", "one" and "other"
+//! ```
+//!
+//! A region tag falls back to its language ("de-AT" uses "de" when there is no "de-AT"
+//! table) and a missing key renders as the key itself. With a locale, `dateFormat` and
+//! friends write month and weekday names (%B %b %A %a) in its language and
+//! `numberFormat` uses its separators.
+
+use crate::{path, re_err};
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Month and weekday names and number separators of a language
+pub struct Names {
+    months: [&'static str; 12],
+    months_short: [&'static str; 12],
+    /// Monday first
+    weekdays: [&'static str; 7],
+    weekdays_short: [&'static str; 7],
+    pub thousands: &'static str,
+    pub point: &'static str,
+}
+
+const EN: Names = Names {
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    months_short: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    weekdays: [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    weekdays_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    thousands: ",",
+    point: ".",
+};
+
+const DE: Names = Names {
+    months: [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    months_short: [
+        "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+    ],
+    weekdays: [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ],
+    weekdays_short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    thousands: ".",
+    point: ",",
+};
+
+const FR: Names = Names {
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    months_short: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+    weekdays: [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    weekdays_short: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    thousands: "\u{202f}",
+    point: ",",
+};
+
+const ES: Names = Names {
+    months: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    months_short: [
+        "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+    ],
+    weekdays: [
+        "lunes",
+        "martes",
+        "miércoles",
+        "jueves",
+        "viernes",
+        "sábado",
+        "domingo",
+    ],
+    weekdays_short: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    thousands: ".",
+    point: ",",
+};
+
+const IT: Names = Names {
+    months: [
+        "gennaio",
+        "febbraio",
+        "marzo",
+        "aprile",
+        "maggio",
+        "giugno",
+        "luglio",
+        "agosto",
+        "settembre",
+        "ottobre",
+        "novembre",
+        "dicembre",
+    ],
+    months_short: [
+        "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+    ],
+    weekdays: [
+        "lunedì",
+        "martedì",
+        "mercoledì",
+        "giovedì",
+        "venerdì",
+        "sabato",
+        "domenica",
+    ],
+    weekdays_short: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+    thousands: ".",
+    point: ",",
+};
+
+const NL: Names = Names {
+    months: [
+        "januari",
+        "februari",
+        "maart",
+        "april",
+        "mei",
+        "juni",
+        "juli",
+        "augustus",
+        "september",
+        "oktober",
+        "november",
+        "december",
+    ],
+    months_short: [
+        "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+    ],
+    weekdays: [
+        "maandag",
+        "dinsdag",
+        "woensdag",
+        "donderdag",
+        "vrijdag",
+        "zaterdag",
+        "zondag",
+    ],
+    weekdays_short: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+    thousands: ".",
+    point: ",",
+};
+
+const PT: Names = Names {
+    months: [
+        "janeiro",
+        "fevereiro",
+        "março",
+        "abril",
+        "maio",
+        "junho",
+        "julho",
+        "agosto",
+        "setembro",
+        "outubro",
+        "novembro",
+        "dezembro",
+    ],
+    months_short: [
+        "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+    ],
+    weekdays: [
+        "segunda-feira",
+        "terça-feira",
+        "quarta-feira",
+        "quinta-feira",
+        "sexta-feira",
+        "sábado",
+        "domingo",
+    ],
+    weekdays_short: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+    thousands: ".",
+    point: ",",
+};
+
+/// Language part of a tag: "de" for "de-AT" or "de_AT"
+fn language(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
+
+/// Locale of the run: formatting names and the translations for its tag
+#[derive(Default)]
+pub struct Locale {
+    /// Language tag, e.g. "de-AT" (empty: no locale)
+    pub tag: String,
+    /// Translation table of the locale (null without translations)
+    messages: Value,
+}
+
+impl Locale {
+    /// Locale for a tag, with its table from a translations file when one was given
+    pub fn new(tag: &str, translations: Option<&Value>) -> anyhow::Result<Self> {
+        let tag = tag.trim();
+        let messages = match translations {
+            None => Value::Null,
+            Some(_) if tag.is_empty() => {
+                anyhow::bail!("Translations need a locale (--locale or the locale setting)")
+            }
+            Some(Value::Object(tables)) => tables
+                .get(tag)
+                .or_else(|| tables.get(language(tag)))
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No translations for locale '{}'", tag))?,
+            Some(_) => anyhow::bail!("Translations must be an object of locale tables"),
+        };
+        Ok(Locale {
+            tag: tag.to_string(),
+            messages,
+        })
+    }
+
+    /// Names and separators of the locale's language (English when it has none)
+    pub fn names(&self) -> &'static Names {
+        match language(&self.tag).to_ascii_lowercase().as_str() {
+            "de" => &DE,
+            "fr" => &FR,
+            "es" => &ES,
+            "it" => &IT,
+            "nl" => &NL,
+            "pt" => &PT,
+            _ => &EN,
+        }
+    }
+
+    /// Replace the name specifiers of a strftime pattern (%B %b %h %A %a) with the
+    /// locale's names for a date; other specifiers are left to chrono
+    pub fn localize_format(&self, format: &str, month0: usize, weekday0: usize) -> String {
+        if self.tag.is_empty() {
+            return format.to_string();
+        }
+        let names = self.names();
+        let mut out = String::with_capacity(format.len() + 16);
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('B') => out.push_str(names.months[month0]),
+                Some('b' | 'h') => out.push_str(names.months_short[month0]),
+                Some('A') => out.push_str(names.weekdays[weekday0]),
+                Some('a') => out.push_str(names.weekdays_short[weekday0]),
+                Some(next) => {
+                    out.push('%');
+                    out.push(next);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+
+    /// Message for a key: a literal key first, then a dot path
+    fn message(&self, key: &str) -> Option<Value> {
+        self.messages
+            .get(key)
+            .cloned()
+            .or_else(|| path::select(&self.messages, key))
+    }
+}
+
+/// Plural form of a message object for a count: "zero" (if present), "one", "other"
+fn plural(forms: &serde_json::Map<String, Value>, count: Option<f64>) -> Option<&Value> {
+    let form = if count == Some(0.0) && forms.contains_key("zero") {
+        "zero"
+    } else if count == Some(1.0) {
+        "one"
+    } else {
+        "other"
+    };
+    forms.get(form).or_else(|| forms.get("other"))
+}
+
+/// {{t key [name=value ...] [count=n]}}
+struct TranslateHelper(Arc<Locale>);
+
+impl HelperDef for TranslateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let key = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or_else(|| re_err("t: translation key required"))?;
+        let count = h.hash_get("count").and_then(|p| match p.value() {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        });
+        let text = match self.0.message(key) {
+            Some(Value::String(text)) => text,
+            Some(Value::Object(forms)) => match plural(&forms, count) {
+                Some(Value::String(text)) => text.clone(),
+                _ => key.to_string(),
+            },
+            Some(Value::Null) | None => key.to_string(),
+            Some(other) => other.to_string(),
+        };
+        let text = h.hash().iter().fold(text, |text, (name, value)| {
+            let value = match value.value() {
+                Value::String(s) => s.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            text.replace(&format!("{{{}}}", name), &value)
+        });
+        Ok(ScopedJson::Derived(Value::String(text)))
+    }
+}
+
+/// The translation helper with its name
+pub fn helpers(locale: &Arc<Locale>) -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![("t", Box::new(TranslateHelper(Arc::clone(locale))))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn translations() -> Value {
+        json!({
+            "en": {"title": "Report"},
+            "de": {
+                "title": "Bericht",
+                "nav": {"home": "Start"},
+                "greeting": "Hallo {name}!",
+                "items": {"zero": "keine", "one": "{count} Eintrag", "other": "{count} Einträge"},
+                "lonely": {"other": "{count} allein"},
+                "bad": {"one": 1},
+                "nothing": null
+            }
+        })
+    }
+
+    fn render(template: &str, locale: Locale) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        for (name, helper) in helpers(&Arc::new(locale)) {
+            hb.register_helper(name, helper);
+        }
+        hb.render_template(template, &json!({"author": "Ada", "n": 3}))
+    }
+
+    #[test]
+    fn locales_fall_back_to_their_language_and_need_a_table() {
+        let table = translations();
+        assert_eq!(Locale::new(" de-AT ", Some(&table)).unwrap().tag, "de-AT");
+        assert_eq!(Locale::new("de_CH", None).unwrap().names().point, ",");
+        assert_eq!(Locale::new("ja", None).unwrap().names().point, ".");
+        assert!(Locale::new("fr", Some(&table)).is_err());
+        assert!(Locale::new("", Some(&table)).is_err());
+        assert!(Locale::new("de", Some(&json!(["de"]))).is_err());
+    }
+
+    #[test]
+    fn t_fills_placeholders_and_picks_plural_forms() {
+        let de = || Locale::new("de-AT", Some(&translations())).unwrap();
+        assert_eq!(
+            render(
+                r#"{{t "title"}}|{{t "nav.home"}}|{{t "greeting" name=author}}"#,
+                de()
+            )
+            .unwrap(),
+            "Bericht|Start|Hallo Ada!"
+        );
+        assert_eq!(
+            render(
+                r#"{{t "items" count=0}}|{{t "items" count="1"}}|{{t "items" count=n}}"#,
+                de()
+            )
+            .unwrap(),
+            "keine|1 Eintrag|3 Einträge"
+        );
+        assert_eq!(
+            render(r#"{{t "lonely" count=1}}"#, de()).unwrap(),
+            "1 allein"
+        );
+        // Missing keys and unusable messages render as the key
+        assert_eq!(
+            render(
+                r#"{{t "missing"}}|{{t "nothing"}}|{{t "bad" count=1}}"#,
+                de()
+            )
+            .unwrap(),
+            "missing|nothing|bad"
+        );
+        assert_eq!(
+            render(r#"{{t "title"}}"#, Locale::default()).unwrap(),
+            "title"
+        );
+        assert!(render("{{t}}", de()).is_err());
+        assert!(render("{{t n}}", de()).is_err());
+    }
+
+    #[test]
+    fn formats_get_localized_names_only_with_a_locale() {
+        let fr = Locale::new("fr", None).unwrap();
+        // March (month 2) on a Sunday (weekday 6)
+        assert_eq!(
+            fr.localize_format("%A %d %B (%a, %b) %Y %%B %", 2, 6),
+            "dimanche %d mars (dim., mars) %Y %%B %"
+        );
+        assert_eq!(Locale::default().localize_format("%B", 2, 6), "%B");
+    }
+}
@@ -0,0 +1,1525 @@
+//! Input data loading: format detection and conversion to `serde_json::Value`.
+//!
+//! Text formats (JSON, JSON5/JSONC, CSV/TSV, fixed-width, YAML, TOML, XML) are always available.
+//! A directory of Markdown notes is read as one item per note (frontmatter + body).
+//! HTTP(S) URLs require --features http
+//! Excel workbooks require --features xlsx
+//! SQLite databases require --features sqlite (used with --query)
+//! Parquet files require --features parquet (optionally projected with --columns)
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Input data format, detected from the file extension (or forced with --format)
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum DataFormat {
+    Json,
+    /// Lenient JSON: comments, trailing commas, unquoted keys (also covers JSONC)
+    Json5,
+    Csv,
+    /// Tab-separated values (CSV options apply; the delimiter defaults to tab)
+    Tsv,
+    /// Fixed-width text, split by the column offsets in settings fixed_width_columns
+    FixedWidth,
+    Yaml,
+    Toml,
+    Xml,
+    Xlsx,
+    Sqlite,
+    Parquet,
+    /// Markdown notes with YAML frontmatter (a directory or a single .md file)
+    Markdown,
+}
+
+impl DataFormat {
+    /// Detect format by extension; anything unrecognized is parsed as JSON
+    pub fn from_path(path: &Path) -> Self {
+        if path.is_dir() {
+            return Self::Markdown;
+        }
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("json5" | "jsonc") => Self::Json5,
+            Some("csv") => Self::Csv,
+            Some("tsv" | "tab") => Self::Tsv,
+            Some("fwf") => Self::FixedWidth,
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            Some("xml") => Self::Xml,
+            Some("xlsx" | "xlsm" | "xls" | "ods") => Self::Xlsx,
+            Some("sqlite" | "sqlite3" | "db") => Self::Sqlite,
+            Some("parquet" | "pq") => Self::Parquet,
+            Some("md" | "markdown") => Self::Markdown,
+            _ => Self::Json,
+        }
+    }
+
+    /// Guess format from an HTTP Content-Type header; defaults to JSON
+    fn from_content_type(content_type: &str) -> Self {
+        let mime = content_type.to_ascii_lowercase();
+        if mime.contains("csv") {
+            Self::Csv
+        } else if mime.contains("yaml") {
+            Self::Yaml
+        } else if mime.contains("toml") {
+            Self::Toml
+        } else if mime.contains("xml") {
+            Self::Xml
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// How CSV cell values are typed
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum CsvTypes {
+    /// Every cell is a string
+    #[default]
+    String,
+    /// Numbers and booleans are converted, empty cells become null
+    Auto,
+}
+
+/// Format-specific parsing options
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+    /// Worksheet to read from a workbook (defaults to the first sheet)
+    #[cfg_attr(not(feature = "xlsx"), allow(dead_code))]
+    pub sheet: Option<String>,
+    /// SQL query whose result set becomes the item array (SQLite input)
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    pub query: Option<String>,
+    /// Columns to read from columnar input (Parquet); empty reads all columns
+    #[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+    pub columns: Vec<String>,
+    /// Extra request headers for URL sources ("Name: value")
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub headers: Vec<String>,
+    /// Directory caching URL responses between runs
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub cache_dir: Option<PathBuf>,
+    /// Follow next-page links for URL sources; the value is the items field in each
+    /// page (empty when pages are arrays)
+    pub paginate: Option<String>,
+    /// Default typing of CSV cells
+    pub csv_types: CsvTypes,
+    /// Per-column CSV type overrides (string, auto, int, float, bool)
+    pub csv_column_types: HashMap<String, String>,
+    /// CSV field delimiter (default ',')
+    pub csv_delimiter: Option<u8>,
+    /// CSV quote character (default '"')
+    pub csv_quote: Option<u8>,
+    /// CSV escape character inside quotes (default: doubled quotes)
+    pub csv_escape: Option<u8>,
+    /// Treat the first CSV row as data rather than headers
+    pub csv_no_headers: bool,
+    /// Column names replacing the CSV header row (missing ones become colN)
+    pub csv_column_names: Vec<String>,
+    /// Column layout for fixed-width text input
+    pub fixed_width_columns: Vec<FixedWidthColumn>,
+    /// Expand dotted column names ("user.name") into nested objects
+    pub csv_nested: bool,
+    /// Character encoding label for text input (None: detect)
+    pub encoding: Option<String>,
+}
+
+/// Check whether a data source argument is an HTTP(S) URL
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Expand data source arguments: glob patterns (quoted so the shell leaves them alone)
+/// become their sorted matches; URLs and plain paths pass through unchanged
+pub fn expand_sources(args: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    for arg in args {
+        let pattern = arg.to_string_lossy();
+        if is_url(&pattern) || arg.exists() || !pattern.contains(['*', '?', '[']) {
+            sources.push(arg.clone());
+            continue;
+        }
+        sources.extend(glob_sources(&pattern)?);
+    }
+    Ok(sources)
+}
+
+/// Sorted files matching a glob pattern (`**` recurses); errors when nothing matches
+pub fn glob_sources(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut matches = glob::glob(pattern)
+        .with_context(|| format!("Invalid glob pattern: {}", pattern))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    if matches.is_empty() {
+        anyhow::bail!("No data files match: {}", pattern);
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Fetch and parse a URL; format comes from the override, the URL extension, or Content-Type.
+/// With `paginate` set, follows next-page links and concatenates page items into one array.
+pub fn load_url(url: &str, format: Option<DataFormat>, opts: &InputOptions) -> Result<Value> {
+    let Some(items_field) = &opts.paginate else {
+        return fetch_page(url, format, opts).map(|(page, _)| page);
+    };
+
+    let mut items = Vec::new();
+    let mut visited = HashSet::new();
+    let mut next = Some(url.to_string());
+    while let Some(page_url) = next.take() {
+        if !visited.insert(page_url.clone()) {
+            break; // pagination loop
+        }
+        let (page, link) = fetch_page(&page_url, format, opts)?;
+
+        // JSON `next` field wins over the Link header
+        next = match page.get("next") {
+            Some(Value::String(s)) if !s.is_empty() => Some(resolve_url(&page_url, s)),
+            _ => link.map(|l| resolve_url(&page_url, &l)),
+        };
+
+        let page_items = if items_field.is_empty() {
+            page
+        } else {
+            crate::objfield(&page, items_field, None)
+                .with_context(|| format!("Page {} has no '{}' field", page_url, items_field))?
+        };
+        match page_items {
+            Value::Array(arr) => items.extend(arr),
+            Value::Object(_) => anyhow::bail!(
+                "Page {} is an object: pass the items field, e.g. --paginate results",
+                page_url
+            ),
+            other => items.push(other),
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+/// Fetch one URL, returning the parsed body and its rel="next" link if any
+fn fetch_page(
+    url: &str,
+    format: Option<DataFormat>,
+    opts: &InputOptions,
+) -> Result<(Value, Option<String>)> {
+    let response = http_get(url, opts)?;
+    let format = format.unwrap_or_else(|| {
+        let url_path = url.split(['?', '#']).next().unwrap_or(url);
+        let has_ext = Path::new(url_path)
+            .extension()
+            .is_some_and(|e| e.len() <= 7 && url_path.rsplit('/').next() != Some(""));
+        if has_ext {
+            DataFormat::from_path(Path::new(url_path))
+        } else {
+            DataFormat::from_content_type(response.content_type.as_deref().unwrap_or(""))
+        }
+    });
+    let raw = response
+        .body
+        .strip_prefix('\u{feff}')
+        .unwrap_or(&response.body);
+    let page = parse_text(raw, format, opts)
+        .with_context(|| format!("Failed to parse response from {}", url))?;
+    let next = response.link.as_deref().and_then(parse_next_link);
+    Ok((page, next))
+}
+
+/// Extract the rel="next" target from a Link header
+fn parse_next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        let is_next = params.split(';').any(|p| {
+            let p = p.trim().replace(' ', "");
+            p == "rel=\"next\"" || p == "rel=next"
+        });
+        is_next.then(|| {
+            target
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+    })
+}
+
+/// Resolve a possibly relative link against the page it came from
+fn resolve_url(base: &str, link: &str) -> String {
+    if is_url(link) {
+        return link.to_string();
+    }
+    let scheme_end = base.find("://").map_or(0, |i| i + 3);
+    if let Some(path) = link.strip_prefix('/') {
+        let host_end = base[scheme_end..]
+            .find('/')
+            .map_or(base.len(), |i| scheme_end + i);
+        return format!("{}/{}", &base[..host_end], path);
+    }
+    let base = base.split(['?', '#']).next().unwrap_or(base);
+    if let Some(query) = link.strip_prefix('?') {
+        return format!("{}?{}", base, query);
+    }
+    let dir_end = base
+        .rfind('/')
+        .filter(|&i| i >= scheme_end)
+        .map_or(base.len(), |i| i);
+    format!("{}/{}", &base[..dir_end], link)
+}
+
+/// Read and parse a data file into a JSON value
+pub fn load_data(path: &Path, format: DataFormat, opts: &InputOptions) -> Result<Value> {
+    match format {
+        DataFormat::Xlsx => return load_workbook(path, opts),
+        DataFormat::Sqlite => return load_sqlite(path, opts),
+        DataFormat::Parquet => return load_parquet(path, opts),
+        DataFormat::Markdown if path.is_dir() => return load_markdown_dir(path),
+        _ => {}
+    }
+
+    let bytes =
+        fs::read(path).with_context(|| format!("Failed to read data file: {}", path.display()))?;
+    let raw = decode_text(&bytes, opts.encoding.as_deref())
+        .with_context(|| format!("Failed to decode data file: {}", path.display()))?;
+    parse_text(&raw, format, opts)
+}
+
+/// Stream the elements of a top-level JSON array from a file, calling `f` for each one
+/// without holding the array in memory. `f` returns false to stop rendering; the rest
+/// of the file is then only validated. With an encoding label the file is transcoded
+/// as it is read (see `decode_text`); otherwise it must be UTF-8.
+pub fn stream_json_array(
+    path: &Path,
+    encoding: Option<&str>,
+    mut f: impl FnMut(Value) -> Result<bool>,
+) -> Result<()> {
+    use serde::de::{Deserializer, Error as _, IgnoredAny, SeqAccess, Visitor};
+    use std::io::{BufRead, BufReader, Read};
+
+    struct ArrayVisitor<'f, F> {
+        f: &'f mut F,
+        failure: &'f mut Option<anyhow::Error>,
+    }
+
+    impl<'de, F: FnMut(Value) -> Result<bool>> Visitor<'de> for ArrayVisitor<'_, F> {
+        type Value = ();
+
+        fn expecting(&self, out: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            out.write_str("a top-level JSON array")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            while let Some(item) = seq.next_element::<Value>()? {
+                match (self.f)(item) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        while seq.next_element::<IgnoredAny>()?.is_some() {}
+                        break;
+                    }
+                    Err(err) => {
+                        // Keep the real error; serde only carries a message
+                        *self.failure = Some(err);
+                        return Err(A::Error::custom("item processing failed"));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open data file: {}", path.display()))?;
+    let reader: Box<dyn Read> = match encoding {
+        Some(label) => {
+            let encoding = encoding_rs::Encoding::for_label(label.trim().as_bytes())
+                .with_context(|| format!("Unknown encoding '{}'", label))?;
+            Box::new(BufReader::new(DecodingReader::new(file, encoding)))
+        }
+        None => {
+            let mut reader = BufReader::new(file);
+            // Skip a UTF-8 BOM
+            if reader.fill_buf()?.starts_with(b"\xef\xbb\xbf") {
+                reader.consume(3);
+            }
+            Box::new(reader)
+        }
+    };
+
+    let mut failure = None;
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let result = de.deserialize_seq(ArrayVisitor {
+        f: &mut f,
+        failure: &mut failure,
+    });
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    result
+        .and_then(|_| de.end())
+        .with_context(|| format!("JSON stream parse failed: {}", path.display()))
+}
+
+/// Reader transcoding another encoding to UTF-8 as it goes, for streamed input. Like
+/// `decode_text`, a BOM overrides the label and malformed input is an error.
+struct DecodingReader<R> {
+    inner: R,
+    decoder: encoding_rs::Decoder,
+    input: Vec<u8>,
+    input_range: std::ops::Range<usize>,
+    output: Vec<u8>,
+    output_range: std::ops::Range<usize>,
+    eof: bool,
+}
+
+impl<R: std::io::Read> DecodingReader<R> {
+    fn new(inner: R, encoding: &'static encoding_rs::Encoding) -> Self {
+        DecodingReader {
+            inner,
+            decoder: encoding.new_decoder(),
+            input: vec![0; 8192],
+            input_range: 0..0,
+            output: vec![0; 8192],
+            output_range: 0..0,
+            eof: false,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use encoding_rs::DecoderResult;
+
+        loop {
+            if !self.output_range.is_empty() {
+                let n = self.output_range.len().min(buf.len());
+                let start = self.output_range.start;
+                buf[..n].copy_from_slice(&self.output[start..start + n]);
+                self.output_range.start += n;
+                return Ok(n);
+            }
+            if self.input_range.is_empty() {
+                if self.eof {
+                    return Ok(0);
+                }
+                let n = self.inner.read(&mut self.input)?;
+                self.input_range = 0..n;
+                self.eof = n == 0;
+            }
+            let (result, read, written) = self.decoder.decode_to_utf8_without_replacement(
+                &self.input[self.input_range.clone()],
+                &mut self.output,
+                self.eof,
+            );
+            self.input_range.start += read;
+            self.output_range = 0..written;
+            if let DecoderResult::Malformed(..) = result {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Input is not valid {}", self.decoder.encoding().name()),
+                ));
+            }
+        }
+    }
+}
+
+/// Transcode input bytes to UTF-8 (dropping any BOM). Without an explicit encoding label,
+/// a BOM decides; otherwise NUL-heavy input is read as UTF-16, valid UTF-8 is used as is
+/// and anything else is read as Windows-1252 (Excel's usual export encoding)
+pub fn decode_text(bytes: &[u8], encoding: Option<&str>) -> Result<String> {
+    use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+    // UTF-16 text without BOM has a NUL in most ASCII code units (and ASCII-only UTF-16
+    // is also valid UTF-8, so this goes first)
+    let nul_at = |parity: usize| {
+        bytes
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let half = bytes.len() / 2;
+    let encoding = match encoding {
+        Some(label) => Encoding::for_label(label.trim().as_bytes())
+            .with_context(|| format!("Unknown encoding '{}'", label))?,
+        None => match Encoding::for_bom(bytes) {
+            Some((bom_encoding, _)) => bom_encoding,
+            None if half > 0 && nul_at(1) * 2 > half => UTF_16LE,
+            None if half > 0 && nul_at(0) * 2 > half => UTF_16BE,
+            None if std::str::from_utf8(bytes).is_ok() => encoding_rs::UTF_8,
+            None => WINDOWS_1252,
+        },
+    };
+
+    // decode() sniffs and strips a BOM, overriding the label like browsers do
+    let (text, used, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        anyhow::bail!("Input is not valid {}", used.name());
+    }
+    Ok(text.into_owned())
+}
+
+/// Parse text input in the given format
+pub fn parse_text(raw: &str, format: DataFormat, opts: &InputOptions) -> Result<Value> {
+    match format {
+        DataFormat::Csv => parse_csv(raw, b',', opts),
+        DataFormat::Tsv => parse_csv(raw, b'\t', opts),
+        DataFormat::FixedWidth => parse_fixed_width(raw, opts),
+        DataFormat::Yaml => serde_yaml::from_str(raw).context("YAML parse failed"),
+        DataFormat::Toml => Ok(toml_to_json(toml::Value::Table(
+            toml::from_str(raw).context("TOML parse failed")?,
+        ))),
+        DataFormat::Xml => parse_xml(raw),
+        DataFormat::Json5 => json5::from_str(raw).context("JSON5 parse failed"),
+        DataFormat::Markdown => Ok(Value::Array(vec![parse_markdown_note(raw)?])),
+        DataFormat::Json => serde_json::from_str(raw).with_context(|| {
+            let first_line = raw.lines().next().unwrap_or("");
+            format!("JSON parse failed. First line: {:?}", first_line)
+        }),
+        DataFormat::Xlsx | DataFormat::Sqlite | DataFormat::Parquet => {
+            anyhow::bail!("{:?} input is binary and cannot be parsed as text", format)
+        }
+    }
+}
+
+/// Parse CSV into an array of objects keyed by the header row (or by --column-names /
+/// col0, col1, ... with --no-headers); cells stay strings unless type inference or a
+/// per-column type applies
+fn parse_csv(raw: &str, default_delimiter: u8, opts: &InputOptions) -> Result<Value> {
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .has_headers(!opts.csv_no_headers)
+        .delimiter(opts.csv_delimiter.unwrap_or(default_delimiter))
+        .quote(opts.csv_quote.unwrap_or(b'"'));
+    if let Some(escape) = opts.csv_escape {
+        builder.escape(Some(escape)).double_quote(false);
+    }
+    let mut rdr = builder.from_reader(raw.as_bytes());
+
+    let file_headers: Vec<String> = if opts.csv_no_headers {
+        Vec::new()
+    } else {
+        rdr.headers()
+            .with_context(|| "CSV: failed to read headers")?
+            .iter()
+            .map(String::from)
+            .collect()
+    };
+    // Explicit names win, then the header row, then positional colN keys
+    let header = |i: usize| -> String {
+        opts.csv_column_names
+            .get(i)
+            .or_else(|| file_headers.get(i))
+            .cloned()
+            .unwrap_or_else(|| format!("col{}", i))
+    };
+    let first_data_line = if opts.csv_no_headers { 1 } else { 2 };
+
+    let mut rows = Vec::new();
+    for (line_num, record) in rdr.records().enumerate() {
+        let line = line_num + first_data_line;
+        let record = record.with_context(|| format!("CSV: error on line {}", line))?;
+        let cells = record.iter().enumerate().map(|(i, f)| (header(i), f));
+        rows.push(row_to_object(cells, line, opts).context("CSV")?);
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Build an item from (column, cell) pairs, applying --csv-types and csv_column_types
+fn row_to_object<'a>(
+    cells: impl Iterator<Item = (String, &'a str)>,
+    line: usize,
+    opts: &InputOptions,
+) -> Result<Value> {
+    let mut map = serde_json::Map::new();
+    for (h, f) in cells {
+        let kind =
+            opts.csv_column_types
+                .get(&h)
+                .map(String::as_str)
+                .unwrap_or(match opts.csv_types {
+                    CsvTypes::String => "string",
+                    CsvTypes::Auto => "auto",
+                });
+        let value =
+            csv_cell(f, kind).with_context(|| format!("column '{}' on line {}", h, line))?;
+        if opts.csv_nested && h.contains('.') {
+            insert_nested(&mut map, &h, value);
+        } else {
+            map.insert(h, value);
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+/// Insert under a dotted column name ("address.city") as nested objects; if a prefix is
+/// already taken by a non-object value the flat key is kept instead
+fn insert_nested(map: &mut serde_json::Map<String, Value>, dotted: &str, value: Value) {
+    let mut parts: Vec<&str> = dotted.split('.').collect();
+    let leaf = parts.pop().unwrap_or_default();
+    let mut current = &mut *map;
+    for part in parts {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        match entry {
+            Value::Object(obj) => current = obj,
+            _ => {
+                map.insert(dotted.to_string(), value);
+                return;
+            }
+        }
+    }
+    current.insert(leaf.to_string(), value);
+}
+
+/// Column of a fixed-width layout: `start` is a 0-based character offset, `width`
+/// runs to the end of the line when omitted
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    pub start: usize,
+    #[serde(default)]
+    pub width: Option<usize>,
+}
+
+/// Parse fixed-width text: one item per non-blank line, cells trimmed of padding
+fn parse_fixed_width(raw: &str, opts: &InputOptions) -> Result<Value> {
+    if opts.fixed_width_columns.is_empty() {
+        anyhow::bail!("Fixed-width input needs fixed_width_columns in settings");
+    }
+    let mut rows = Vec::new();
+    for (line_num, line) in raw.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let cells: Vec<(String, String)> = opts
+            .fixed_width_columns
+            .iter()
+            .map(|col| {
+                let start = col.start.min(chars.len());
+                let end = col
+                    .width
+                    .map_or(chars.len(), |w| (col.start + w).min(chars.len()));
+                let cell: String = chars[start..end.max(start)].iter().collect();
+                (col.name.clone(), cell.trim().to_string())
+            })
+            .collect();
+        let cells = cells.iter().map(|(h, f)| (h.clone(), f.as_str()));
+        rows.push(row_to_object(cells, line_num + 1, opts).context("Fixed-width")?);
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Parse a single-byte CSV character argument: one ASCII character, or "tab" / "\\t"
+pub fn parse_csv_char(arg: &str) -> Result<u8, String> {
+    match arg {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if arg.len() == 1 && arg.is_ascii() => Ok(arg.as_bytes()[0]),
+        _ => Err(format!("expected a single ASCII character, got '{}'", arg)),
+    }
+}
+
+/// Convert a CSV cell to the requested type: string, auto, int/integer, float/number,
+/// bool/boolean. Empty cells become null for every type except string.
+fn csv_cell(cell: &str, kind: &str) -> Result<Value> {
+    let trimmed = cell.trim();
+    if kind != "string" && trimmed.is_empty() {
+        return Ok(Value::Null);
+    }
+    let parse_bool = |s: &str| match s.to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    };
+    Ok(match kind {
+        "string" => Value::String(cell.to_string()),
+        "int" | "integer" => Value::from(
+            trimmed
+                .parse::<i64>()
+                .with_context(|| format!("'{}' is not an integer", cell))?,
+        ),
+        "float" | "number" => serde_json::Number::from_f64(
+            trimmed
+                .parse::<f64>()
+                .with_context(|| format!("'{}' is not a number", cell))?,
+        )
+        .map_or(Value::Null, Value::Number),
+        "bool" | "boolean" => Value::Bool(
+            parse_bool(trimmed).with_context(|| format!("'{}' is not a boolean", cell))?,
+        ),
+        "auto" => infer_cell(trimmed).unwrap_or_else(|| Value::String(cell.to_string())),
+        other => anyhow::bail!("unknown CSV column type '{}'", other),
+    })
+}
+
+/// Infer a number or boolean from a cell; leading-zero values like ZIP codes stay strings
+fn infer_cell(cell: &str) -> Option<Value> {
+    match cell {
+        "true" | "TRUE" | "True" => return Some(Value::Bool(true)),
+        "false" | "FALSE" | "False" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    let digits = cell.trim_start_matches(['-', '+']);
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return None;
+    }
+    if let Ok(n) = cell.parse::<i64>() {
+        return Some(Value::from(n));
+    }
+    if !cell.bytes().any(|b| b.is_ascii_digit()) {
+        return None; // "inf", "NaN" and friends
+    }
+    cell.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+}
+
+/// Convert a TOML document to JSON, rendering datetimes as their TOML string form
+fn toml_to_json(val: toml::Value) -> Value {
+    match val {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(arr) => Value::Array(arr.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parse XML into a JSON tree rooted at `{rootName: ...}`.
+///
+/// - attributes become `@name` keys
+/// - text content becomes `#text` (or the whole value for text-only elements)
+/// - repeated child elements are collected into arrays
+/// - empty elements without attributes become null
+///
+/// Templates reach these keys with segment literals: `{{[@id]}}`, `{{title.[#text]}}`
+fn parse_xml(raw: &str) -> Result<Value> {
+    let doc = roxmltree::Document::parse(raw).context("XML parse failed")?;
+    let root = doc.root_element();
+    let mut map = serde_json::Map::new();
+    map.insert(
+        root.tag_name().name().to_string(),
+        xml_element_to_json(root),
+    );
+    Ok(Value::Object(map))
+}
+
+fn xml_element_to_json(node: roxmltree::Node<'_, '_>) -> Value {
+    let mut map = serde_json::Map::new();
+    for attr in node.attributes() {
+        map.insert(
+            format!("@{}", attr.name()),
+            Value::String(attr.value().to_string()),
+        );
+    }
+
+    let mut text = String::new();
+    for child in node.children() {
+        if child.is_element() {
+            let name = child.tag_name().name().to_string();
+            let val = xml_element_to_json(child);
+            match map.get_mut(&name) {
+                Some(Value::Array(arr)) => arr.push(val),
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = Value::Array(vec![first, val]);
+                }
+                None => {
+                    map.insert(name, val);
+                }
+            }
+        } else if let Some(t) = child.text() {
+            text.push_str(t);
+        }
+    }
+
+    let text = text.trim();
+    if map.is_empty() {
+        return if text.is_empty() {
+            Value::Null
+        } else {
+            Value::String(text.to_string())
+        };
+    }
+    if !text.is_empty() {
+        map.insert("#text".to_string(), Value::String(text.to_string()));
+    }
+    Value::Object(map)
+}
+
+/// Read every .md file under a directory (recursively, sorted by path) as one item.
+/// Each item holds the frontmatter fields, `body`, and `file` (path relative to the directory).
+fn load_markdown_dir(dir: &Path) -> Result<Value> {
+    let mut files = Vec::new();
+    collect_markdown_files(dir, &mut files)?;
+    files.sort();
+
+    let mut items = Vec::new();
+    for file in files {
+        let raw = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read note: {}", file.display()))?;
+        let raw = raw.strip_prefix('\u{feff}').unwrap_or(&raw);
+        let mut note = parse_markdown_note(raw)
+            .with_context(|| format!("Invalid frontmatter in {}", file.display()))?;
+        if let Value::Object(map) = &mut note {
+            let rel = file.strip_prefix(dir).unwrap_or(&file);
+            map.insert(
+                "file".to_string(),
+                Value::String(rel.to_string_lossy().replace('\\', "/")),
+            );
+        }
+        items.push(note);
+    }
+    Ok(Value::Array(items))
+}
+
+fn collect_markdown_files(dir: &Path, out: &mut Vec<std::path::PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_markdown_files(&path, out)?;
+        } else if DataFormat::from_path(&path) == DataFormat::Markdown {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Split a note into its YAML frontmatter fields plus a `body` field
+fn parse_markdown_note(raw: &str) -> Result<Value> {
+    let mut map = serde_json::Map::new();
+    let mut body = raw;
+
+    if let Some((yaml, rest)) = split_frontmatter(raw) {
+        if let Value::Object(fields) = serde_yaml::from_str(yaml)? {
+            map.extend(fields);
+        }
+        body = rest;
+    }
+
+    map.insert("body".to_string(), Value::String(body.to_string()));
+    Ok(Value::Object(map))
+}
+
+/// Split a note into its YAML frontmatter text and the body after the closing `---`
+/// (or `...`); None without frontmatter
+pub fn split_frontmatter(raw: &str) -> Option<(&str, &str)> {
+    let rest = raw
+        .strip_prefix("---\n")
+        .or_else(|| raw.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Stub implementation when xlsx feature is disabled
+#[cfg(not(feature = "xlsx"))]
+fn load_workbook(_path: &Path, _opts: &InputOptions) -> Result<Value> {
+    anyhow::bail!("Excel input requires: cargo build --features xlsx")
+}
+
+/// Read one worksheet into an array of objects keyed by the header row
+#[cfg(feature = "xlsx")]
+fn load_workbook(path: &Path, opts: &InputOptions) -> Result<Value> {
+    use calamine::{Data, Reader, open_workbook_auto};
+
+    let mut workbook = open_workbook_auto(path)
+        .with_context(|| format!("Failed to open workbook: {}", path.display()))?;
+
+    let sheet = match &opts.sheet {
+        Some(name) => name.clone(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .context("Workbook has no sheets")?,
+    };
+    let range = workbook.worksheet_range(&sheet).with_context(|| {
+        format!(
+            "Sheet '{}' not found (available: {})",
+            sheet,
+            workbook.sheet_names().join(", ")
+        )
+    })?;
+
+    let cell_to_json = |cell: &Data| -> Value {
+        match cell {
+            Data::Int(i) => Value::from(*i),
+            // Excel stores all numbers as floats; keep whole numbers integral
+            Data::Float(f) if f.fract() == 0.0 && f.abs() < 1e15 => Value::from(*f as i64),
+            Data::Float(f) => Value::from(*f),
+            Data::String(s) => Value::String(s.clone()),
+            Data::Bool(b) => Value::Bool(*b),
+            Data::DateTime(dt) => dt
+                .as_datetime()
+                .map(|d| Value::String(d.format("%Y-%m-%dT%H:%M:%S").to_string()))
+                .unwrap_or_else(|| Value::from(dt.as_f64())),
+            Data::DateTimeIso(s) | Data::DurationIso(s) => Value::String(s.clone()),
+            Data::Error(e) => Value::String(format!("#{:?}", e)),
+            Data::Empty => Value::Null,
+        }
+    };
+
+    let mut rows = range.rows();
+    let headers: Vec<String> = match rows.next() {
+        Some(header_row) => header_row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| match cell {
+                Data::Empty => format!("col{}", i),
+                other => other.to_string(),
+            })
+            .collect(),
+        None => return Ok(Value::Array(Vec::new())),
+    };
+
+    let items = rows
+        .filter(|row| row.iter().any(|cell| *cell != Data::Empty))
+        .map(|row| {
+            let map = headers
+                .iter()
+                .zip(row.iter())
+                .map(|(h, cell)| (h.clone(), cell_to_json(cell)))
+                .collect();
+            Value::Object(map)
+        })
+        .collect();
+    Ok(Value::Array(items))
+}
+
+/// Stub implementation when sqlite feature is disabled
+#[cfg(not(feature = "sqlite"))]
+fn load_sqlite(_path: &Path, _opts: &InputOptions) -> Result<Value> {
+    anyhow::bail!("SQLite input requires: cargo build --features sqlite")
+}
+
+/// Run the --query against a SQLite database, one object per result row
+#[cfg(feature = "sqlite")]
+fn load_sqlite(path: &Path, opts: &InputOptions) -> Result<Value> {
+    use rusqlite::types::ValueRef;
+    use rusqlite::{Connection, OpenFlags};
+
+    let query = opts
+        .query
+        .as_deref()
+        .context("SQLite input requires --query \"SELECT ...\"")?;
+
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open database: {}", path.display()))?;
+    let mut stmt = conn
+        .prepare(query)
+        .with_context(|| format!("Invalid query: {}", query))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    let mut rows = stmt.query([]).context("Query failed")?;
+    let mut items = Vec::new();
+    while let Some(row) = rows.next().context("Failed to read row")? {
+        let mut map = serde_json::Map::new();
+        for (i, name) in columns.iter().enumerate() {
+            let val = match row.get_ref(i)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(n) => Value::from(n),
+                ValueRef::Real(f) => Value::from(f),
+                ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+                ValueRef::Blob(b) => Value::String(String::from_utf8_lossy(b).into_owned()),
+            };
+            map.insert(name.clone(), val);
+        }
+        items.push(Value::Object(map));
+    }
+    Ok(Value::Array(items))
+}
+
+/// Stub implementation when parquet feature is disabled
+#[cfg(not(feature = "parquet"))]
+fn load_parquet(_path: &Path, _opts: &InputOptions) -> Result<Value> {
+    anyhow::bail!("Parquet input requires: cargo build --features parquet")
+}
+
+/// Read Parquet rows as objects, projected to --columns when given
+#[cfg(feature = "parquet")]
+fn load_parquet(path: &Path, opts: &InputOptions) -> Result<Value> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::schema::types::Type;
+    use std::sync::Arc;
+
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open Parquet file: {}", path.display()))?;
+    let reader = SerializedFileReader::new(file).context("Invalid Parquet file")?;
+
+    let projection = if opts.columns.is_empty() {
+        None
+    } else {
+        let schema = reader.metadata().file_metadata().schema();
+        let mut fields = Vec::new();
+        for column in &opts.columns {
+            let field = schema
+                .get_fields()
+                .iter()
+                .find(|f| f.name() == column)
+                .with_context(|| format!("Parquet column '{}' not found", column))?;
+            fields.push(Arc::clone(field));
+        }
+        Some(
+            Type::group_type_builder(schema.name())
+                .with_fields(fields)
+                .build()
+                .context("Failed to build Parquet projection")?,
+        )
+    };
+
+    let rows = reader
+        .get_row_iter(projection)
+        .context("Failed to read Parquet rows")?;
+    let items = rows
+        .map(|row| row.map(|r| r.to_json_value()))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to decode Parquet row")?;
+    Ok(Value::Array(items))
+}
+
+/// Body and relevant headers of a fetched URL
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+struct HttpResponse {
+    body: String,
+    content_type: Option<String>,
+    /// Raw Link header (pagination)
+    link: Option<String>,
+}
+
+/// Stub implementation when http feature is disabled
+#[cfg(not(feature = "http"))]
+fn http_get(_url: &str, _opts: &InputOptions) -> Result<HttpResponse> {
+    anyhow::bail!("URL data sources require: cargo build --features http")
+}
+
+/// GET a URL with the configured headers, serving from and filling the cache when enabled
+#[cfg(feature = "http")]
+fn http_get(url: &str, opts: &InputOptions) -> Result<HttpResponse> {
+    cached_get(url, opts, || fetch_url(url, opts))
+}
+
+/// Cache file name stem for a URL and its request headers (auth tokens may change the
+/// response): 64-bit FNV-1a, so the key is the same across builds and Rust versions
+fn cache_key(url: &str, headers: &[String]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in std::iter::once(url).chain(headers.iter().map(String::as_str)) {
+        // A NUL after each part keeps ("ab", "c") and ("a", "bc") apart
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Serve a response from the cache directory when enabled and present; otherwise `fetch`
+/// it and store it there
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+fn cached_get(
+    url: &str,
+    opts: &InputOptions,
+    fetch: impl FnOnce() -> Result<HttpResponse>,
+) -> Result<HttpResponse> {
+    let cache_paths = opts.cache_dir.as_ref().map(|dir| {
+        let key = cache_key(url, &opts.headers);
+        (
+            dir.join(format!("{}.body", key)),
+            dir.join(format!("{}.type", key)),
+            dir.join(format!("{}.link", key)),
+        )
+    });
+    if let Some((body_path, type_path, link_path)) = &cache_paths
+        && let Ok(body) = fs::read_to_string(body_path)
+    {
+        return Ok(HttpResponse {
+            body,
+            content_type: fs::read_to_string(type_path).ok(),
+            link: fs::read_to_string(link_path).ok(),
+        });
+    }
+
+    let response = fetch()?;
+    if let Some((body_path, type_path, link_path)) = &cache_paths {
+        if let Some(dir) = body_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(body_path, &response.body)?;
+        if let Some(ct) = &response.content_type {
+            fs::write(type_path, ct)?;
+        }
+        if let Some(link) = &response.link {
+            fs::write(link_path, link)?;
+        }
+    }
+    Ok(response)
+}
+
+/// GET a URL with the configured headers
+#[cfg(feature = "http")]
+fn fetch_url(url: &str, opts: &InputOptions) -> Result<HttpResponse> {
+    let mut request = ureq::get(url);
+    for header in &opts.headers {
+        let (name, value) = header
+            .split_once(':')
+            .with_context(|| format!("Invalid header '{}': expected 'Name: value'", header))?;
+        request = request.header(name.trim(), value.trim());
+    }
+    let mut response = request
+        .call()
+        .with_context(|| format!("HTTP request failed: {}", url))?;
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let link = response
+        .headers()
+        .get("link")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_string()
+        .with_context(|| format!("Failed to read response body: {}", url))?;
+    Ok(HttpResponse {
+        body,
+        content_type,
+        link,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn yaml_files_parse_to_values() {
+        assert_eq!(DataFormat::from_path(Path::new("a.yaml")), DataFormat::Yaml);
+        assert_eq!(DataFormat::from_path(Path::new("b.YML")), DataFormat::Yaml);
+        let raw =
+            "- title: One\n  tags: [a, b]\n  count: 3\n  draft: false\n- title: \"2\"\n  note: ~\n";
+        assert_eq!(
+            parse_text(raw, DataFormat::Yaml, &InputOptions::default()).unwrap(),
+            json!([
+                {"title": "One", "tags": ["a", "b"], "count": 3, "draft": false},
+                {"title": "2", "note": null}
+            ])
+        );
+        assert!(parse_text("a: [1", DataFormat::Yaml, &InputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn streamed_json_is_transcoded_from_the_given_encoding() {
+        let path = std::env::temp_dir().join(format!("json2md-stream-{}.json", std::process::id()));
+        // Large enough to cross the decoder's buffers; 0xE9 is é in Windows-1252
+        let mut bytes = b"[".to_vec();
+        for i in 0..2000 {
+            bytes.extend(
+                format!(
+                    "{}{{\"n\": {}, \"s\": \"caf",
+                    if i > 0 { "," } else { "" },
+                    i
+                )
+                .as_bytes(),
+            );
+            bytes.extend(b"\xe9\"}");
+        }
+        bytes.push(b']');
+        fs::write(&path, &bytes).unwrap();
+
+        let mut items = Vec::new();
+        stream_json_array(&path, Some("windows-1252"), |item| {
+            items.push(item);
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(items.len(), 2000);
+        assert_eq!(items[1999], json!({"n": 1999, "s": "café"}));
+
+        // Without a label the file must be UTF-8
+        let err = stream_json_array(&path, None, |_| Ok(true)).unwrap_err();
+        assert!(format!("{:#}", err).contains("JSON stream parse failed"));
+        assert!(stream_json_array(&path, Some("klingon"), |_| Ok(true)).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn url_cache_keys_are_stable_and_cover_headers() {
+        let url = "https://example.com/api";
+        let key = cache_key(url, &[]);
+        assert_eq!(key, cache_key(url, &[]));
+        assert_eq!(key.len(), 16);
+        assert_ne!(
+            key,
+            cache_key(url, &["Authorization: Bearer x".to_string()])
+        );
+        assert_ne!(
+            cache_key("a", &["bc".to_string()]),
+            cache_key("ab", &["c".to_string()])
+        );
+        // FNV-1a of "a\0", pinned so cached files survive toolchain upgrades
+        assert_eq!(cache_key("a", &[]), "089be207b544f1e4");
+    }
+
+    #[test]
+    fn url_cache_serves_stored_responses_until_the_headers_change() {
+        let dir = std::env::temp_dir().join(format!("json2md-cache-{}", std::process::id()));
+        let mut opts = InputOptions {
+            cache_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let url = "https://example.com/items";
+        let fetches = std::cell::Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Ok(HttpResponse {
+                body: format!("[{}]", fetches.get()),
+                content_type: Some("application/json".to_string()),
+                link: Some("<https://example.com/items?page=2>; rel=\"next\"".to_string()),
+            })
+        };
+
+        let miss = cached_get(url, &opts, fetch).unwrap();
+        let hit = cached_get(url, &opts, fetch).unwrap();
+        assert_eq!(fetches.get(), 1);
+        assert_eq!(hit.body, miss.body);
+        assert_eq!(hit.content_type.as_deref(), Some("application/json"));
+        assert_eq!(hit.link, miss.link);
+
+        opts.headers = vec!["Authorization: Bearer other".to_string()];
+        assert_eq!(cached_get(url, &opts, fetch).unwrap().body, "[2]");
+        assert_eq!(fetches.get(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn toml_documents_parse_with_datetimes_as_strings() {
+        let raw = "title = \"Site\"\nupdated = 2024-05-01T10:00:00Z\n\n[[posts]]\nname = \"a\"\nviews = 12\nscore = 1.5\ndraft = true\n\n[[posts]]\nname = \"b\"\ntags = [\"x\"]\n";
+        assert_eq!(
+            parse_text(raw, DataFormat::Toml, &InputOptions::default()).unwrap(),
+            json!({
+                "title": "Site",
+                "updated": "2024-05-01T10:00:00Z",
+                "posts": [
+                    {"name": "a", "views": 12, "score": 1.5, "draft": true},
+                    {"name": "b", "tags": ["x"]}
+                ]
+            })
+        );
+        assert_eq!(DataFormat::from_path(Path::new("c.toml")), DataFormat::Toml);
+        assert!(parse_text("a = ", DataFormat::Toml, &InputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn xml_maps_attributes_text_and_repeated_elements() {
+        let raw = r#"<feed version="2"><entry id="1"><title lang="en">One</title></entry><entry id="2"><title>Two</title><empty/></entry>note</feed>"#;
+        assert_eq!(
+            parse_text(raw, DataFormat::Xml, &InputOptions::default()).unwrap(),
+            json!({"feed": {
+                "@version": "2",
+                "entry": [
+                    {"@id": "1", "title": {"@lang": "en", "#text": "One"}},
+                    {"@id": "2", "title": "Two", "empty": null}
+                ],
+                "#text": "note"
+            }})
+        );
+        assert!(parse_text("<a><b></a>", DataFormat::Xml, &InputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn json5_accepts_comments_trailing_commas_and_bare_keys() {
+        let raw = "// list\n[{name: 'a', /* inline */ n: 0x10, tags: ['x',],},]";
+        assert_eq!(
+            parse_text(raw, DataFormat::Json5, &InputOptions::default()).unwrap(),
+            json!([{"name": "a", "n": 16, "tags": ["x"]}])
+        );
+        assert_eq!(
+            DataFormat::from_path(Path::new("c.jsonc")),
+            DataFormat::Json5
+        );
+        // Strict JSON still rejects the same input
+        assert!(parse_text(raw, DataFormat::Json, &InputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn markdown_notes_split_into_frontmatter_fields_and_body() {
+        let note = "---\ntitle: Hello\ntags: [a]\n---\n# Hello\n\nText\n";
+        assert_eq!(
+            parse_text(note, DataFormat::Markdown, &InputOptions::default()).unwrap(),
+            json!([{"title": "Hello", "tags": ["a"], "body": "# Hello\n\nText\n"}])
+        );
+        // No frontmatter, or an unclosed one, leaves everything in the body
+        assert_eq!(
+            parse_markdown_note("# Plain\n").unwrap(),
+            json!({"body": "# Plain\n"})
+        );
+        assert_eq!(split_frontmatter("---\ntitle: x\n"), None);
+        assert_eq!(
+            split_frontmatter("---\r\na: 1\r\n...\r\nrest"),
+            Some(("a: 1\r\n", "rest"))
+        );
+        assert!(parse_markdown_note("---\na: [1\n---\n").is_err());
+
+        let dir = std::env::temp_dir().join(format!("json2md-notes-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("b.md"), "---\nn: 2\n---\nB").unwrap();
+        fs::write(dir.join("sub/a.md"), "\u{feff}---\nn: 1\n---\nA").unwrap();
+        fs::write(dir.join("skip.txt"), "not a note").unwrap();
+        assert_eq!(
+            load_data(&dir, DataFormat::Markdown, &InputOptions::default()).unwrap(),
+            json!([
+                {"n": 2, "body": "B", "file": "b.md"},
+                {"n": 1, "body": "A", "file": "sub/a.md"}
+            ])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn next_page_links_are_found_and_resolved() {
+        let header = r#"<https://api.test/items?page=1>; rel="prev", <https://api.test/items?page=3>; rel="next""#;
+        assert_eq!(
+            parse_next_link(header).as_deref(),
+            Some("https://api.test/items?page=3")
+        );
+        assert_eq!(parse_next_link("</p/2>; rel=next").as_deref(), Some("/p/2"));
+        assert_eq!(parse_next_link(r#"<https://api.test/a>; rel="last""#), None);
+        assert_eq!(parse_next_link(""), None);
+
+        let base = "https://api.test/v1/items?page=2";
+        assert_eq!(
+            resolve_url(base, "https://other.test/x"),
+            "https://other.test/x"
+        );
+        assert_eq!(resolve_url(base, "/v2/items"), "https://api.test/v2/items");
+        assert_eq!(
+            resolve_url(base, "?page=3"),
+            "https://api.test/v1/items?page=3"
+        );
+        assert_eq!(resolve_url(base, "more"), "https://api.test/v1/more");
+        assert_eq!(resolve_url("https://api.test", "/p"), "https://api.test/p");
+    }
+
+    #[test]
+    fn csv_cells_infer_numbers_and_booleans_but_keep_codes_as_strings() {
+        assert_eq!(infer_cell("42"), Some(json!(42)));
+        assert_eq!(infer_cell("-7"), Some(json!(-7)));
+        assert_eq!(infer_cell("0"), Some(json!(0)));
+        assert_eq!(infer_cell("0.5"), Some(json!(0.5)));
+        assert_eq!(infer_cell("1e3"), Some(json!(1000.0)));
+        assert_eq!(infer_cell("TRUE"), Some(json!(true)));
+        assert_eq!(infer_cell("False"), Some(json!(false)));
+        // ZIP codes, words and float spellings without digits stay strings
+        assert_eq!(infer_cell("02134"), None);
+        assert_eq!(infer_cell("-007"), None);
+        assert_eq!(infer_cell("yes"), None);
+        assert_eq!(infer_cell("NaN"), None);
+        assert_eq!(infer_cell("inf"), None);
+        assert_eq!(
+            infer_cell("99999999999999999999"),
+            Some(json!(99999999999999999999.0))
+        );
+
+        assert_eq!(csv_cell(" 5 ", "auto").unwrap(), json!(5));
+        assert_eq!(csv_cell("abc", "auto").unwrap(), json!("abc"));
+        assert_eq!(csv_cell("", "auto").unwrap(), Value::Null);
+        assert_eq!(csv_cell("", "string").unwrap(), json!(""));
+        assert_eq!(csv_cell("007", "string").unwrap(), json!("007"));
+        assert_eq!(csv_cell("007", "int").unwrap(), json!(7));
+        assert_eq!(csv_cell("2", "float").unwrap(), json!(2.0));
+        assert_eq!(csv_cell("Yes", "bool").unwrap(), json!(true));
+        assert!(csv_cell("1.5", "int").is_err());
+        assert!(csv_cell("maybe", "bool").is_err());
+        assert!(csv_cell("x", "date").is_err());
+    }
+
+    #[test]
+    fn csv_types_apply_per_column_over_the_default() {
+        let raw = "id,zip,price,flag\n1,02134,9.50,true\n2,,10,no\n";
+        let strings = parse_text(raw, DataFormat::Csv, &InputOptions::default()).unwrap();
+        assert_eq!(
+            strings[0],
+            json!({"id": "1", "zip": "02134", "price": "9.50", "flag": "true"})
+        );
+
+        let opts = InputOptions {
+            csv_types: CsvTypes::Auto,
+            csv_column_types: HashMap::from([
+                ("zip".to_string(), "string".to_string()),
+                ("flag".to_string(), "bool".to_string()),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_text(raw, DataFormat::Csv, &opts).unwrap(),
+            json!([
+                {"id": 1, "zip": "02134", "price": 9.5, "flag": true},
+                {"id": 2, "zip": "", "price": 10, "flag": false}
+            ])
+        );
+
+        let bad = InputOptions {
+            csv_column_types: HashMap::from([("id".to_string(), "int".to_string())]),
+            ..Default::default()
+        };
+        let err = parse_text("id\n1\nx\n", DataFormat::Csv, &bad).unwrap_err();
+        assert!(format!("{:#}", err).contains("column 'id' on line 3"));
+    }
+
+    #[test]
+    fn csv_delimiter_quoting_and_header_options() {
+        let opts = InputOptions::default();
+        let quoted = "name,note\n\"Doe, Jane\",\"said \"\"hi\"\"\"\n";
+        assert_eq!(
+            parse_text(quoted, DataFormat::Csv, &opts).unwrap(),
+            json!([{"name": "Doe, Jane", "note": "said \"hi\""}])
+        );
+
+        let semi = InputOptions {
+            csv_delimiter: Some(b';'),
+            csv_quote: Some(b'\''),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_text("a;b\n'x;y';2\n", DataFormat::Csv, &semi).unwrap(),
+            json!([{"a": "x;y", "b": "2"}])
+        );
+
+        let escaped = InputOptions {
+            csv_escape: Some(b'\\'),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_text("q\n\"a \\\"b\\\"\"\n", DataFormat::Csv, &escaped).unwrap(),
+            json!([{"q": "a \"b\""}])
+        );
+
+        // Without headers, explicit names win and the rest are positional
+        let headless = InputOptions {
+            csv_no_headers: true,
+            csv_column_names: vec!["first".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_text("1,2\n3,4\n", DataFormat::Csv, &headless).unwrap(),
+            json!([{"first": "1", "col1": "2"}, {"first": "3", "col1": "4"}])
+        );
+
+        // Ragged rows are an error naming the line
+        let err = parse_text("a,b\n1,2\n3\n", DataFormat::Csv, &opts).unwrap_err();
+        assert!(format!("{:#}", err).contains("CSV: error on line 3"));
+
+        assert_eq!(parse_csv_char("tab"), Ok(b'\t'));
+        assert_eq!(parse_csv_char("|"), Ok(b'|'));
+        assert!(parse_csv_char(";;").is_err());
+        assert!(parse_csv_char("é").is_err());
+    }
+
+    #[test]
+    fn tsv_and_fixed_width_rows_become_items() {
+        assert_eq!(
+            parse_text("a\tb\nx, y\t2\n", DataFormat::Tsv, &InputOptions::default()).unwrap(),
+            json!([{"a": "x, y", "b": "2"}])
+        );
+        assert_eq!(DataFormat::from_path(Path::new("t.tsv")), DataFormat::Tsv);
+
+        let column = |name: &str, start, width| FixedWidthColumn {
+            name: name.to_string(),
+            start,
+            width,
+        };
+        let opts = InputOptions {
+            fixed_width_columns: vec![
+                column("id", 0, Some(4)),
+                column("name", 4, Some(8)),
+                column("rest", 12, None),
+            ],
+            csv_types: CsvTypes::Auto,
+            ..Default::default()
+        };
+        let raw = "0001Ada     tail end\n\n2   Bö\n";
+        assert_eq!(
+            parse_text(raw, DataFormat::FixedWidth, &opts).unwrap(),
+            json!([
+                {"id": "0001", "name": "Ada", "rest": "tail end"},
+                {"id": 2, "name": "Bö", "rest": null}
+            ])
+        );
+        assert!(parse_text(raw, DataFormat::FixedWidth, &InputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn dotted_csv_columns_expand_into_nested_objects() {
+        let raw = "id,user.name,user.address.city,id.extra\n1,Ada,Paris,x\n";
+        let opts = InputOptions {
+            csv_nested: true,
+            ..Default::default()
+        };
+        // A prefix already holding a plain value keeps the dotted key flat
+        assert_eq!(
+            parse_text(raw, DataFormat::Csv, &opts).unwrap(),
+            json!([{
+                "id": "1",
+                "user": {"name": "Ada", "address": {"city": "Paris"}},
+                "id.extra": "x"
+            }])
+        );
+        assert_eq!(
+            parse_text(raw, DataFormat::Csv, &InputOptions::default()).unwrap()[0]["user.name"],
+            json!("Ada")
+        );
+    }
+
+    #[test]
+    fn text_is_decoded_from_boms_labels_and_guesses() {
+        assert_eq!(decode_text(b"plain", None).unwrap(), "plain");
+        assert_eq!(decode_text(b"\xef\xbb\xbfbom", None).unwrap(), "bom");
+        // UTF-16 with and without a BOM
+        assert_eq!(decode_text(b"\xff\xfeh\0i\0", None).unwrap(), "hi");
+        assert_eq!(decode_text(b"h\0i\0!\0", None).unwrap(), "hi!");
+        assert_eq!(decode_text(b"\0h\0i", None).unwrap(), "hi");
+        // Invalid UTF-8 falls back to Windows-1252
+        assert_eq!(decode_text(b"caf\xe9 \x80", None).unwrap(), "café €");
+        assert_eq!(decode_text(b"caf\xe9", Some("latin1")).unwrap(), "café");
+        assert_eq!(
+            decode_text(b"caf\xc3\xa9", Some(" UTF-8 ")).unwrap(),
+            "café"
+        );
+        // A BOM overrides the label
+        assert_eq!(
+            decode_text(b"\xef\xbb\xbfok", Some("latin1")).unwrap(),
+            "ok"
+        );
+        assert!(decode_text(b"caf\xe9", Some("utf-8")).is_err());
+        assert!(decode_text(b"x", Some("klingon")).is_err());
+        assert_eq!(decode_text(b"", None).unwrap(), "");
+    }
+}
@@ -1,33 +1,127 @@
-// src/js_helpers.rs
 //! Dynamic JavaScript helper loading via QuickJS engine.
 //!
 //! Enabled with --features dynamic-helpers
 //! Usage: --js-helpers path/to/helpers.js
+//!
+//! The path may also be an ES module (.mjs, or a .js file with `import` / `export`),
+//! whose exported functions become helpers and whose relative imports are resolved
+//! from its directory, or a directory whose .js and .mjs files are all loaded.
+//!
+//! Helpers get their params plus a Handlebars.js-style options object (`hash`, the
+//! item's `context`, and `fn` / `inverse` in blocks):
+//!
+//! ```js
+//! // {{#card title level=2}}...{{else}}...{{/card}}
+//! function card(title, options) {
+//!     if (!title) return options.inverse();
+//!     return "#".repeat(options.hash.level || 1) + " " + title + "\n" + options.fn(this);
+//! }
+//! ```
 
 #![allow(unexpected_cfgs)]
 
-use anyhow::{Context, Result};
+use crate::HelperInfo;
+use anyhow::Result;
+use handlebars::Handlebars;
+use std::path::Path;
+
+#[cfg(feature = "dynamic-helpers")]
+use anyhow::Context;
+#[cfg(feature = "dynamic-helpers")]
 use handlebars::{
-    Context as HbContext, Handlebars, Helper, Output, RenderContext, RenderError, RenderErrorReason,
+    BlockContext, Context as HbContext, Helper, Output, RenderContext, RenderError,
+    RenderErrorReason, Renderable,
 };
+#[cfg(feature = "dynamic-helpers")]
 use serde_json::Value;
-use std::path::Path;
 
 #[cfg(feature = "dynamic-helpers")]
 use rquickjs::{
-    CatchResultExt, Context as JsContext, Ctx, Filter, Runtime, Undefined, Value as JsValue,
+    CatchResultExt, Context as JsContext, Ctx, Filter, FromJs, Runtime, Value as JsValue,
+    context::EvalOptions,
+    convert::Coerced,
+    function::{Opt, Rest},
+    loader::{Resolver, ScriptLoader},
 };
 #[cfg(feature = "dynamic-helpers")]
+use std::path::PathBuf;
+#[cfg(feature = "dynamic-helpers")]
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "dynamic-helpers")]
+use std::time::{Duration, Instant};
 
 /// Registry for dynamically loaded helpers (JS via QuickJS, Rust via libloading)
 pub struct DynamicHelperRegistry {
+    /// One runtime per loaded JS path, with the helpers it defines
     #[cfg(feature = "dynamic-helpers")]
-    js_runtime: Option<(Runtime, Arc<Mutex<JsContext>>)>,
+    js_runtimes: Vec<JsRuntime>,
     #[cfg(feature = "dynamic-helpers")]
     loaded_plugins: Vec<libloading::Library>,
+    /// Helpers registered by plugin libraries, with the library each comes from
+    #[cfg(feature = "dynamic-helpers")]
+    plugin_helpers: Vec<(String, PathBuf)>,
+    /// Treat console.error output from JS helpers as a render failure
+    #[cfg(feature = "dynamic-helpers")]
+    strict: bool,
+    /// Configuration for helpers (--helper-config), an object
+    #[cfg(feature = "dynamic-helpers")]
+    config: Value,
+    /// Whether a loaded JS file mentions `dataRoot`
     #[cfg(feature = "dynamic-helpers")]
-    js_helper_names: Vec<String>,
+    reads_data_root: bool,
+    /// Memory limit of each JS runtime in bytes (--js-max-memory)
+    #[cfg(feature = "dynamic-helpers")]
+    max_memory: Option<usize>,
+    /// Time limit for loading a JS path and for each call (--js-timeout-ms)
+    #[cfg(feature = "dynamic-helpers")]
+    timeout: Option<Duration>,
+}
+
+/// A loaded JS path: its runtime (kept alive for the context), the files it loaded, the
+/// helpers they define and the time limit its interrupt handler enforces
+#[cfg(feature = "dynamic-helpers")]
+struct JsRuntime {
+    _runtime: Runtime,
+    ctx: Arc<Mutex<JsContext>>,
+    path: PathBuf,
+    files: Vec<PathBuf>,
+    helpers: Vec<String>,
+    limit: Arc<TimeLimit>,
+}
+
+/// Execution time limit of a JS runtime; the deadline is only set while JS runs
+#[cfg(feature = "dynamic-helpers")]
+struct TimeLimit {
+    timeout: Option<Duration>,
+    deadline: Mutex<Option<Instant>>,
+}
+
+#[cfg(feature = "dynamic-helpers")]
+impl TimeLimit {
+    /// Start the clock before running JS
+    fn start(&self) {
+        *self.deadline.lock().unwrap() = self.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /// Stop the clock; an error naming the limit if JS ran past it (and was interrupted)
+    fn stop(&self) -> Result<(), String> {
+        let deadline = self.deadline.lock().unwrap().take();
+        match (deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => Err(format!(
+                "timed out after {} ms (--js-timeout-ms)",
+                timeout.as_millis()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether running JS should be interrupted
+    fn expired(&self) -> bool {
+        self.deadline
+            .lock()
+            .unwrap()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
 }
 
 impl DynamicHelperRegistry {
@@ -35,30 +129,89 @@ impl DynamicHelperRegistry {
     pub fn new() -> Self {
         Self {
             #[cfg(feature = "dynamic-helpers")]
-            js_runtime: None,
+            js_runtimes: Vec::new(),
             #[cfg(feature = "dynamic-helpers")]
             loaded_plugins: Vec::new(),
             #[cfg(feature = "dynamic-helpers")]
-            js_helper_names: Vec::new(),
+            plugin_helpers: Vec::new(),
+            #[cfg(feature = "dynamic-helpers")]
+            strict: false,
+            #[cfg(feature = "dynamic-helpers")]
+            config: Value::Object(serde_json::Map::new()),
+            #[cfg(feature = "dynamic-helpers")]
+            reads_data_root: false,
+            #[cfg(feature = "dynamic-helpers")]
+            max_memory: None,
+            #[cfg(feature = "dynamic-helpers")]
+            timeout: None,
         }
     }
 
     /// Stub implementation when dynamic-helpers feature is disabled
     #[cfg(not(feature = "dynamic-helpers"))]
-    pub fn load_js_helpers(&mut self, path: &Path) -> Result<Vec<String>> {
-        eprintln!("⚠️ JS helpers require: cargo build --features dynamic-helpers");
-        Ok(vec![])
+    pub fn set_strict(&mut self, _strict: bool) {}
+
+    /// Fail helper calls that log via console.error (applies to helpers registered afterwards)
+    #[cfg(feature = "dynamic-helpers")]
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Stub implementation when dynamic-helpers feature is disabled
+    #[cfg(not(feature = "dynamic-helpers"))]
+    pub fn set_limits(
+        &mut self,
+        _max_memory: Option<usize>,
+        _timeout: Option<std::time::Duration>,
+    ) {
+    }
+
+    /// Memory limit (bytes) and time limit of JS runtimes created afterwards; the time
+    /// limit applies to loading each path and to each helper call, which then fails
+    #[cfg(feature = "dynamic-helpers")]
+    pub fn set_limits(&mut self, max_memory: Option<usize>, timeout: Option<Duration>) {
+        self.max_memory = max_memory;
+        self.timeout = timeout;
+    }
+
+    /// Stub implementation when dynamic-helpers feature is disabled
+    #[cfg(not(feature = "dynamic-helpers"))]
+    pub fn set_config(&mut self, _config: serde_json::Value) {}
+
+    /// Configuration given to files and plugins loaded afterwards: `globalThis.helperConfig`
+    /// in JS, `PluginHost::config` for plugins
+    #[cfg(feature = "dynamic-helpers")]
+    pub fn set_config(&mut self, config: Value) {
+        self.config = config;
+    }
+
+    /// Stub implementation when dynamic-helpers feature is disabled
+    #[cfg(not(feature = "dynamic-helpers"))]
+    pub fn uses_data_root(&self) -> bool {
+        false
+    }
+
+    /// Whether a loaded JS file mentions `dataRoot`, which helpers read from
+    /// `options.context` (as for templates, the "auto" data_root setting then injects it)
+    #[cfg(feature = "dynamic-helpers")]
+    pub fn uses_data_root(&self) -> bool {
+        self.reads_data_root
+    }
+
+    /// Stub implementation when dynamic-helpers feature is disabled
+    #[cfg(not(feature = "dynamic-helpers"))]
+    pub fn load_js_helpers(&mut self, _path: &Path) -> Result<Vec<String>> {
+        anyhow::bail!("JS helpers require: cargo build --features dynamic-helpers")
     }
 
     /// Stub implementation when dynamic-helpers feature is disabled
     #[cfg(not(feature = "dynamic-helpers"))]
     pub fn load_rust_plugin(
         &mut self,
-        path: &Path,
+        _path: &Path,
         _hb: &mut Handlebars<'_>,
     ) -> Result<Vec<String>> {
-        eprintln!("⚠️ Rust plugins require: cargo build --features dynamic-helpers");
-        Ok(vec![])
+        anyhow::bail!("Rust plugins require: cargo build --features dynamic-helpers")
     }
 
     /// Stub implementation when dynamic-helpers feature is disabled
@@ -67,221 +220,617 @@ impl DynamicHelperRegistry {
         Ok(())
     }
 
-    /// Load JavaScript helpers from file using QuickJS engine
+    /// Stub implementation when dynamic-helpers feature is disabled
+    #[cfg(not(feature = "dynamic-helpers"))]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        Vec::new()
+    }
+
+    /// Load JavaScript helpers from a file or directory (see `js_helper_files`) using
+    /// QuickJS engine; each path gets its own runtime, and a later path's helper replaces
+    /// an earlier one of the same name
     #[cfg(feature = "dynamic-helpers")]
     pub fn load_js_helpers(&mut self, js_path: &Path) -> Result<Vec<String>> {
-        let js_code = std::fs::read_to_string(js_path)
-            .with_context(|| format!("Failed to read JS: {}", js_path.display()))?;
+        let files = js_helper_files(js_path)?;
+        if files.is_empty() {
+            anyhow::bail!("No .js or .mjs files in {}", js_path.display());
+        }
 
         let rt = Runtime::new().context("QuickJS runtime init failed")?;
+        rt.set_loader(LocalResolver, ScriptLoader::default().with_extension("mjs"));
+        if let Some(bytes) = self.max_memory {
+            rt.set_memory_limit(bytes);
+        }
+        let limit = Arc::new(TimeLimit {
+            timeout: self.timeout,
+            deadline: Mutex::new(None),
+        });
+        let interrupt = limit.clone();
+        rt.set_interrupt_handler(Some(Box::new(move || interrupt.expired())));
         let ctx = JsContext::full(&rt).context("QuickJS context init failed")?;
         let ctx = Arc::new(Mutex::new(ctx));
 
         let discovered = {
             let ctx_guard = ctx.lock().unwrap();
             ctx_guard
-                .with(|ctx| {
-                    // Inject minimal console stub to prevent "console is not defined" errors
+                .with(|ctx| -> Result<Vec<String>> {
+                    // console output goes to the log: log/info/debug with --verbose, warn as
+                    // a warning, error always. console.error messages are also buffered so
+                    // helper calls can surface them.
+                    let label = js_path.display().to_string();
+                    let log = move |level: String, text: String| match level.as_str() {
+                        "error" => error_log!("{}: {}", label, text),
+                        "warn" => warn_log!("{}: {}", label, text),
+                        _ => debug_log!("📜 {}: {}", label, text),
+                    };
+                    let log = rquickjs::Function::new(ctx.clone(), log)
+                        .map_err(|e| anyhow::anyhow!("console: {}", e))?;
+                    ctx.globals()
+                        .set("__json2mdLog", log)
+                        .map_err(|e| anyhow::anyhow!("console: {}", e))?;
                     let console_inject = r#"
-globalThis.console = { log: function() {}, error: function() {} };
+globalThis.__json2mdConsoleErrors = [];
+(function (log) {
+    const show = (value) => {
+        if (typeof value !== 'object' || value === null) return String(value);
+        try { return JSON.stringify(value); } catch (e) { return String(value); }
+    };
+    const format = (args) => Array.prototype.map.call(args, show).join(' ');
+    globalThis.console = {
+        log: function() { log('debug', format(arguments)); },
+        info: function() { log('debug', format(arguments)); },
+        debug: function() { log('debug', format(arguments)); },
+        warn: function() { log('warn', format(arguments)); },
+        error: function() {
+            const text = format(arguments);
+            __json2mdConsoleErrors.push(text);
+            log('error', text);
+        }
+    };
+})(__json2mdLog);
+delete globalThis.__json2mdLog;
 "#;
                     let _ = ctx.eval::<(), _>(console_inject.as_bytes()).catch(&ctx);
 
-                    // Execute user helper code
-                    let _ = ctx.eval::<(), _>(js_code.as_bytes()).catch(&ctx);
+                    // Configuration from --helper-config, readable while the script loads
+                    let config = serde_value_to_js(&ctx, &self.config)
+                        .map_err(|e| anyhow::anyhow!("helperConfig: {}", e))?;
+                    ctx.globals()
+                        .set("helperConfig", config)
+                        .map_err(|e| anyhow::anyhow!("helperConfig: {}", e))?;
+
+                    // Execute user helper code; a script that throws or does not parse has
+                    // not loaded
+                    for file in &files {
+                        let js_code = std::fs::read_to_string(file)
+                            .with_context(|| format!("Failed to read JS: {}", file.display()))?;
+                        self.reads_data_root |= js_code.contains("dataRoot");
+                        limit.start();
+                        let loaded = if is_es_module(file, &js_code) {
+                            import_module(&ctx, file)
+                        } else {
+                            // Named after the file, so stack traces point at it
+                            let mut options = EvalOptions::default();
+                            options.filename = Some(file.to_string_lossy().into_owned());
+                            ctx.eval_with_options::<(), _>(js_code.as_bytes(), options)
+                                .catch(&ctx)
+                                .map_err(|e| anyhow::anyhow!("{}", describe_js_error(&e)))
+                        };
+                        limit.stop().map_err(|e| anyhow::anyhow!(e))?;
+                        if js_path.is_dir() {
+                            loaded.with_context(|| file.display().to_string())?;
+                        } else {
+                            loaded?;
+                        }
+                    }
+
+                    // Discard anything logged while loading the script
+                    let _ = take_console_errors(&ctx);
 
                     let globals = ctx.globals();
                     let mut found = Vec::new();
 
                     // Scan globals for user-defined helpers (exclude built-in JS functions)
                     let keys_iter = globals.own_keys::<String>(Filter::new().string());
-                    for key_result in keys_iter {
-                        if let Ok(key) = key_result {
-                            // Skip known JavaScript built-ins
-                            if is_builtin_js_function(&key) {
-                                continue;
-                            }
-
-                            // Verify it's actually a function
-                            if let Ok(val) = globals.get::<_, JsValue>(&key) {
-                                if val.is_function() {
-                                    found.push(key);
-                                }
-                            }
+                    for key in keys_iter.flatten() {
+                        // Skip known JavaScript built-ins
+                        if is_builtin_js_function(&key) {
+                            continue;
+                        }
+
+                        // Verify it's actually a function
+                        if let Ok(val) = globals.get::<_, JsValue>(&key)
+                            && val.is_function()
+                        {
+                            found.push(key);
                         }
                     }
                     Ok(found)
-                })
-                .map_err(|e: rquickjs::Error| anyhow::anyhow!("JS context error: {}", e))?
+                })?
         };
 
-        self.js_runtime = Some((rt, ctx));
-        self.js_helper_names = discovered.clone();
+        self.js_runtimes.push(JsRuntime {
+            _runtime: rt,
+            ctx,
+            path: js_path.to_path_buf(),
+            files,
+            helpers: discovered.clone(),
+            limit,
+        });
         Ok(discovered)
     }
 
     /// Register discovered JS helpers with Handlebars instance
     #[cfg(feature = "dynamic-helpers")]
     pub fn register_with_handlebars(&self, hb: &mut Handlebars<'_>) -> Result<()> {
-        if let Some((_, ctx_arc)) = &self.js_runtime {
-            for name in &self.js_helper_names {
-                let js_name = name.clone();
-                let ctx_clone = ctx_arc.clone();
-
-                // Create Handlebars helper closure that calls JS function via QuickJS
-                let helper = move |h: &Helper<'_>,
-                                   _: &Handlebars<'_>,
-                                   _: &HbContext,
-                                   _: &mut RenderContext<'_, '_>,
-                                   out: &mut dyn Output|
-                      -> Result<(), RenderError> {
-                    let ctx_guard = ctx_clone.lock().unwrap();
-
-                    let call_result = ctx_guard.with(|ctx| -> Result<String, String> {
-                        // Get JS function from global scope
-                        let js_func: rquickjs::Function = ctx
-                            .globals()
-                            .get(&js_name)
-                            .map_err(|e| format!("Helper '{}' not found: {}", js_name, e))?;
-
-                        // Convert Handlebars params to QuickJS values
-                        let mut js_args: Vec<JsValue> = Vec::new();
-                        for param in h.params() {
-                            let val = param.value();
-                            if let Ok(js_val) = serde_value_to_js(&ctx, val) {
-                                js_args.push(js_val);
-                            }
-                        }
-
-                        // Call JS function with appropriate argument pattern
-                        let js_result: Result<JsValue<'_>, rquickjs::CaughtError<'_>> =
-                            match js_args.len() {
-                                0 => js_func.call(()).catch(&ctx),
-                                1 => js_func.call((js_args[0].clone(),)).catch(&ctx),
-                                2 => js_func
-                                    .call((js_args[0].clone(), js_args[1].clone()))
-                                    .catch(&ctx),
-                                3 => js_func
-                                    .call((
-                                        js_args[0].clone(),
-                                        js_args[1].clone(),
-                                        js_args[2].clone(),
-                                    ))
-                                    .catch(&ctx),
-                                4 => js_func
-                                    .call((
-                                        js_args[0].clone(),
-                                        js_args[1].clone(),
-                                        js_args[2].clone(),
-                                        js_args[3].clone(),
-                                    ))
-                                    .catch(&ctx),
-                                5 => js_func
-                                    .call((
-                                        js_args[0].clone(),
-                                        js_args[1].clone(),
-                                        js_args[2].clone(),
-                                        js_args[3].clone(),
-                                        js_args[4].clone(),
-                                    ))
-                                    .catch(&ctx),
-                                6 => js_func
-                                    .call((
-                                        js_args[0].clone(),
-                                        js_args[1].clone(),
-                                        js_args[2].clone(),
-                                        js_args[3].clone(),
-                                        js_args[4].clone(),
-                                        js_args[5].clone(),
-                                    ))
-                                    .catch(&ctx),
-                                _ => {
-                                    // Fallback: pack args into array + apply pattern
-                                    let args_arr = rquickjs::Array::new(ctx.clone())
-                                        .map_err(|e| e.to_string())?;
-                                    for (i, arg) in js_args.iter().enumerate() {
-                                        let _ = args_arr.set(i, arg.clone());
-                                    }
-                                    js_func.call((Undefined, args_arr)).catch(&ctx)
-                                }
-                            };
-
-                        // Convert JS result to Rust String for Handlebars
-                        match js_result {
-                            Ok(result_val) => {
-                                if let Some(js_str) = result_val.as_string() {
-                                    js_str.to_string().map_err(|e| e.to_string())
-                                } else {
-                                    // Fallback: JSON stringify complex results
-                                    let json_global: rquickjs::Object = ctx
-                                        .globals()
-                                        .get("JSON")
-                                        .map_err(|e| format!("JSON global not found: {}", e))?;
-                                    let stringify: rquickjs::Function = json_global
-                                        .get("stringify")
-                                        .map_err(|e| format!("JSON.stringify not found: {}", e))?;
-
-                                    match stringify
-                                        .call::<_, rquickjs::Value<'_>>((result_val,))
-                                        .catch(&ctx)
-                                    {
-                                        Ok(json_val) => {
-                                            if let Some(json_str) = json_val.as_string() {
-                                                json_str.to_string().map_err(|e| e.to_string())
-                                            } else {
-                                                Err("JSON.stringify returned non-string".to_string())
-                                            }
-                                        }
-                                        Err(e) => Err(format!("JSON.stringify failed: {}", e)),
-                                    }
-                                }
-                            }
-                            Err(e) => Err(format!("JS call failed: {}", e)),
-                        }
-                    });
-
-                    // Write result to Handlebars output or return error
-                    match call_result {
-                        Ok(output) => {
-                            out.write(&output)
-                                .map_err(|e| RenderError::from(RenderErrorReason::NestedError(Box::new(e))))?;
-                        }
-                        Err(e) => {
-                            return Err(RenderError::from(RenderErrorReason::Other(format!(
-                                "Helper '{}': {}",
-                                js_name, e
-                            ))));
-                        }
-                    }
-                    Ok(())
+        for runtime in &self.js_runtimes {
+            for name in &runtime.helpers {
+                let helper = JsHelper {
+                    name: name.clone(),
+                    ctx: runtime.ctx.clone(),
+                    limit: runtime.limit.clone(),
+                    strict: self.strict,
                 };
-
                 hb.register_helper(name, Box::new(helper));
             }
         }
         Ok(())
     }
 
-    /// Load Rust plugin library and register its helpers
+    /// The plugin and JS helpers for --list-helpers, in registration order (plugin
+    /// helpers register as they load, JS helpers after all plugins). A JS helper's
+    /// source is the file defining it, its signature comes from the function and its
+    /// description from the comment above its definition.
+    #[cfg(feature = "dynamic-helpers")]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        let mut list: Vec<HelperInfo> = self
+            .plugin_helpers
+            .iter()
+            .map(|(name, path)| HelperInfo {
+                name: name.clone(),
+                source: format!("{} (plugin)", path.display()),
+                signature: None,
+                description: None,
+            })
+            .collect();
+        for runtime in &self.js_runtimes {
+            let sources: Vec<(&PathBuf, String)> = runtime
+                .files
+                .iter()
+                .filter_map(|file| Some((file, std::fs::read_to_string(file).ok()?)))
+                .collect();
+            let ctx = runtime.ctx.lock().unwrap();
+            for name in &runtime.helpers {
+                let text = ctx.with(|ctx| {
+                    ctx.globals()
+                        .get::<_, Coerced<String>>(name.as_str())
+                        .map(|text| text.0)
+                        .ok()
+                });
+                let definition = regex::Regex::new(&format!(
+                    r"(?m)^[ \t]*(?:export\s+)?(?:(?:async\s+)?function\s*\*?\s*{0}\s*\(|(?:const|let|var)\s+{0}\s*=|globalThis\.{0}\s*=)",
+                    regex::escape(name)
+                ))
+                .expect("JS definition regex");
+                let defined = sources.iter().find_map(|(file, code)| {
+                    Some((*file, code, crate::definition_line(code, &definition)?))
+                });
+                list.push(HelperInfo {
+                    name: name.clone(),
+                    source: defined
+                        .map_or(&runtime.path, |(file, _, _)| file)
+                        .display()
+                        .to_string(),
+                    signature: text
+                        .as_deref()
+                        .and_then(js_params)
+                        .map(|params| crate::helper_signature(name, &params)),
+                    description: defined
+                        .and_then(|(_, code, line)| crate::comment_above(code, line, "//")),
+                });
+            }
+        }
+        list
+    }
+
+    /// Load Rust plugin library and register its helpers, after checking its ABI
+    /// version and required capabilities
     #[cfg(feature = "dynamic-helpers")]
     pub fn load_rust_plugin(
         &mut self,
         lib_path: &Path,
         target_hb: &mut Handlebars<'_>,
     ) -> Result<Vec<String>> {
+        use crate::plugin::{
+            AbiVersionFn, HOST_CAPABILITIES, PLUGIN_ABI_VERSION, PluginHost, RegisterFn,
+            RequiresFn,
+        };
         use libloading::Library;
 
         let lib = unsafe { Library::new(lib_path) }
             .with_context(|| format!("Failed to load: {}", lib_path.display()))?;
 
-        let factory: libloading::Symbol<crate::plugin::PluginFactory> =
-            unsafe { lib.get(b"create_helpers") }
-                .with_context(|| "Missing 'create_helpers' export")?;
+        let version = unsafe { lib.get::<AbiVersionFn>(b"json2md_plugin_abi_version") };
+        let Ok(version) = version else {
+            // Unversioned plugin passing Rust trait objects
+            let factory: libloading::Symbol<crate::plugin::PluginFactory> =
+                unsafe { lib.get(b"create_helpers") }.with_context(|| {
+                    "Not a json2md plugin: missing 'json2md_plugin_abi_version' export"
+                })?;
+            warn_log!(
+                "{} uses the unversioned 'create_helpers' ABI, which only works when built with the same rustc and handlebars as json2md; export 'json2md_plugin_abi_version' instead",
+                lib_path.display()
+            );
+            let plugin = factory();
+            plugin.register(target_hb);
+            self.loaded_plugins.push(lib);
+            return Ok(vec![]);
+        };
+        let version = unsafe { version() };
+        if version != PLUGIN_ABI_VERSION {
+            anyhow::bail!(
+                "Plugin ABI version mismatch: built for v{}, this json2md supports v{}; rebuild the plugin",
+                version,
+                PLUGIN_ABI_VERSION
+            );
+        }
+        if let Ok(requires) = unsafe { lib.get::<RequiresFn>(b"json2md_plugin_requires") } {
+            let missing = unsafe { requires() } & !HOST_CAPABILITIES;
+            if missing != 0 {
+                anyhow::bail!(
+                    "Plugin requires capabilities this json2md lacks (flags {:#x})",
+                    missing
+                );
+            }
+        }
+        let register: libloading::Symbol<RegisterFn> =
+            unsafe { lib.get(b"json2md_plugin_register") }
+                .with_context(|| "Missing 'json2md_plugin_register' export")?;
+
+        let mut helpers: Vec<(String, crate::plugin::CHelperFn)> = Vec::new();
+        let config = serde_json::to_vec(&self.config)?;
+        let host = PluginHost {
+            abi_version: PLUGIN_ABI_VERSION,
+            capabilities: HOST_CAPABILITIES,
+            config: config.as_ptr(),
+            config_len: config.len(),
+            registry: &mut helpers as *mut _ as *mut std::ffi::c_void,
+            register_helper: register_c_helper,
+        };
+        let status = unsafe { register(&host) };
+        if status != 0 {
+            anyhow::bail!("Plugin registration failed with status {}", status);
+        }
 
-        let plugin = factory();
-        plugin.register(target_hb);
+        let names: Vec<String> = helpers.iter().map(|(name, _)| name.clone()).collect();
+        self.plugin_helpers.extend(
+            names
+                .iter()
+                .map(|name| (name.clone(), lib_path.to_path_buf())),
+        );
+        for (name, func) in helpers {
+            target_hb.register_helper(&name.clone(), Box::new(CHelper { name, func }));
+        }
         self.loaded_plugins.push(lib);
-        Ok(vec![])
+        Ok(names)
+    }
+}
+
+/// The param list of a JS function from its source text (`function f(a, b)`,
+/// `(a, b) =>` or `a =>`); `None` for classes and other callables
+#[cfg(feature = "dynamic-helpers")]
+fn js_params(text: &str) -> Option<String> {
+    let params = regex::Regex::new(r"^(?:async\s*)?(?:function\b[^(]*)?\(([^)]*)\)")
+        .expect("JS params regex");
+    let single = regex::Regex::new(r"^(?:async\s+)?([\w$]+)\s*=>").expect("JS arrow regex");
+    params
+        .captures(text)
+        .or_else(|| single.captures(text))
+        .map(|caps| caps[1].to_string())
+}
+
+/// `PluginHost::register_helper`: collects the helpers a plugin registers
+#[cfg(feature = "dynamic-helpers")]
+unsafe extern "C" fn register_c_helper(
+    registry: *mut std::ffi::c_void,
+    name: *const u8,
+    name_len: usize,
+    helper: crate::plugin::CHelperFn,
+) -> i32 {
+    let helpers = unsafe { &mut *(registry as *mut Vec<(String, crate::plugin::CHelperFn)>) };
+    let name = unsafe { std::slice::from_raw_parts(name, name_len) };
+    match std::str::from_utf8(name) {
+        Ok(name) if !name.is_empty() => {
+            helpers.push((name.to_string(), helper));
+            0
+        }
+        _ => 1,
+    }
+}
+
+/// `HelperCall::write`: appends plugin output to the host buffer
+#[cfg(feature = "dynamic-helpers")]
+unsafe extern "C" fn write_c_output(out: *mut std::ffi::c_void, data: *const u8, len: usize) {
+    let out = unsafe { &mut *(out as *mut Vec<u8>) };
+    out.extend_from_slice(unsafe { std::slice::from_raw_parts(data, len) });
+}
+
+/// A JS function registered as a helper. As in Handlebars.js it is called with the
+/// helper's params and an options object: `options.hash` holds the hash arguments,
+/// `options.context` the item's context (with `dataRoot` when injected), and
+/// `options.fn(context)` / `options.inverse(context)` render the block and its
+/// `{{else}}` part, with the current context when none is given
+#[cfg(feature = "dynamic-helpers")]
+struct JsHelper {
+    name: String,
+    ctx: Arc<Mutex<JsContext>>,
+    limit: Arc<TimeLimit>,
+    /// Treat console.error output as a render failure
+    strict: bool,
+}
+
+/// A block render asked for from JS: the inverse or not, and the context to use
+#[cfg(feature = "dynamic-helpers")]
+type BlockRequest = (bool, Option<Value>);
+
+/// `options.fn()` returns a marker standing for the block, which renders once the JS
+/// call has returned (the block may use helpers from the same script). JS can place the
+/// text it returns but not inspect it
+#[cfg(feature = "dynamic-helpers")]
+const BLOCK_START: char = '\u{E000}';
+#[cfg(feature = "dynamic-helpers")]
+const BLOCK_END: char = '\u{E001}';
+
+#[cfg(feature = "dynamic-helpers")]
+impl JsHelper {
+    /// Call the JS function; blocks it asks for are collected in `blocks`
+    fn call_js(
+        &self,
+        h: &Helper<'_>,
+        context: &Value,
+        blocks: &Arc<Mutex<Vec<BlockRequest>>>,
+    ) -> Result<String, String> {
+        let ctx_guard = self.ctx.lock().unwrap();
+        self.limit.start();
+        let result = ctx_guard.with(|ctx| -> Result<String, String> {
+            // Get JS function from global scope
+            let js_func: rquickjs::Function = ctx
+                .globals()
+                .get(&self.name)
+                .map_err(|e| format!("Helper '{}' not found: {}", self.name, e))?;
+
+            // Convert Handlebars params to QuickJS values, keeping their positions
+            let mut js_args = h
+                .params()
+                .iter()
+                .map(|param| serde_value_to_js(&ctx, param.value()))
+                .collect::<Result<Vec<JsValue>, String>>()?;
+            // Handlebars.js-style options object last
+            js_args.push(self.options(&ctx, h, context, blocks)?);
+
+            // Call JS function with every argument in position, whatever their number
+            let js_result: Result<JsValue<'_>, rquickjs::CaughtError<'_>> =
+                js_func.call((Rest(js_args),)).catch(&ctx);
+
+            // Surface console.error output from this call
+            let logged = take_console_errors(&ctx);
+            let result_val = match js_result {
+                Ok(val) => val,
+                Err(e) => {
+                    // The stack trace goes last, after what was logged
+                    let error = describe_js_error(&e);
+                    let (message, stack) = error.split_once('\n').unwrap_or((&error, ""));
+                    let mut msg = format!("JS call failed: {}", message);
+                    if !logged.is_empty() {
+                        msg.push_str(&format!(" (console.error: {})", logged.join("; ")));
+                    }
+                    if !stack.is_empty() {
+                        msg.push('\n');
+                        msg.push_str(stack);
+                    }
+                    return Err(msg);
+                }
+            };
+            if self.strict && !logged.is_empty() {
+                return Err(format!("console.error: {}", logged.join("; ")));
+            }
+
+            // Convert JS result to Rust String for Handlebars
+            if let Some(js_str) = result_val.as_string() {
+                js_str.to_string().map_err(|e| e.to_string())
+            } else {
+                // Fallback: JSON stringify complex results
+                let json_global: rquickjs::Object = ctx
+                    .globals()
+                    .get("JSON")
+                    .map_err(|e| format!("JSON global not found: {}", e))?;
+                let stringify: rquickjs::Function = json_global
+                    .get("stringify")
+                    .map_err(|e| format!("JSON.stringify not found: {}", e))?;
+
+                match stringify
+                    .call::<_, rquickjs::Value<'_>>((result_val,))
+                    .catch(&ctx)
+                {
+                    Ok(json_val) => {
+                        if let Some(json_str) = json_val.as_string() {
+                            json_str.to_string().map_err(|e| e.to_string())
+                        } else {
+                            Err("JSON.stringify returned non-string".to_string())
+                        }
+                    }
+                    Err(e) => Err(format!("JSON.stringify failed: {}", e)),
+                }
+            }
+        });
+        self.limit.stop()?;
+        result
+    }
+
+    /// The options object: helper name, hash arguments, item context and the block
+    /// functions
+    fn options<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        h: &Helper<'_>,
+        context: &Value,
+        blocks: &Arc<Mutex<Vec<BlockRequest>>>,
+    ) -> Result<JsValue<'js>, String> {
+        let hash: serde_json::Map<String, Value> = h
+            .hash()
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.value().clone()))
+            .collect();
+        let options = rquickjs::Object::new(ctx.clone()).map_err(|e| e.to_string())?;
+        options
+            .set("name", self.name.as_str())
+            .map_err(|e| e.to_string())?;
+        options
+            .set("hash", serde_value_to_js(ctx, &Value::Object(hash))?)
+            .map_err(|e| e.to_string())?;
+        options
+            .set("context", serde_value_to_js(ctx, context)?)
+            .map_err(|e| e.to_string())?;
+
+        for (key, inverse) in [("fn", false), ("inverse", true)] {
+            let blocks = blocks.clone();
+            let render = move |context: Opt<JsValue<'_>>| -> rquickjs::Result<String> {
+                // Without a context, or with `this` outside of strict mode, the block keeps
+                // the current one
+                let value = match context.0 {
+                    Some(value) if !value.is_undefined() => {
+                        let ctx = value.ctx().clone();
+                        if value == ctx.globals().into_value() {
+                            None
+                        } else {
+                            ctx.json_stringify(value)?
+                                .and_then(|json| json.to_string().ok())
+                                .and_then(|json| serde_json::from_str(&json).ok())
+                        }
+                    }
+                    _ => None,
+                };
+                let mut blocks = blocks.lock().unwrap();
+                blocks.push((inverse, value));
+                Ok(format!("{}{}{}", BLOCK_START, blocks.len() - 1, BLOCK_END))
+            };
+            let render = rquickjs::Function::new(ctx.clone(), render).map_err(|e| e.to_string())?;
+            options.set(key, render).map_err(|e| e.to_string())?;
+        }
+        Ok(options.into_value())
+    }
+}
+
+#[cfg(feature = "dynamic-helpers")]
+impl handlebars::HelperDef for JsHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> Result<(), RenderError> {
+        let blocks = Arc::new(Mutex::new(Vec::new()));
+        let text = self
+            .call_js(h, ctx.data(), &blocks)
+            .map_err(|e| RenderErrorReason::Other(format!("Helper '{}': {}", self.name, e)))?;
+        let blocks = std::mem::take(&mut *blocks.lock().unwrap());
+
+        // Replace block markers with the rendered blocks
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(BLOCK_START) {
+            out.write(&rest[..start])?;
+            let marker = &rest[start + BLOCK_START.len_utf8()..];
+            let block = marker.split_once(BLOCK_END).and_then(|(index, after)| {
+                Some((blocks.get(index.parse::<usize>().ok()?)?, after))
+            });
+            match block {
+                Some((request, after)) => {
+                    render_block(h, r, ctx, rc, out, request)?;
+                    rest = after;
+                }
+                None => {
+                    out.write(&rest[start..start + BLOCK_START.len_utf8()])?;
+                    rest = marker;
+                }
+            }
+        }
+        out.write(rest)?;
+        Ok(())
+    }
+}
+
+/// Render a helper's block, or its inverse, with the given context or the current one
+#[cfg(feature = "dynamic-helpers")]
+fn render_block<'reg: 'rc, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc HbContext,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+    (inverse, value): &BlockRequest,
+) -> Result<(), RenderError> {
+    let template = if *inverse { h.inverse() } else { h.template() };
+    let Some(template) = template else {
+        return Ok(());
+    };
+    let Some(value) = value else {
+        return template.render(r, ctx, rc, out);
+    };
+    let mut block = BlockContext::new();
+    block.set_base_value(value.clone());
+    rc.push_block(block);
+    let result = template.render(r, ctx, rc, out);
+    rc.pop_block();
+    result
+}
+
+/// A helper from a plugin, called through the C ABI with its arguments as JSON
+#[cfg(feature = "dynamic-helpers")]
+struct CHelper {
+    name: String,
+    func: crate::plugin::CHelperFn,
+}
+
+#[cfg(feature = "dynamic-helpers")]
+impl handlebars::HelperDef for CHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> Result<(), RenderError> {
+        let hash: serde_json::Map<String, Value> = h
+            .hash()
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.value().clone()))
+            .collect();
+        let args = serde_json::json!({
+            "name": self.name,
+            "params": h.params().iter().map(|p| p.value().clone()).collect::<Vec<_>>(),
+            "hash": hash,
+            "context": ctx.data(),
+        });
+        let args = serde_json::to_vec(&args).map_err(RenderErrorReason::SerdeError)?;
+        let mut buffer: Vec<u8> = Vec::new();
+        let call = crate::plugin::HelperCall {
+            args: args.as_ptr(),
+            args_len: args.len(),
+            out: &mut buffer as *mut _ as *mut std::ffi::c_void,
+            write: write_c_output,
+        };
+        let status = unsafe { (self.func)(&call) };
+        let text = String::from_utf8_lossy(&buffer);
+        if status != 0 {
+            return Err(
+                RenderErrorReason::Other(format!("Helper '{}': {}", self.name, text)).into(),
+            );
+        }
+        out.write(&text)?;
+        Ok(())
     }
 }
 
@@ -306,6 +855,139 @@ fn is_builtin_js_function(name: &str) -> bool {
     BUILTINS.contains(&name)
 }
 
+/// Files a --js-helpers path loads: the file itself, or the .js and .mjs files of a
+/// directory in name order, which share one runtime as if concatenated
+pub fn js_helper_files(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let entries = std::fs::read_dir(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read JS directory {}: {}", path.display(), e))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let file = entry?.path();
+        let js = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("js") || ext.eq_ignore_ascii_case("mjs"));
+        if js && file.is_file() {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether a file is an ES module: .mjs, or top-level `import` / `export` statements
+#[cfg(feature = "dynamic-helpers")]
+fn is_es_module(path: &Path, code: &str) -> bool {
+    let statement = regex::Regex::new(r#"(?m)^\s*(?:import\s*[\w{*'"]|export\s)"#)
+        .expect("module statement regex");
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mjs"))
+        || statement.is_match(code)
+}
+
+/// Evaluate an ES module (and what it imports) and make its exported functions global,
+/// where helpers are looked up; the default export is not a helper
+#[cfg(feature = "dynamic-helpers")]
+fn import_module(ctx: &Ctx<'_>, path: &Path) -> Result<()> {
+    let name = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read JS: {}", path.display()))?;
+    let namespace: rquickjs::Object =
+        rquickjs::Module::import(ctx, name.to_string_lossy().as_ref())
+            .and_then(|promise| promise.finish())
+            .catch(ctx)
+            .map_err(|e| anyhow::anyhow!("{}", describe_js_error(&e)))?;
+    let globals = ctx.globals();
+    for key in namespace.keys::<String>().flatten() {
+        if key == "default" {
+            continue;
+        }
+        if let Ok(value) = namespace.get::<_, JsValue>(&key)
+            && value.is_function()
+        {
+            globals
+                .set(&key, value)
+                .map_err(|e| anyhow::anyhow!("{}: {}", key, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolves `import "./file.js"` relative to the importing module; the extension may be
+/// left out. Package imports are not supported
+#[cfg(feature = "dynamic-helpers")]
+struct LocalResolver;
+
+#[cfg(feature = "dynamic-helpers")]
+impl Resolver for LocalResolver {
+    fn resolve<'js>(
+        &mut self,
+        _ctx: &Ctx<'js>,
+        base: &str,
+        name: &str,
+    ) -> rquickjs::Result<String> {
+        let path = Path::new(name);
+        if !name.starts_with('.') && !path.is_absolute() {
+            return Err(rquickjs::Error::new_resolving(base, name));
+        }
+        let path = Path::new(base)
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(path);
+        let found = if path.extension().is_some() {
+            Some(path)
+        } else {
+            ["js", "mjs"]
+                .iter()
+                .map(|ext| path.with_extension(ext))
+                .find(|candidate| candidate.is_file())
+        };
+        found
+            .and_then(|found| found.canonicalize().ok())
+            .map(|found| found.to_string_lossy().into_owned())
+            .ok_or_else(|| rquickjs::Error::new_resolving(base, name))
+    }
+}
+
+/// A caught JS error as "TypeError: message", followed by its stack trace with one
+/// frame per line; thrown values that are not errors have no trace
+#[cfg(feature = "dynamic-helpers")]
+fn describe_js_error(error: &rquickjs::CaughtError<'_>) -> String {
+    match error {
+        rquickjs::CaughtError::Exception(exception) => {
+            let name = exception
+                .get::<_, String>("name")
+                .unwrap_or_else(|_| "Error".to_string());
+            let mut text = match exception.message().filter(|m| !m.is_empty()) {
+                Some(message) => format!("{}: {}", name, message),
+                None => name,
+            };
+            let stack = exception.stack().unwrap_or_default();
+            for frame in stack.lines().map(str::trim).filter(|f| !f.is_empty()) {
+                text.push_str("\n    ");
+                text.push_str(frame);
+            }
+            text
+        }
+        rquickjs::CaughtError::Value(value) => {
+            let thrown = Coerced::<String>::from_js(value.ctx(), value.clone())
+                .map(|coerced| coerced.0)
+                .unwrap_or_else(|_| value.type_name().to_string());
+            format!("threw {}", thrown)
+        }
+        rquickjs::CaughtError::Error(e) => e.to_string(),
+    }
+}
+
+/// Drain messages buffered by the console.error stub
+#[cfg(feature = "dynamic-helpers")]
+fn take_console_errors(ctx: &Ctx<'_>) -> Vec<String> {
+    ctx.eval::<Vec<String>, _>("globalThis.__json2mdConsoleErrors.splice(0)")
+        .unwrap_or_default()
+}
+
 /// Convert serde_json::Value to rquickjs::Value for JS interop
 #[cfg(feature = "dynamic-helpers")]
 fn serde_value_to_js<'js>(ctx: &Ctx<'js>, val: &Value) -> Result<JsValue<'js>, String> {
@@ -343,4 +1025,69 @@ fn serde_value_to_js<'js>(ctx: &Ctx<'js>, val: &Value) -> Result<JsValue<'js>, S
             Ok(js_obj.into_value())
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "dynamic-helpers"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Registry with the given JS loaded from a file unique to the test
+    fn registry(name: &str, code: &str) -> DynamicHelperRegistry {
+        let path = std::env::temp_dir().join(format!("json2md-{}-{}.js", name, std::process::id()));
+        std::fs::write(&path, code).unwrap();
+        let mut registry = DynamicHelperRegistry::new();
+        registry.load_js_helpers(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        registry
+    }
+
+    fn render(registry: &DynamicHelperRegistry, template: &str, data: &Value) -> String {
+        let mut hb = Handlebars::new();
+        registry.register_with_handlebars(&mut hb).unwrap();
+        hb.render_template(template, data).unwrap()
+    }
+
+    #[test]
+    fn seven_params_arrive_in_position() {
+        let registry = registry(
+            "seven",
+            "function join7(a, b, c, d, e, f, g, options) {
+                return [a, b, c, d, e, f, g].join('-') + '|' + options.name;
+            }",
+        );
+        let data = json!({"a": 1, "b": "two", "c": true});
+        let out = render(&registry, r#"{{join7 a b c "d" 5 "f" 7}}"#, &data);
+        assert_eq!(out, "1-two-true-d-5-f-7|join7");
+    }
+
+    #[test]
+    fn nine_params_arrive_in_position_with_options_last() {
+        let registry = registry(
+            "nine",
+            "function join9() {
+                const args = Array.prototype.slice.call(arguments);
+                const options = args.pop();
+                return args.join(',') + '|' + arguments.length + '|' + options.name
+                    + '|' + options.hash.sep;
+            }",
+        );
+        let out = render(
+            &registry,
+            r#"{{join9 1 2 3 4 5 6 7 8 9 sep="/"}}"#,
+            &json!({}),
+        );
+        assert_eq!(out, "1,2,3,4,5,6,7,8,9|10|join9|/");
+    }
+
+    #[test]
+    fn options_directly_follow_the_given_params() {
+        let registry = registry(
+            "fewer",
+            "function pair(a, b, options) {
+                return a + '|' + b.name + '|' + typeof options;
+            }",
+        );
+        let out = render(&registry, "{{pair 1}}", &json!({}));
+        assert_eq!(out, "1|pair|undefined");
+    }
+}
@@ -0,0 +1,2591 @@
+#![allow(unexpected_cfgs)]
+//! JSON/CSV (and YAML, TOML, XML, ...) to Markdown converter with Handlebars templating
+//! and dynamic helpers.
+//!
+//! Supports:
+//! - Built-in helpers for strings, dates, math, translations, tables, encoding, logic,
+//!   collections and trees (`replaceRegex`, `table`, `dateFormat`, ...; `json2md
+//!   --list-helpers` prints them all)
+//! - Dynamic JS helpers via QuickJS (--js-helpers flag)
+//! - Dynamic Lua helpers via mlua (--lua-helpers flag)
+//! - Python helpers via an embedded interpreter (--python-helpers flag)
+//! - Rhai helper scripts, pure Rust and in the default build (--rhai-helpers flag)
+//! - Dynamic Rust plugins via libloading (--rs-plugin flag)
+//!
+//! The `json2md` binary is a thin wrapper around [`cli`]; applications embed the
+//! conversion with [`Converter`].
+
+// Logging macros, used by the modules below
+#[macro_use]
+pub mod logging;
+
+mod archive;
+mod assets;
+pub mod cli;
+mod collections;
+mod config;
+mod converter;
+mod dates;
+mod encode;
+mod filter;
+mod flavor;
+mod frontmatter;
+mod html;
+mod i18n;
+mod input;
+mod js_helpers;
+mod lint;
+mod logic;
+mod lua_helpers;
+mod math;
+mod merge;
+mod nav;
+mod obsidian;
+mod path;
+mod python_helpers;
+mod rhai_helpers;
+mod sink;
+mod site;
+mod strings;
+mod table;
+mod tree;
+mod watch;
+// Plugin ABI, also used by plugin crates
+pub mod plugin;
+
+pub use converter::{Converter, Destination};
+pub use flavor::Flavor;
+/// The Handlebars version helpers must be written against
+pub use handlebars;
+pub use sink::{ArchiveSink, FileSink, MemorySink, OutputSink, StdoutSink};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use handlebars::{
+    BlockContext, Context as HbContext, Handlebars, Helper, HelperDef, HelperResult, Output,
+    RenderContext, RenderError, RenderErrorReason, Renderable, ScopedJson, StringOutput, Template,
+    handlebars_helper,
+};
+use indexmap::IndexMap;
+use input::FixedWidthColumn;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Output strategy: single consolidated file or multiple files in a directory
+#[derive(Clone)]
+enum OutputStrategy {
+    /// Write all rendered items to a single file
+    SingleFile(PathBuf),
+    /// Write each item to a separate file in the specified directory
+    /// Optional split_config overrides per-item naming
+    MultiFile {
+        directory: PathBuf,
+        split_config: Option<SplitConfig>,
+    },
+}
+
+/// Configuration for per-item filename generation in multi-file mode
+#[derive(Clone, Debug)]
+struct SplitConfig {
+    /// Template for generating per-item filenames (supports Handlebars syntax)
+    /// - Empty: use settings.json_name
+    /// - Plain string: treat as JSON path (e.g., "title", "user.name")
+    /// - Contains "{{": treat as Handlebars template
+    template: String,
+}
+
+impl SplitConfig {
+    /// Parse split argument: empty → index mode, plain → path, "{{" → template
+    fn from_arg(arg: Option<&str>) -> Self {
+        match arg {
+            None | Some("") => Self {
+                template: String::new(),
+            }, // Index mode
+            Some(s) => Self {
+                template: s.to_string(),
+            }, // JSON path mode
+        }
+    }
+
+    /// Check if using index-based naming (no template/path provided)
+    fn is_index_mode(&self) -> bool {
+        self.template.is_empty()
+    }
+
+    /// Check if using Handlebars template for naming
+    fn is_template_mode(&self) -> bool {
+        self.template.contains("{{")
+    }
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JsonImportSettings {
+    /// Field to use for output filename (supports Handlebars template syntax)
+    pub json_name: String,
+    /// Allow path separators in json_name (creates subdirectories)
+    pub json_name_path: bool,
+    /// Output folder for generated markdown files
+    pub folder_name: String,
+    /// Top-level field to iterate over (for nested JSON structures)
+    pub top_field: String,
+    /// Prefix for output filenames
+    pub note_prefix: String,
+    /// Suffix for output filenames
+    pub note_suffix: String,
+    /// Force treating objects as arrays (single-item iteration)
+    pub force_array: bool,
+    /// Ensure unique filenames by appending counter on collision
+    pub unique_names: bool,
+    /// Render the template once with the whole dataset as root context, whatever its
+    /// shape (no per-item iteration; `{{#each this}}` iterates an array root). Metadata
+    /// such as `SourceFilename` and `vars` is only added to object roots; not combinable
+    /// with `top_field`
+    pub single_context: bool,
+    /// Obsidian vault output: filenames follow Obsidian's naming rules (no `# ^ [ ] |`)
+    /// and notes get `tags`/`aliases` properties from the item fields
+    pub obsidian: bool,
+    /// Aggregates computed once over the items and injected as `aggregates.<name>`
+    /// Spec: "name=op:field,..." with op in sum, avg, min, max, count, length
+    pub aggregate: String,
+    /// Per-column CSV types overriding --csv-types: {"price": "float", "zip": "string"}
+    /// (string, auto, int, float, bool)
+    pub csv_column_types: HashMap<String, String>,
+    /// Column layout for fixed-width input (.fwf or --format fixed-width):
+    /// [{"name": "id", "start": 0, "width": 6}, {"name": "title", "start": 6}]
+    pub fixed_width_columns: Vec<FixedWidthColumn>,
+    /// Expand dotted CSV headers ("user.name", "address.city") into nested objects
+    pub csv_nested: bool,
+    /// Item filter rules applied before rendering; items must pass every rule
+    pub filter: Vec<FilterRule>,
+    /// Sort keys applied before rendering: "field[:asc|desc][:auto|num|str|date],..."
+    pub sort_by: String,
+    /// Field to bucket items on: renders once per distinct value with `GroupKey`,
+    /// `GroupItems` and `GroupSize` in the context (files are named by the group key
+    /// unless json_name is a template)
+    pub group_by: String,
+    /// Drop items whose value in this field was already seen (items without it are kept)
+    pub dedupe_on: String,
+    /// Which duplicate survives dedupe_on: "first" or "last"
+    pub dedupe_keep: DedupeKeep,
+    /// Subdirectory per item in multi-file mode, rendered with the item context, e.g.
+    /// "{{category}}/{{status}}" (directories are created as needed)
+    pub folder_template: String,
+    /// Extension of generated files (default "md"); may be a template rendered per item,
+    /// e.g. "{{#if canvas}}canvas{{else}}md{{/if}}" or "{{ext}}"
+    pub output_extension: String,
+    /// Markup of the output: "markdown", "asciidoc" or "rst" (item separator, default
+    /// extension and the `escape` helper)
+    pub flavor: Flavor,
+    /// Escape the flavor's markup characters in every `{{value}}` instead of writing
+    /// values as is; `{{{value}}}` stays raw. File names and frontmatter are not escaped
+    pub auto_escape: bool,
+    /// Language tag (e.g. "de" or "fr-CA") for the `t` helper, month and weekday names
+    /// in dates and number separators
+    pub locale: String,
+    /// YAML frontmatter prepended to each generated note; string values are templates
+    /// rendered with the item context ("{{tags}}" alone keeps the field's type), e.g.
+    /// {"title": "{{name}}", "tags": "{{tags}}", "created": "{{now \"%Y-%m-%d\"}}"}
+    pub frontmatter: IndexMap<String, Value>,
+    /// Files named in item fields, copied into the output's assets directory and linked
+    /// with {{assetPath field}}: {"fields": ["cover"], "dir": "assets"}
+    pub assets: assets::AssetSettings,
+    /// When to copy the whole dataset into each context as `dataRoot`: "auto" (only
+    /// if the template reads it as a value or a JS helper file mentions it), "always"
+    /// or "never"; the
+    /// {{dataRoot "path"}} helper works in every mode without the copy
+    pub data_root: DataRootMode,
+    /// Directory whose .js/.mjs, .lua, .py and .rhai helper files and .so/.dll/.dylib
+    /// plugins are all loaded, in name order (like --plugin-dir)
+    pub plugin_dir: String,
+}
+
+/// Whether `dataRoot` is injected into every render context
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataRootMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Which occurrence of a duplicate item is kept
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupeKeep {
+    #[default]
+    First,
+    Last,
+}
+
+/// Item filter rule: keep items where `field op value` holds, or drop them with `exclude`
+/// e.g. {"field": "draft", "op": "!=", "value": true}
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FilterRule {
+    /// Item field path
+    pub field: String,
+    /// == != < <= > >= (or eq ne lt lte gt gte), contains, matches (regex), exists, missing
+    #[serde(default = "FilterRule::default_op")]
+    pub op: String,
+    /// Value to compare with (unused by exists/missing)
+    #[serde(default)]
+    pub value: Value,
+    /// Drop matching items instead of keeping them
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+impl FilterRule {
+    fn default_op() -> String {
+        "==".to_string()
+    }
+
+    /// Reject unknown operators and invalid patterns up front
+    fn validate(&self) -> Result<()> {
+        match self.op.as_str() {
+            "contains" | "exists" | "missing" => Ok(()),
+            "matches" => Regex::new(self.value.as_str().unwrap_or_default())
+                .map(|_| ())
+                .with_context(|| format!("Filter on '{}': invalid regex", self.field)),
+            op if path::compare_op(&Value::Null, op, &Value::Null).is_some() => Ok(()),
+            op => anyhow::bail!("Filter on '{}': unknown operator '{}'", self.field, op),
+        }
+    }
+
+    /// Whether the item is kept by this rule
+    fn keeps(&self, item: &Value) -> bool {
+        let field = objfield(item, &self.field, None).filter(|v| !v.is_null());
+        let hit = match (self.op.as_str(), &field) {
+            ("exists", _) => field.is_some(),
+            ("missing", _) => field.is_none(),
+            ("contains", Some(Value::Array(arr))) => arr.contains(&self.value),
+            ("contains", Some(Value::String(s))) => {
+                self.value.as_str().is_some_and(|needle| s.contains(needle))
+            }
+            ("contains", _) => false,
+            ("matches", Some(v)) => {
+                let text = v.as_str().map_or_else(|| v.to_string(), String::from);
+                Regex::new(self.value.as_str().unwrap_or_default())
+                    .is_ok_and(|re| re.is_match(&text))
+            }
+            ("matches", None) => false,
+            (op, _) => path::compare_op(field.as_ref().unwrap_or(&Value::Null), op, &self.value)
+                .unwrap_or(false),
+        };
+        hit != self.exclude
+    }
+}
+
+impl JsonImportSettings {
+    /// Settings as a run uses them: the Obsidian preset applied, the flavor's extension
+    /// for the default "md" and the filter rules checked
+    fn prepare(&mut self) -> Result<()> {
+        if self.obsidian {
+            obsidian::apply(self);
+        }
+        if self.output_extension == "md" {
+            self.output_extension = self.flavor.extension().to_string();
+        }
+        for rule in &self.filter {
+            rule.validate()?;
+        }
+        if self.single_context && !self.top_field.is_empty() {
+            anyhow::bail!(
+                "single_context renders the whole input, so top_field \"{}\" would be ignored; \
+                 read it in the template instead ({{{{#each dataRoot.{}}}}})",
+                self.top_field,
+                self.top_field
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for JsonImportSettings {
+    fn default() -> Self {
+        Self {
+            json_name: "name".to_string(),
+            json_name_path: false,
+            folder_name: "JSON2MD".to_string(),
+            top_field: String::new(),
+            note_prefix: String::new(),
+            note_suffix: String::new(),
+            force_array: true,
+            unique_names: false,
+            single_context: false,
+            obsidian: false,
+            aggregate: String::new(),
+            csv_column_types: HashMap::new(),
+            fixed_width_columns: Vec::new(),
+            csv_nested: false,
+            filter: Vec::new(),
+            sort_by: String::new(),
+            group_by: String::new(),
+            dedupe_on: String::new(),
+            dedupe_keep: DedupeKeep::First,
+            folder_template: String::new(),
+            output_extension: "md".to_string(),
+            flavor: Flavor::Markdown,
+            auto_escape: false,
+            locale: String::new(),
+            frontmatter: IndexMap::new(),
+            assets: assets::AssetSettings::default(),
+            data_root: DataRootMode::Auto,
+            plugin_dir: String::new(),
+        }
+    }
+}
+
+// ============================================================================
+// Utilities
+// ============================================================================
+
+/// Navigate nested JSON using dot notation: "user.profile.name"
+/// JSONPath-style indices, wildcards and filters also work: "users[0].name", "items[?(@.active)]"
+/// Supports '@' prefix to fallback to alternative data source
+fn objfield(src: &Value, field: &str, fallback: Option<&Value>) -> Option<Value> {
+    if field.is_empty() {
+        return Some(src.clone());
+    }
+
+    let (path, source) = match (field.strip_prefix('@'), fallback) {
+        (Some(rest), Some(fb)) => (rest, fb),
+        _ => (field, src),
+    };
+
+    path::select(source, path)
+}
+
+/// Sanitize filename for filesystem safety across platforms
+fn valid_filename(name: &str, allow_paths: bool) -> String {
+    let pattern = if allow_paths {
+        r#"[<>:"\\|?\*]"#
+    } else {
+        r#"[<>:"/\\|?\*]"#
+    };
+    Regex::new(pattern)
+        .expect("valid_filename regex compilation failed")
+        .replace_all(name, "_")
+        .to_string()
+}
+
+/// Sanitize an output filename with the rules of the configured target
+fn note_filename(name: &str, settings: &JsonImportSettings) -> String {
+    if settings.obsidian {
+        obsidian::sanitize(name, settings.json_name_path)
+    } else {
+        valid_filename(name, settings.json_name_path)
+    }
+}
+
+/// Reference time of the current run, taken on first use after `begin_run`
+static RUN_NOW: Mutex<Option<DateTime<Local>>> = Mutex::new(None);
+
+/// Reference time shared by the whole run so date helpers agree with each other
+fn run_now() -> DateTime<Local> {
+    *RUN_NOW
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(Local::now)
+}
+
+/// Start a new run: a fresh reference time for `run_now` and no warnings left over from
+/// an earlier run (--watch converts many times in one process)
+fn begin_run() {
+    *RUN_NOW.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    logging::take_warnings();
+}
+
+/// Parse a date value: RFC 3339, "YYYY-MM-DD[ HH:MM[:SS]]", or epoch seconds/millis
+fn parse_date(val: &Value) -> Option<DateTime<Local>> {
+    match val {
+        Value::Number(n) => {
+            let ts = n.as_i64()?;
+            // Values beyond year ~5000 in seconds are treated as milliseconds
+            if ts.abs() > 100_000_000_000 {
+                Local.timestamp_millis_opt(ts).single()
+            } else {
+                Local.timestamp_opt(ts, 0).single()
+            }
+        }
+        Value::String(s) => {
+            let s = s.trim();
+            if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+                return Some(dt.with_timezone(&Local));
+            }
+            for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+                if let Ok(ndt) = NaiveDateTime::parse_from_str(s, fmt) {
+                    return Local.from_local_datetime(&ndt).earliest();
+                }
+            }
+            if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                return Local
+                    .from_local_datetime(&d.and_hms_opt(0, 0, 0)?)
+                    .earliest();
+            }
+            s.parse::<i64>()
+                .ok()
+                .and_then(|n| parse_date(&Value::from(n)))
+        }
+        _ => None,
+    }
+}
+
+/// Convert displayable errors to Handlebars RenderError
+fn re_err(msg: impl std::fmt::Display) -> RenderError {
+    RenderError::from(RenderErrorReason::Other(msg.to_string()))
+}
+
+// ============================================================================
+// Built-in Handlebars Helpers
+// ============================================================================
+
+/// replace regex multiple
+fn hb_table_regex(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &HbContext,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> Result<(), RenderError> {
+    let params = h.params();
+    if params.len() < 3 {
+        return Ok(());
+    }
+
+    let input = params[0].render();
+    for chunk in params[1..params.len() - 1].chunks(2) {
+        if chunk.len() < 2 {
+            break;
+        }
+        let pattern = chunk[0].render();
+        let replacement = chunk[1].render();
+
+        if let Ok(re) = Regex::new(&format!("^{}$", &pattern))
+            && let Some(caps) = re.captures(&input)
+        {
+            let mut result = replacement;
+            for (i, m) in caps.iter().enumerate().skip(1) {
+                if let Some(text) = m {
+                    result = result.replace(&format!("${}", i), text.as_str());
+                }
+            }
+            return out.write(&result).map_err(re_err);
+        }
+    }
+    out.write(&input).map_err(re_err)
+}
+
+/// replace with regex
+fn hb_replace_regex(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &HbContext,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> Result<(), RenderError> {
+    let params = h.params();
+    if params.len() != 3 {
+        return Ok(());
+    }
+
+    let text = params[0].render();
+    let pattern = params[1].render();
+    let repl = params[2].render();
+
+    match Regex::new(&pattern) {
+        Ok(re) => Ok(out
+            .write(&re.replace_all(&text, repl.as_str()))
+            .map_err(re_err)?),
+        Err(e) => {
+            // Log regex error but continue with original text
+            warn_log!("Invalid regex '{}': {}", pattern, e);
+            Ok(out.write(&text).map_err(re_err)?)
+        }
+    }
+}
+
+/// Parse a version string (leading "v" allowed); invalid input yields None with a verbose warning
+fn parse_semver(val: &Value) -> Option<semver::Version> {
+    let raw = match val {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let parsed = semver::Version::parse(raw.trim().trim_start_matches(['v', 'V'])).ok();
+    if parsed.is_none() {
+        debug_log!("⚠️ Invalid semver '{}'", raw);
+    }
+    parsed
+}
+
+/// Sort version strings by semver precedence; invalid versions go last
+/// {{#each (semverSort versions)}} / {{#each (semverSort versions desc=true)}}
+fn semver_sort(versions: &[Value], desc: bool) -> Vec<Value> {
+    let mut keyed: Vec<(Option<semver::Version>, &Value)> =
+        versions.iter().map(|v| (parse_semver(v), v)).collect();
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) if desc => b.cmp(a),
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    keyed.into_iter().map(|(_, v)| v.clone()).collect()
+}
+
+/// Compare two versions with an operator (<, <=, ==, !=, >=, >); invalid versions compare lowest
+/// {{#if (semverCompare version ">=" "1.10.0")}}
+fn semver_compare(a: &Value, op: &str, b: &Value) -> bool {
+    let ord = parse_semver(a).cmp(&parse_semver(b));
+    match op {
+        "<" => ord.is_lt(),
+        "<=" => ord.is_le(),
+        "==" | "=" => ord.is_eq(),
+        "!=" => ord.is_ne(),
+        ">=" => ord.is_ge(),
+        ">" => ord.is_gt(),
+        _ => {
+            debug_log!("⚠️ Unknown semverCompare operator '{}'", op);
+            false
+        }
+    }
+}
+
+/// Label a date relative to the run's "now": Upcoming (after today), Today, Yesterday,
+/// This week, This month, Older
+/// Thresholds are day counts: {{timeBucket created_at week=7 month=30}}
+fn time_bucket(date: &Value, week: u64, month: u64) -> String {
+    let Some(dt) = parse_date(date) else {
+        debug_log!("⚠️ timeBucket: unparseable date {}", date);
+        return String::new();
+    };
+    let days_ago = (run_now().date_naive() - dt.date_naive()).num_days();
+    let label = match days_ago {
+        d if d < 0 => "Upcoming",
+        0 => "Today",
+        1 => "Yesterday",
+        d if d < week as i64 => "This week",
+        d if d < month as i64 => "This month",
+        _ => "Older",
+    };
+    label.to_string()
+}
+
+/// Collect `<prefix><n>` fields into an array, skipping missing, null and empty-string values.
+/// Keys match exactly: prefix "tag" with range 1..=3 reads tag1, tag2, tag3 (not tag01/tag_1).
+/// Without a range, every `<prefix><digits>` key is gathered in numeric order; with only
+/// `from`, every such key numbered `from` or higher.
+/// {{#each (gather this "tag" 1 5)}} / {{#each (gather this "tag" 2)}}
+fn gather(
+    obj: &serde_json::Map<String, Value>,
+    prefix: &str,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Vec<Value> {
+    let indices: Vec<u64> = match (from, to) {
+        (Some(from), Some(to)) => (from..=to).collect(),
+        _ => {
+            let mut found: Vec<u64> = obj
+                .keys()
+                .filter_map(|k| k.strip_prefix(prefix))
+                .filter(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()))
+                .filter_map(|rest| rest.parse().ok())
+                .filter(|i| from.is_none_or(|from| *i >= from))
+                .collect();
+            found.sort_unstable();
+            found.dedup();
+            found
+        }
+    };
+
+    indices
+        .into_iter()
+        .filter_map(|i| obj.get(&format!("{}{}", prefix, i)))
+        .filter(|v| !v.is_null() && v.as_str().is_none_or(|s| !s.trim().is_empty()))
+        .cloned()
+        .collect()
+}
+
+/// Count occurrences of a field across an array of objects.
+/// Returns [{value, count}] sorted by count descending, ties alphabetically by value.
+/// {{#each (frequency items "category")}}| {{value}} | {{count}} |{{/each}}
+fn frequency(items: &[Value], field: &str) -> Vec<Value> {
+    let mut counts: Vec<(String, Value, u64)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for val in items.iter().filter_map(|item| objfield(item, field, None)) {
+        if val.is_null() {
+            continue;
+        }
+        let key = match &val {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        match index.get(&key) {
+            Some(&i) => counts[i].2 += 1,
+            None => {
+                index.insert(key.clone(), counts.len());
+                counts.push((key, val, 1));
+            }
+        }
+    }
+    counts.sort_by(|(ka, _, na), (kb, _, nb)| nb.cmp(na).then_with(|| ka.cmp(kb)));
+    counts
+        .into_iter()
+        .map(|(_, value, count)| serde_json::json!({ "value": value, "count": count }))
+        .collect()
+}
+
+handlebars_helper!(hb_semver_sort: |versions: array, {desc: bool = false}| semver_sort(versions, desc));
+handlebars_helper!(hb_semver_compare: |a: Json, op: str, b: Json| semver_compare(a, op, b));
+handlebars_helper!(hb_gather: |obj: object, prefix: str, *args| {
+    gather(obj, prefix, args.get(2).and_then(|v| v.as_u64()), args.get(3).and_then(|v| v.as_u64()))
+});
+handlebars_helper!(hb_frequency: |items: array, field: str| frequency(items, field));
+handlebars_helper!(hb_wikilink: |target: Json, *args| obsidian::wikilink(target, args.get(1).copied()));
+handlebars_helper!(hb_embed: |target: Json, *args| obsidian::embed(target, args.get(1).copied()));
+handlebars_helper!(hb_tag: |value: Json| obsidian::tags(value));
+handlebars_helper!(hb_time_bucket: |date: Json, {week: u64 = 7, month: u64 = 30}| time_bucket(date, week, month));
+
+/// Find the record for a key in a lookup dataset: arrays match `key_field` (compared as
+/// text so CSV strings join JSON numbers), objects are indexed by the key directly
+fn lookup_join(dataset: &Value, key_field: &str, key: &Value) -> Option<Value> {
+    let key_text = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let wanted = key_text(key);
+    match dataset {
+        Value::Array(rows) => rows
+            .iter()
+            .find(|row| row.get(key_field).is_some_and(|v| key_text(v) == wanted))
+            .cloned(),
+        Value::Object(map) => map.get(&wanted).cloned(),
+        _ => None,
+    }
+}
+
+/// {{lookupJoin "users" authorId [key="id"]}}: resolve a foreign key against a --lookup
+/// dataset; an array of keys yields the matching records (unmatched keys are dropped)
+struct LookupJoinHelper;
+
+impl HelperDef for LookupJoinHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or_else(|| re_err("lookupJoin: dataset name required"))?;
+        let key = h.param(1).map_or(&Value::Null, |p| p.value());
+        let key_field = h
+            .hash_get("key")
+            .and_then(|v| v.value().as_str())
+            .unwrap_or("id");
+        let dataset = ctx
+            .data()
+            .get("lookup")
+            .and_then(|lookup| lookup.get(name))
+            .ok_or_else(|| re_err(format!("lookupJoin: unknown lookup dataset '{}'", name)))?;
+
+        let result = match key {
+            Value::Array(keys) => Value::Array(
+                keys.iter()
+                    .filter_map(|k| lookup_join(dataset, key_field, k))
+                    .collect(),
+            ),
+            k => lookup_join(dataset, key_field, k).unwrap_or(Value::Null),
+        };
+        Ok(ScopedJson::Derived(result))
+    }
+}
+
+/// Items of a dataset with a field loosely equal to `wanted` (dot paths, numbers match
+/// numeric strings); objects are searched by their values
+fn lookup_matches<'a>(dataset: &'a Value, field: &str, wanted: &Value) -> Vec<&'a Value> {
+    let items: Vec<&Value> = match dataset {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    items
+        .into_iter()
+        .filter(|item| path::select(item, field).is_some_and(|v| logic::loose_eq(&v, wanted)))
+        .collect()
+}
+
+/// {{crossref dataRoot "id" parentId}}: the item of a dataset whose field matches a value,
+/// for cross-links between notes; the dataset is a value or the name of a --lookup
+/// dataset, `all=true` returns every match and an array of values their matches
+struct CrossrefHelper;
+
+impl HelperDef for CrossrefHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let (Some(collection), Some(key), Some(wanted)) = (h.param(0), h.param(1), h.param(2))
+        else {
+            return Err(re_err(
+                "crossref: expected a dataset, a field name and a value",
+            ));
+        };
+        let (collection, wanted) = (collection.value(), wanted.value());
+        let field = key
+            .value()
+            .as_str()
+            .ok_or_else(|| re_err("crossref: field name must be a string"))?;
+        let dataset = match collection {
+            Value::String(name) => ctx
+                .data()
+                .get("lookup")
+                .and_then(|lookup| lookup.get(name))
+                .ok_or_else(|| re_err(format!("crossref: unknown lookup dataset '{}'", name)))?,
+            other => other,
+        };
+        let all = h
+            .hash_get("all")
+            .is_some_and(|p| p.value().as_bool() == Some(true));
+
+        let matches = |v: &Value| {
+            let mut found = lookup_matches(dataset, field, v);
+            if !all {
+                found.truncate(1);
+            }
+            found
+        };
+        let result = match wanted {
+            Value::Array(values) => {
+                Value::Array(values.iter().flat_map(matches).cloned().collect())
+            }
+            v if all => Value::Array(matches(v).into_iter().cloned().collect()),
+            v => matches(v).first().copied().cloned().unwrap_or(Value::Null),
+        };
+        Ok(ScopedJson::Derived(result))
+    }
+}
+
+/// {{render "card" author}}: render a partial or registered template with the given
+/// value as its context (hash arguments are added to an object context), e.g. a
+/// "comment" partial calling {{render "comment" this}} for each reply
+struct RenderHelper;
+
+impl HelperDef for RenderHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or_else(|| re_err("render: template name required"))?;
+        let template = rc
+            .get_partial(name)
+            .or_else(|| r.get_template(name))
+            .ok_or_else(|| re_err(format!("render: unknown template '{}'", name)))?;
+        let mut value = h.param(1).map_or(Value::Null, |p| p.value().clone());
+        if let Value::Object(map) = &mut value {
+            for (key, param) in h.hash() {
+                map.insert(key.to_string(), param.value().clone());
+            }
+        }
+
+        let mut block = BlockContext::new();
+        block.set_base_value(value);
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result
+    }
+}
+
+/// {{dataRoot "posts.0.title"}}: read from the full dataset without copying it into
+/// every context; without a path the whole dataset is returned
+struct DataRootHelper(Arc<Value>);
+
+impl HelperDef for DataRootHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let result = match h.param(0).map(|p| p.value()) {
+            None => Some(self.0.as_ref().clone()),
+            Some(Value::String(field)) => path::select(&self.0, field),
+            Some(_) => return Err(re_err("dataRoot: path must be a string")),
+        };
+        Ok(ScopedJson::Derived(result.unwrap_or(Value::Null)))
+    }
+}
+
+/// {{escape title}}: escape the output flavor's markup characters so the value renders
+/// literally (Markdown, AsciiDoc or reST, per --flavor); {{mdEscape title}} always
+/// escapes Markdown
+struct EscapeHelper(Flavor);
+
+impl HelperDef for EscapeHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let text = match h.param(0).map(|p| p.value()) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        };
+        Ok(ScopedJson::Derived(Value::String(self.0.escape(&text))))
+    }
+}
+
+/// Helpers whose output is markup, written as is even with auto_escape
+const MARKUP_HELPERS: &[&str] = &[
+    "table",
+    "json",
+    "jsonPretty",
+    "yaml",
+    "escape",
+    "mdEscape",
+    "assetPath",
+];
+
+/// Helper writing its output without auto-escaping
+struct Markup(Box<dyn HelperDef + Send + Sync>);
+
+impl HelperDef for Markup {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        self.0.call_inner(h, r, ctx, rc)
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let disabled = rc.is_disable_escape();
+        rc.set_disable_escape(true);
+        let result = self.0.call(h, r, ctx, rc, out);
+        rc.set_disable_escape(disabled);
+        result
+    }
+}
+
+/// Failure that ends a run with a specific exit code (other errors exit with 1)
+#[derive(Debug)]
+enum RunError {
+    /// The run completed, but items failed or helpers did not load (exit code 2)
+    Partial(String),
+    /// A template check failed (exit code 3)
+    Invalid(String),
+}
+
+impl RunError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            RunError::Partial(_) => 2,
+            RunError::Invalid(_) => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Partial(message) | RunError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// An item that failed to render, as logged and written to the errors report
+#[derive(Debug, Serialize)]
+struct ItemFailure {
+    index: usize,
+    /// Output file of the item, when it was named before the failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    /// Missing field in strict mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+    reason: String,
+}
+
+impl ItemFailure {
+    /// Failure from any item error, keeping the details of a render failure
+    fn from_error(e: anyhow::Error, idx: usize) -> Self {
+        e.downcast().unwrap_or_else(|e| ItemFailure {
+            index: idx,
+            file: None,
+            field: None,
+            reason: format!("{:#}", e),
+        })
+    }
+}
+
+impl std::fmt::Display for ItemFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "Item {} ({}): {}", self.index, file, self.reason),
+            None => write!(f, "Item {}: {}", self.index, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for ItemFailure {}
+
+/// Render error of an item with where it happened: the item index, the output file, the
+/// template position (naming partials) and, for a missing field in strict mode, the field
+/// path. The position goes on the first line, ahead of details such as a JS stack trace
+fn render_failure(e: RenderError, idx: usize, file: &Path) -> ItemFailure {
+    let template = match e.template_name.as_deref() {
+        None | Some("tpl") => String::new(),
+        Some(name) => format!(" of '{}'", name.strip_prefix("tpl:").unwrap_or(name)),
+    };
+    let at = match (e.line_no, e.column_no) {
+        (Some(line), Some(col)) => format!(" (line {}, column {}{})", line, col, template),
+        _ => String::new(),
+    };
+    let (field, reason) = match e.reason() {
+        RenderErrorReason::MissingVariable(Some(field)) => (
+            Some(field.clone()),
+            format!("missing field '{}'{}", field, at),
+        ),
+        reason => {
+            let reason = reason.to_string();
+            match reason.split_once('\n') {
+                Some((first, details)) => (None, format!("{}{}\n{}", first, at, details)),
+                None => (None, format!("{}{}", reason, at)),
+            }
+        }
+    };
+    ItemFailure {
+        index: idx,
+        file: Some(file.display().to_string()),
+        field,
+        reason,
+    }
+}
+
+/// Render a template string without auto-escaping: file and folder names and
+/// frontmatter values are not Markdown
+fn render_raw(hb: &Handlebars<'_>, src: &str, ctx: &HbContext) -> Result<String, RenderError> {
+    render_unescaped(hb, &Template::compile(src)?, ctx)
+}
+
+fn render_unescaped(
+    hb: &Handlebars<'_>,
+    template: &Template,
+    ctx: &HbContext,
+) -> Result<String, RenderError> {
+    let mut rc = RenderContext::new(None);
+    rc.set_disable_escape(true);
+    let mut out = StringOutput::new();
+    template.render(hb, ctx, &mut rc, &mut out)?;
+    out.into_string().map_err(RenderError::from)
+}
+
+/// {{assetPath cover}}: link to a copied asset, relative to the note being rendered
+/// (its folder is `_note_dir_` in the root context)
+struct AssetPathHelper(assets::AssetSettings);
+
+impl HelperDef for AssetPathHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc HbContext,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let reference = match h.param(0).map(|p| p.value()) {
+            None | Some(Value::Null) => return Ok(ScopedJson::Derived(Value::Null)),
+            Some(Value::String(s)) => s.clone(),
+            Some(_) => return Err(re_err("assetPath: path must be a string")),
+        };
+        let note_dir = ctx
+            .data()
+            .get("_note_dir_")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        Ok(ScopedJson::Derived(Value::String(assets::link(
+            &self.0, note_dir, &reference,
+        ))))
+    }
+}
+
+/// Whether a template reads `dataRoot` as a context value (`{{dataRoot.x}}`,
+/// `{{#each dataRoot}}`) rather than only through the `dataRoot` helper
+fn uses_data_root_value(src: &str) -> bool {
+    let mentions = Regex::new(r"\bdataRoot\b").expect("dataRoot regex");
+    let calls = Regex::new(r"(?:\{\{~?|\()\s*dataRoot(?:\s|~|\}|\))").expect("dataRoot call regex");
+    mentions.find_iter(src).count() > calls.find_iter(src).count()
+}
+
+/// Helpers registered by `Handlebars::new()` itself
+const HANDLEBARS_BUILTIN_HELPERS: &[&str] = &[
+    "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+    "and", "or", "not", "len",
+];
+
+/// Helpers with the names they are registered under
+type NamedHelpers = Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)>;
+
+/// Built-in helpers by group: "core" for the ones defined here, else their module
+fn builtin_helper_groups(locale: &Arc<i18n::Locale>) -> Vec<(&'static str, NamedHelpers)> {
+    let core: NamedHelpers = vec![
+        ("tableRegex", Box::new(hb_table_regex)),
+        ("replaceRegex", Box::new(hb_replace_regex)),
+        ("semverSort", Box::new(hb_semver_sort)),
+        ("semverCompare", Box::new(hb_semver_compare)),
+        ("timeBucket", Box::new(hb_time_bucket)),
+        ("gather", Box::new(hb_gather)),
+        ("frequency", Box::new(hb_frequency)),
+        ("wikilink", Box::new(hb_wikilink)),
+        ("embed", Box::new(hb_embed)),
+        ("tag", Box::new(hb_tag)),
+        ("crossref", Box::new(CrossrefHelper)),
+        ("render", Box::new(RenderHelper)),
+        ("lookupJoin", Box::new(LookupJoinHelper)),
+        ("dataRoot", Box::new(DataRootHelper(Arc::new(Value::Null)))),
+        ("escape", Box::new(EscapeHelper(Flavor::Markdown))),
+        ("mdEscape", Box::new(EscapeHelper(Flavor::Markdown))),
+        (
+            "assetPath",
+            Box::new(AssetPathHelper(assets::AssetSettings::default())),
+        ),
+    ];
+    vec![
+        ("core", core),
+        ("strings", strings::helpers()),
+        ("dates", dates::helpers(locale)),
+        ("math", math::helpers(locale)),
+        ("i18n", i18n::helpers(locale)),
+        ("table", table::helpers()),
+        ("encode", encode::helpers()),
+        ("logic", logic::helpers()),
+        ("collections", collections::helpers()),
+        ("tree", tree::helpers()),
+    ]
+}
+
+/// Register all built-in helpers with the Handlebars instance, returning their names
+fn register_helpers(hb: &mut Handlebars<'_>, locale: &Arc<i18n::Locale>) -> Vec<&'static str> {
+    let helpers: Vec<_> = builtin_helper_groups(locale)
+        .into_iter()
+        .flat_map(|(_, helpers)| helpers)
+        .collect();
+    let names = helpers.iter().map(|(name, _)| *name).collect();
+    for (name, helper) in helpers {
+        if MARKUP_HELPERS.contains(&name) {
+            hb.register_helper(name, Box::new(Markup(helper)));
+        } else {
+            hb.register_helper(name, helper);
+        }
+    }
+    names
+}
+
+/// A helper as listed by --list-helpers: where it comes from and, when its source
+/// shows them, its params and the comment describing it
+pub(crate) struct HelperInfo {
+    pub name: String,
+    pub source: String,
+    pub signature: Option<String>,
+    pub description: Option<String>,
+}
+
+/// The built-in helpers in registration order, Handlebars' own first
+pub(crate) fn builtin_helper_info(locale: &Arc<i18n::Locale>) -> Vec<HelperInfo> {
+    let handlebars = HANDLEBARS_BUILTIN_HELPERS
+        .iter()
+        .map(|name| (*name, "handlebars"));
+    let ours = builtin_helper_groups(locale)
+        .into_iter()
+        .flat_map(|(group, helpers)| helpers.into_iter().map(move |(name, _)| (name, group)));
+    handlebars
+        .chain(ours)
+        .map(|(name, group)| HelperInfo {
+            name: name.to_string(),
+            source: format!("built-in ({})", group),
+            signature: None,
+            description: None,
+        })
+        .collect()
+}
+
+/// `name(a, b)` from a script's param list, whitespace normalized
+#[cfg_attr(
+    not(any(
+        feature = "dynamic-helpers",
+        feature = "lua-helpers",
+        feature = "rhai-helpers"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn helper_signature(name: &str, params: &str) -> String {
+    let params: Vec<&str> = params
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .collect();
+    format!("{}({})", name, params.join(", "))
+}
+
+/// Line (0-based) of the first match of a definition pattern in a script
+#[cfg_attr(
+    not(any(feature = "dynamic-helpers", feature = "rhai-helpers")),
+    allow(dead_code)
+)]
+pub(crate) fn definition_line(code: &str, definition: &Regex) -> Option<usize> {
+    definition
+        .find(code)
+        .map(|found| code[..found.start()].matches('\n').count())
+}
+
+/// The comment right above line `line` (0-based) of a helper script, its lines joined:
+/// a `/* */` block, or a run of lines starting with `marker` (`//`, `--`). Blank lines
+/// and `@tag` lines are left out.
+#[cfg_attr(
+    not(any(
+        feature = "dynamic-helpers",
+        feature = "lua-helpers",
+        feature = "python-helpers",
+        feature = "rhai-helpers"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn comment_above(code: &str, line: usize, marker: &str) -> Option<String> {
+    let above: Vec<&str> = code.lines().take(line).map(str::trim).collect();
+    let mut text: Vec<&str> = Vec::new();
+    if above.last().is_some_and(|last| last.ends_with("*/")) {
+        for line in above.iter().rev() {
+            text.push(
+                line.trim_end_matches("*/")
+                    .trim_start_matches('/')
+                    .trim_start_matches('*')
+                    .trim(),
+            );
+            if line.starts_with("/*") {
+                break;
+            }
+        }
+    } else {
+        text = above
+            .iter()
+            .rev()
+            .take_while(|line| line.starts_with(marker))
+            .map(|line| line.trim_start_matches(|c| marker.contains(c)).trim())
+            .collect();
+    }
+    text.retain(|line| !line.is_empty() && !line.starts_with('@'));
+    text.reverse();
+    (!text.is_empty()).then(|| text.join(" "))
+}
+
+/// Handlebars set up for the settings (escaping, strict mode, built-in helpers), with
+/// the names of every helper it knows
+fn new_handlebars(
+    settings: &JsonImportSettings,
+    locale: &Arc<i18n::Locale>,
+    strict: bool,
+) -> (Handlebars<'static>, HashSet<String>) {
+    let mut hb = Handlebars::new();
+    hb.set_strict_mode(strict);
+    if settings.auto_escape {
+        let flavor = settings.flavor;
+        hb.register_escape_fn(move |text| flavor.escape(text));
+    } else {
+        hb.register_escape_fn(handlebars::no_escape);
+    }
+    let known_helpers = HANDLEBARS_BUILTIN_HELPERS
+        .iter()
+        .chain(register_helpers(&mut hb, locale).iter())
+        .map(|name| name.to_string())
+        .collect();
+    (hb, known_helpers)
+}
+
+// ============================================================================
+// Core Generation Logic
+// ============================================================================
+
+/// Determine output strategy based on CLI args, data structure, and settings
+fn determine_output_strategy(
+    output_arg: Option<&PathBuf>,
+    out_dir_arg: Option<&PathBuf>,
+    split_arg: Option<Option<&str>>,
+    data: &Value,
+    settings: &JsonImportSettings,
+    create_dirs: bool,
+) -> Result<OutputStrategy> {
+    // Directories are not created for dry runs or archive output
+    let create_dir = |dir: &Path| -> Result<()> {
+        if create_dirs {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    };
+
+    // Parse split configuration
+    let split_config = split_arg.map(SplitConfig::from_arg);
+
+    // Explicit output directory: no file/directory guessing
+    if let Some(dir) = out_dir_arg {
+        create_dir(dir)?;
+        return Ok(OutputStrategy::MultiFile {
+            directory: dir.clone(),
+            split_config,
+        });
+    }
+
+    match output_arg {
+        // Rendered output goes to stdout
+        Some(out) if is_stdout(out) => Ok(OutputStrategy::SingleFile(out.clone())),
+        // User explicitly specified output path
+        Some(out) => {
+            // Check if it's likely a directory vs file
+            let is_dir = out.is_dir()
+                || out.to_string_lossy().ends_with('/')
+                || out.to_string_lossy().ends_with('\\')
+                || (out.extension().is_none() && out.file_name().is_some());
+
+            if is_dir {
+                // Ensure directory exists
+                create_dir(out)?;
+                Ok(OutputStrategy::MultiFile {
+                    directory: out.clone(),
+                    split_config,
+                })
+            } else {
+                // Single-file mode: ensure parent dir exists
+                if let Some(parent) = out.parent() {
+                    create_dir(parent)?;
+                }
+                Ok(OutputStrategy::SingleFile(out.clone()))
+            }
+        }
+        // No output specified: infer from data structure
+        None => {
+            match data {
+                // Single-context mode or single-item array: default to single-file mode
+                _ if settings.single_context => single_file_for_item(data, settings),
+                Value::Array(arr) if arr.len() == 1 => single_file_for_item(&arr[0], settings),
+                // Multiple items: default to multi-file mode with optional split
+                _ => {
+                    let out_dir = PathBuf::from(&settings.folder_name);
+                    create_dir(&out_dir)?;
+                    Ok(OutputStrategy::MultiFile {
+                        directory: out_dir,
+                        split_config,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Whether an output path means stdout ("-")
+fn is_stdout(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Single-file strategy named after the item's json_name field
+fn single_file_for_item(item: &Value, settings: &JsonImportSettings) -> Result<OutputStrategy> {
+    let base_name = if settings.json_name.contains("{{") {
+        // Template syntax: use placeholder (user should use -o for this case)
+        "output".to_string()
+    } else {
+        objfield(item, &settings.json_name, None)
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_else(|| "output".to_string())
+    };
+
+    // Per-item extension templates need -o as well
+    let extension = if settings.output_extension.contains("{{") {
+        "md".to_string()
+    } else {
+        output_extension(&settings.output_extension)
+    };
+    let filename = format!(
+        "{}{}{}.{}",
+        settings.note_prefix,
+        note_filename(&base_name, settings),
+        settings.note_suffix,
+        extension
+    );
+
+    Ok(OutputStrategy::SingleFile(PathBuf::from(filename)))
+}
+
+/// Generate filename for a single item based on split configuration
+fn generate_item_filename(
+    item: &Value,
+    idx: usize,
+    base_name: &str,
+    split_config: Option<&SplitConfig>,
+    settings: &JsonImportSettings,
+    hb: &Handlebars<'_>,
+) -> Result<String> {
+    let name = match split_config {
+        None => {
+            // Use settings.json_name (original behavior)
+            if settings.json_name.contains("{{") {
+                render_raw(hb, &settings.json_name, &HbContext::wraps(item)?)?
+            } else {
+                objfield(item, &settings.json_name, None)
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_else(|| format!("item_{}", idx))
+            }
+        }
+        Some(config) if config.is_index_mode() => {
+            // Index mode: append counter
+            format!("{}_{}", base_name, idx)
+        }
+        Some(config) if config.is_template_mode() => {
+            // Handlebars template mode: render with full context
+            render_raw(hb, &config.template, &HbContext::wraps(item)?)?
+        }
+        Some(config) => {
+            // JSON path mode: extract field value
+            objfield(item, &config.template, None)
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_else(|| format!("{}_{}", base_name, idx))
+        }
+    };
+
+    // Apply prefix/suffix and sanitize
+    let final_name = format!(
+        "{}{}{}",
+        settings.note_prefix,
+        note_filename(&name, settings),
+        settings.note_suffix
+    );
+
+    Ok(final_name)
+}
+
+/// Items rendered in parallel before their output is written in order
+const RENDER_BATCH: usize = 1024;
+
+/// Keys injected into every render context (item fields with these names are overwritten)
+const RESERVED_CONTEXT_KEYS: &[&str] = &[
+    "SourceIndex",
+    "dataRoot",
+    "SourceFilename",
+    "SourceDir",
+    "SourceModified",
+    "_note_name_",
+    "_note_dir_",
+    "aggregates",
+    "vars",
+    "globals",
+    "params",
+    "env",
+    "lookup",
+    "GroupKey",
+    "GroupItems",
+    "GroupSize",
+];
+
+/// Resolve the value to iterate over (supports nested top_field)
+fn resolve_target(data: &Value, settings: &JsonImportSettings) -> Result<Value> {
+    if settings.top_field.is_empty() {
+        return Ok(data.clone());
+    }
+    objfield(data, &settings.top_field, None)
+        .with_context(|| format!("Field '{}' not found", settings.top_field))
+}
+
+/// Flatten the target value into the list of items to render
+fn collect_items(target: &Value, force_array: bool) -> Vec<&Value> {
+    match target {
+        Value::Array(arr) => arr.iter().collect(),
+        Value::Object(_) if force_array => vec![target],
+        Value::Object(obj) => obj.values().collect(),
+        _ => vec![target],
+    }
+}
+
+/// Provenance of a loaded data source, injected into the context of its items
+#[derive(Clone, Debug, Default)]
+struct SourceInfo {
+    /// File name (or URL) exposed as `SourceFilename`
+    filename: String,
+    /// Containing directory as given on the command line, exposed as `SourceDir`
+    dir: Option<String>,
+    /// Last modification time (RFC 3339), exposed as `SourceModified`
+    modified: Option<String>,
+}
+
+impl SourceInfo {
+    fn new(source: &std::path::Path) -> Self {
+        let text = source.to_string_lossy();
+        if input::is_url(&text) {
+            return Self {
+                filename: text.to_string(),
+                ..Default::default()
+            };
+        }
+        let modified = fs::metadata(source)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .map(|time| {
+                DateTime::<Local>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+            });
+        Self {
+            filename: source
+                .file_name()
+                .map_or_else(|| text.to_string(), |n| n.to_string_lossy().to_string()),
+            dir: source.parent().map(|dir| dir.to_string_lossy().to_string()),
+            modified,
+        }
+    }
+
+    /// Insert the provenance keys into a render context
+    fn inject(&self, ctx_map: &mut serde_json::Map<String, Value>) {
+        ctx_map.insert("SourceFilename".into(), self.filename.clone().into());
+        if let Some(dir) = &self.dir {
+            ctx_map.insert("SourceDir".into(), dir.clone().into());
+        }
+        if let Some(modified) = &self.modified {
+            ctx_map.insert("SourceModified".into(), modified.clone().into());
+        }
+    }
+}
+
+/// Merge several loaded sources into one item array (top_field is applied per source).
+/// Returns the merged data and, for each item, the index of the source it came from.
+fn merge_sources(loaded: Vec<Value>, settings: &JsonImportSettings) -> Result<(Value, Vec<usize>)> {
+    let mut items = Vec::new();
+    let mut item_sources = Vec::new();
+    for (source_idx, data) in loaded.iter().enumerate() {
+        let target = resolve_target(data, settings)?;
+        for item in collect_items(&target, settings.force_array) {
+            items.push(item.clone());
+            item_sources.push(source_idx);
+        }
+    }
+    Ok((Value::Array(items), item_sources))
+}
+
+/// One rendered output before it is written
+struct RenderedItem {
+    /// File name without extension (prefix and suffix applied)
+    name: String,
+    /// Subdirectory from folder_template, relative to the output directory
+    folder: PathBuf,
+    /// File extension without the dot
+    extension: String,
+    body: String,
+    /// Why the item failed when `body` is a placeholder (--on-error placeholder)
+    failure: Option<ItemFailure>,
+}
+
+/// Turn a rendered folder_template into a relative directory: each segment is sanitized,
+/// and empty, `.` and `..` segments are dropped so files stay inside the output directory
+fn folder_path(rendered: &str) -> PathBuf {
+    rendered
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(|segment| valid_filename(segment, false))
+        .collect()
+}
+
+/// Normalize an output extension: no leading dot, safe characters, "md" when empty
+fn output_extension(ext: &str) -> String {
+    match valid_filename(ext.trim().trim_start_matches('.'), false) {
+        ext if ext.is_empty() => "md".to_string(),
+        ext => ext,
+    }
+}
+
+/// What to do when an output file already exists (--if-exists)
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+enum IfExists {
+    /// Replace the file
+    #[default]
+    Overwrite,
+    /// Keep the existing file and move on
+    Skip,
+    /// Stop with an error
+    Error,
+    /// Keep a copy as <name>.md.bak, then replace the file
+    Backup,
+}
+
+/// What to do when an item fails to render (--on-error)
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+enum OnError {
+    /// Stop with the error
+    #[default]
+    Abort,
+    /// Report the item and continue without it
+    Skip,
+    /// Report the item and write a file noting the error in its place
+    Placeholder,
+}
+
+/// What happened to an output file
+#[derive(Clone, Copy, PartialEq)]
+enum WriteOutcome {
+    Created,
+    Updated,
+    BackedUp,
+    Merged,
+    Unchanged,
+    Skipped,
+}
+
+impl std::fmt::Display for WriteOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Created => "Created",
+            Self::Updated => "Updated",
+            Self::BackedUp => "Updated (backup kept)",
+            Self::Merged => "Updated (merged)",
+            Self::Unchanged => "Unchanged",
+            Self::Skipped => "Skipped (exists)",
+        })
+    }
+}
+
+/// Output file counts for the --incremental summary, and the files for the run report
+#[derive(Default)]
+struct WriteStats {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    skipped: usize,
+    /// Bytes of the files created or updated
+    bytes: usize,
+    /// `{path, outcome, bytes}` per output file
+    files: Vec<Value>,
+}
+
+impl WriteOutcome {
+    /// Name of the outcome in the run report
+    fn key(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::BackedUp => "backed_up",
+            Self::Merged => "merged",
+            Self::Unchanged => "unchanged",
+            Self::Skipped => "skipped",
+        }
+    }
+
+    /// What a dry run would do to the file
+    fn planned(self) -> &'static str {
+        match self {
+            Self::Created => "create",
+            Self::Updated => "overwrite",
+            Self::BackedUp => "back up and overwrite",
+            Self::Merged => "merge into",
+            Self::Unchanged => "leave unchanged",
+            Self::Skipped => "skip existing",
+        }
+    }
+}
+
+impl WriteStats {
+    fn record(&mut self, outcome: WriteOutcome, path: &Path, bytes: usize) {
+        match outcome {
+            WriteOutcome::Created => self.created += 1,
+            WriteOutcome::Updated | WriteOutcome::BackedUp | WriteOutcome::Merged => {
+                self.updated += 1
+            }
+            WriteOutcome::Unchanged => self.unchanged += 1,
+            WriteOutcome::Skipped => self.skipped += 1,
+        }
+        if !matches!(outcome, WriteOutcome::Unchanged | WriteOutcome::Skipped) {
+            self.bytes += bytes;
+        }
+        self.files.push(serde_json::json!({
+            "path": path.to_string_lossy(),
+            "outcome": outcome.key(),
+            "bytes": bytes,
+        }));
+    }
+}
+
+/// Write an output file. Existing files get their frontmatter merged with
+/// --merge-frontmatter, or only their managed regions replaced; in incremental mode a
+/// file whose content is already identical is left untouched so its modification time
+/// does not change; other existing files are handled per --if-exists; a dry run only
+/// reports what would happen
+fn write_output(
+    sink: &mut dyn OutputSink,
+    path: &Path,
+    body: &str,
+    run: &RunOptions,
+) -> Result<WriteOutcome> {
+    let existing = sink.read(path);
+    let existing_text = existing
+        .as_deref()
+        .and_then(|bytes| std::str::from_utf8(bytes).ok());
+    let merged = match existing_text {
+        Some(text) if run.merge_frontmatter => merge::merge_frontmatter(text, body)
+            .with_context(|| format!("Failed to merge frontmatter into {}", path.display()))?,
+        Some(text) => merge::merge_regions(text, body),
+        None => None,
+    };
+    let body = merged.as_deref().unwrap_or(body);
+    if run.incremental && existing.as_deref() == Some(body.as_bytes()) {
+        return Ok(WriteOutcome::Unchanged);
+    }
+
+    let outcome = match (existing.is_some(), run.if_exists) {
+        (false, _) => WriteOutcome::Created,
+        // Merges protect the hand-written parts, so they are always applied
+        (true, IfExists::Backup) if merged.is_some() => WriteOutcome::BackedUp,
+        (true, _) if merged.is_some() => WriteOutcome::Merged,
+        (true, IfExists::Overwrite) => WriteOutcome::Updated,
+        (true, IfExists::Skip) => return Ok(WriteOutcome::Skipped),
+        (true, IfExists::Error) => anyhow::bail!(
+            "Output file already exists: {} (use --if-exists overwrite|skip|backup)",
+            path.display()
+        ),
+        (true, IfExists::Backup) => WriteOutcome::BackedUp,
+    };
+    if run.dry_run {
+        return Ok(outcome);
+    }
+    if outcome == WriteOutcome::BackedUp
+        && let Some(previous) = &existing
+    {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        sink.write(Path::new(&backup), previous)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+    }
+    sink.write(path, body.as_bytes())?;
+    Ok(outcome)
+}
+
+/// Per-run inputs that are not part of the persisted settings
+#[derive(Default)]
+struct RunOptions {
+    /// Template rendered for each item (the first one with several --template)
+    template: String,
+    /// Name of the data in messages, and `SourceFilename` of contexts spanning sources
+    source_label: String,
+    /// Shared template variables from --vars, exposed as `vars`
+    vars: Option<Value>,
+    /// Site-wide values from --context, exposed as `globals`
+    globals: Option<Value>,
+    /// Parameters from --param, exposed as `params`
+    params: Option<Value>,
+    /// Environment variables selected by --env-prefix, exposed as `env`
+    env: Option<Value>,
+    /// Secondary datasets from --lookup, exposed as `lookup.<name>`
+    lookup: Option<Value>,
+    /// Loaded data sources
+    sources: Vec<SourceInfo>,
+    /// Source index per item when several sources were merged (empty: all from the first)
+    item_sources: Vec<usize>,
+    /// Items to skip before rendering (--skip)
+    skip: usize,
+    /// Maximum number of items to render (--limit)
+    limit: Option<usize>,
+    /// Items per page in chunk mode (--chunk)
+    chunk: Option<usize>,
+    /// JSON file streamed item by item instead of loaded (--stream)
+    stream: Option<PathBuf>,
+    /// Encoding label the streamed file is transcoded from (--encoding)
+    stream_encoding: Option<String>,
+    /// Leave outputs whose content is unchanged untouched (--incremental)
+    incremental: bool,
+    /// Render everything but write nothing, reporting planned files and errors (--dry-run)
+    dry_run: bool,
+    /// Policy for items that fail to render (--on-error, --keep-going)
+    on_error: OnError,
+    /// JSON file listing the failed items (--errors-report)
+    errors_report: Option<PathBuf>,
+    /// JSON summary of the run (--report)
+    report: Option<PathBuf>,
+    /// Policy for output files that already exist (--if-exists)
+    if_exists: IfExists,
+    /// Merge rendered frontmatter into existing files, keeping their body (--merge-frontmatter)
+    merge_frontmatter: bool,
+    /// Template rendered once over all generated files (--index-template)
+    index_template: Option<String>,
+    /// File name of the rendered index, relative to the output directory (--index-name)
+    index_name: String,
+    /// Navigation to keep in sync with the generated pages (--nav)
+    nav: Option<nav::NavKind>,
+    /// Navigation file overriding the default location (--nav-file)
+    nav_file: Option<PathBuf>,
+    /// Archive receiving all outputs instead of the filesystem (--archive); the caller
+    /// passes an `ArchiveSink`, this only turns off what an archive cannot hold
+    archive: Option<PathBuf>,
+    /// Named templates (name, source) when several are rendered per item (--template)
+    artifacts: Vec<(String, String)>,
+    /// Convert rendered Markdown to HTML (--render-html)
+    render_html: bool,
+    /// Handlebars layout wrapping the HTML, with `content` and `title` (--html-layout)
+    html_layout: Option<String>,
+}
+
+impl RunOptions {
+    /// Source an item at the given index was loaded from
+    fn source_for(&self, idx: usize) -> Option<&SourceInfo> {
+        let source_idx = self.item_sources.get(idx).copied().unwrap_or(0);
+        self.sources.get(source_idx)
+    }
+
+    /// Add the run-wide values to a render context: `aggregates`, `vars`, `globals`,
+    /// `params`, `env` and `lookup`, each only when there is one
+    fn inject_context(
+        &self,
+        ctx_map: &mut serde_json::Map<String, Value>,
+        aggregates: &serde_json::Map<String, Value>,
+    ) {
+        if !aggregates.is_empty() {
+            ctx_map.insert("aggregates".into(), Value::Object(aggregates.clone()));
+        }
+        let values = [
+            ("vars", &self.vars),
+            ("globals", &self.globals),
+            ("params", &self.params),
+            ("env", &self.env),
+            ("lookup", &self.lookup),
+        ];
+        for (key, value) in values {
+            if let Some(value) = value {
+                ctx_map.insert(key.into(), value.clone());
+            }
+        }
+    }
+}
+
+/// Process data and generate markdown using the run's template and the helpers; files
+/// go to the sink (stdout output, "-", is written directly)
+fn generate_notes(
+    hb: &mut Handlebars<'_>,
+    data: Value,
+    settings: &JsonImportSettings,
+    output_strategy: OutputStrategy,
+    run: &RunOptions,
+    sink: &mut dyn OutputSink,
+) -> Result<()> {
+    let started = Instant::now();
+    let source_name = run.source_label.as_str();
+    info_log!("Converting: {}", source_name);
+
+    // Chunk mode writes one file per page next to the requested output
+    let chunk = run.chunk.filter(|_| !settings.single_context);
+    if chunk.is_some() && !settings.group_by.is_empty() {
+        anyhow::bail!("--chunk cannot be combined with group_by");
+    }
+    let (output_strategy, page_stem) = converter::paged_output(output_strategy, chunk.is_some())?;
+
+    // Group renders are named by their key unless json_name is a template;
+    // pages are always named "<stem>_page_<n>" so prevPage/nextPage links resolve
+    let grouped = !settings.group_by.is_empty() && !settings.single_context;
+    let combined_settings;
+    let settings = if let Some(stem) = &page_stem {
+        combined_settings = JsonImportSettings {
+            json_name: format!("{}_page_{{{{page}}}}", stem),
+            note_prefix: String::new(),
+            note_suffix: String::new(),
+            ..settings.clone()
+        };
+        &combined_settings
+    } else if grouped && !settings.json_name.contains("{{") {
+        combined_settings = JsonImportSettings {
+            json_name: "GroupKey".to_string(),
+            ..settings.clone()
+        };
+        &combined_settings
+    } else {
+        settings
+    };
+
+    hb.register_template_string("tpl", &run.template)
+        .context("Template compilation failed")?;
+    if let Some(layout_src) = &run.html_layout {
+        hb.register_template_string("layout", layout_src)
+            .context("HTML layout compilation failed")?;
+    }
+    if let Some(index_src) = &run.index_template {
+        hb.register_template_string("index", index_src)
+            .context("Index template compilation failed")?;
+    }
+    // Each named template renders into its own subdirectory
+    let outputs: Vec<(PathBuf, String)> = if run.artifacts.is_empty() {
+        vec![(PathBuf::new(), "tpl".to_string())]
+    } else {
+        if matches!(output_strategy, OutputStrategy::SingleFile(_)) {
+            anyhow::bail!("Several --template outputs need multi-file output (use --out-dir)");
+        }
+        let mut outputs = Vec::new();
+        for (name, src) in &run.artifacts {
+            let registered = format!("tpl:{}", name);
+            hb.register_template_string(&registered, src)
+                .with_context(|| format!("Template '{}' compilation failed", name))?;
+            outputs.push((PathBuf::from(name), registered));
+        }
+        outputs
+    };
+    // The dataset is shared with the dataRoot helper; per-item copies are opt-in
+    let data = Arc::new(data);
+    hb.register_helper("dataRoot", Box::new(DataRootHelper(Arc::clone(&data))));
+    hb.register_helper(
+        "escape",
+        Box::new(Markup(Box::new(EscapeHelper(settings.flavor)))),
+    );
+    hb.register_helper(
+        "assetPath",
+        Box::new(Markup(Box::new(AssetPathHelper(settings.assets.clone())))),
+    );
+    let inject_root = converter::wants_data_root(settings, run);
+    debug_log!("🌳 dataRoot in context: {}", inject_root);
+    let hb: &Handlebars<'_> = hb;
+
+    let data_ref: &Value = &data;
+
+    let target = resolve_target(data_ref, settings)?;
+    // Items keep their original position, so per-source metadata survives
+    // filtering/reordering
+    let items = converter::select_items(&target, settings, run)?;
+
+    let values: Vec<&Value> = items.iter().map(|(_, item)| *item).collect();
+
+    // Precompute aggregates once for all renders
+    let aggregates = converter::compute_aggregates(&settings.aggregate, data_ref, &values)?;
+    debug_log!("📊 Aggregates: {:?}", aggregates);
+
+    // For single-file mode: accumulate content
+    let mut single_file_content = String::new();
+    let mut item_count = 0;
+    let item_separator = settings.flavor.separator();
+
+    // Markdown to HTML, wrapped in the layout template when one was given
+    let to_html = |markdown: &str, item: &Value, title: &str| -> Result<String> {
+        let content = html::markdown_to_html(markdown);
+        if run.html_layout.is_none() {
+            return Ok(content);
+        }
+        let mut ctx_map = match item {
+            Value::Object(obj) => obj.clone(),
+            _ => serde_json::Map::new(),
+        };
+        ctx_map.insert("content".into(), content.into());
+        ctx_map.insert("title".into(), title.into());
+        ctx_map.insert("SourceFilename".into(), source_name.into());
+        // The layout is HTML: Markdown auto-escaping does not apply
+        let layout = hb
+            .get_template("layout")
+            .context("HTML layout not registered")?;
+        render_unescaped(hb, layout, &HbContext::from(Value::Object(ctx_map)))
+            .context("HTML layout render failed")
+    };
+
+    // output_extension for an item, rendered against its context when it is a template
+    let item_extension = |ctx: &HbContext| -> Result<String> {
+        if settings.output_extension.contains("{{") {
+            let rendered = render_raw(hb, &settings.output_extension, ctx)
+                .context("output_extension render failed")?;
+            Ok(output_extension(&rendered))
+        } else {
+            Ok(output_extension(&settings.output_extension))
+        }
+    };
+
+    // Rendering only reads shared state, so it can run on several threads
+    let render_item = |item: &Value,
+                       idx: usize,
+                       origin: usize,
+                       output: &OutputStrategy|
+     -> Result<Vec<RenderedItem>> {
+        // A single-context root renders as it is, whatever its shape
+        if !item.is_object() && !settings.single_context {
+            return Ok(Vec::new());
+        }
+
+        // Build render context with item data + metadata
+        let mut ctx_map = serde_json::Map::new();
+        if let Value::Object(obj) = item {
+            ctx_map.extend(obj.clone());
+        }
+        ctx_map.insert("SourceIndex".into(), (idx as i64).into());
+        if inject_root {
+            ctx_map.insert("dataRoot".into(), data_ref.clone());
+        }
+        // Group and page contexts span sources, so they only get the overall source label
+        let spans_items = grouped || page_stem.is_some();
+        match run.source_for(origin).filter(|_| !spans_items) {
+            Some(source) => source.inject(&mut ctx_map),
+            None => {
+                ctx_map.insert("SourceFilename".into(), source_name.into());
+            }
+        }
+        run.inject_context(&mut ctx_map, &aggregates);
+
+        // Generate filename for this item (used for multi-file output OR template context)
+        let item_filename = match output {
+            OutputStrategy::MultiFile {
+                directory,
+                split_config,
+            } => {
+                // Multi-file mode: generate actual output filename
+                let base_name = directory
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("output");
+
+                generate_item_filename(item, idx, base_name, split_config.as_ref(), settings, hb)?
+            }
+            OutputStrategy::SingleFile(_) => {
+                // Single-file mode: generate placeholder for template context only
+                if settings.json_name.contains("{{") {
+                    render_raw(
+                        hb,
+                        &settings.json_name,
+                        &HbContext::from(Value::Object(ctx_map.clone())),
+                    )
+                    .unwrap_or_default()
+                } else {
+                    let ctx_for_lookup = Value::Object(ctx_map.clone());
+                    objfield(&ctx_for_lookup, &settings.json_name, Some(data_ref))
+                        .and_then(|v| v.as_str().map(String::from))
+                        .unwrap_or_else(|| format!("item_{}", idx))
+                }
+            }
+        };
+
+        // Add _note_name_ to context so templates can reference it (optional but useful)
+        ctx_map.insert("_note_name_".into(), Value::String(item_filename.clone()));
+        // Rebuild ctx with _note_name_ included; other roots carry no metadata
+        let mut ctx = if item.is_object() {
+            HbContext::from(Value::Object(ctx_map))
+        } else {
+            HbContext::from(item.clone())
+        };
+
+        // For multi-file mode: skip items with empty filenames (can't write _.md)
+        if matches!(output, OutputStrategy::MultiFile { .. }) && item_filename.is_empty() {
+            debug_log!("⚠️ Skipping item {}: empty filename (multi-file mode)", idx);
+            return Ok(Vec::new());
+        }
+
+        // Subdirectory for multi-file output, rendered with the same context
+        let folder = match output {
+            OutputStrategy::MultiFile { .. } if !settings.folder_template.is_empty() => {
+                let rendered = render_raw(hb, &settings.folder_template, &ctx)
+                    .context("folder_template render failed")?;
+                folder_path(&rendered)
+            }
+            _ => PathBuf::new(),
+        };
+        // Folder of the note within the output directory, for relative asset links
+        if let Value::Object(map) = ctx.data_mut() {
+            let note_dir = match output {
+                OutputStrategy::MultiFile { .. } => folder
+                    .join(note_filename(&item_filename, settings))
+                    .parent()
+                    .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default(),
+                OutputStrategy::SingleFile(_) => String::new(),
+            };
+            map.insert("_note_dir_".into(), Value::String(note_dir));
+        }
+        let extension = item_extension(&ctx)?;
+
+        // Frontmatter belongs to a note, so it is only added when each item is its own file
+        let note = matches!(output, OutputStrategy::MultiFile { .. }) || settings.single_context;
+        let frontmatter = if note && !settings.frontmatter.is_empty() && !run.render_html {
+            Some(frontmatter::build(
+                &settings.frontmatter,
+                &|src| Ok(render_raw(hb, src, &ctx)?),
+                &|path| objfield(ctx.data(), path, Some(data_ref)),
+            )?)
+        } else {
+            None
+        };
+
+        let mut rendered = Vec::with_capacity(outputs.len());
+        for (subdir, template) in &outputs {
+            let (body, failure) = match hb.render_with_context(template, &ctx) {
+                Ok(body) => (body, None),
+                Err(e) => {
+                    let file = match output {
+                        OutputStrategy::MultiFile { .. } => subdir.join(&folder).join(format!(
+                            "{}.{}",
+                            note_filename(&item_filename, settings),
+                            extension
+                        )),
+                        OutputStrategy::SingleFile(path) => path.clone(),
+                    };
+                    let failure = render_failure(e, idx, &file);
+                    if run.on_error != OnError::Placeholder {
+                        return Err(failure.into());
+                    }
+                    // "--" would end the comment early
+                    let note = failure.to_string().replace("--", "- -");
+                    (format!("<!-- json2md: {} -->\n", note), Some(failure))
+                }
+            };
+            let body = match &frontmatter {
+                Some(mapping) => frontmatter::prepend(&body, mapping.clone())?,
+                None => body,
+            };
+            // Multi-file HTML is converted per item; a single file is converted once at the end
+            let (body, extension) = match output {
+                OutputStrategy::MultiFile { .. } if run.render_html => {
+                    let html = to_html(&body, item, &item_filename)?;
+                    let extension = if extension == "md" {
+                        "html".to_string()
+                    } else {
+                        extension.clone()
+                    };
+                    (html, extension)
+                }
+                _ => (body, extension.clone()),
+            };
+            rendered.push(RenderedItem {
+                name: item_filename.clone(),
+                folder: subdir.join(&folder),
+                extension,
+                body,
+                failure,
+            });
+        }
+        Ok(rendered)
+    };
+
+    // Output is written in item order, so collision suffixes do not depend on thread timing
+    let mut seen_names = HashSet::new();
+    let mut stats = WriteStats::default();
+    // Names that were taken by an earlier item, with the path used instead
+    let mut collisions = Vec::new();
+    // Generated files and their items for the index template
+    let mut index_entries = Vec::new();
+    // Generated pages for --nav
+    let mut nav_entries = Vec::new();
+    let mut asset_planner = assets::AssetPlanner::default();
+    let mut emit =
+        |idx: usize, origin: usize, item: &Value, rendered: RenderedItem| -> Result<()> {
+            let RenderedItem {
+                name,
+                folder,
+                extension,
+                body,
+                ..
+            } = rendered;
+
+            // Referenced assets go below the output directory (or next to the single file)
+            let asset_base = match &output_strategy {
+                _ if settings.assets.fields.is_empty() => None,
+                OutputStrategy::MultiFile { directory, .. } => Some(directory.clone()),
+                OutputStrategy::SingleFile(file) if !is_stdout(file) => {
+                    Some(file.parent().map(Path::to_path_buf).unwrap_or_default())
+                }
+                OutputStrategy::SingleFile(_) => None,
+            };
+            if let Some(base) = asset_base {
+                let source_dir = run
+                    .source_for(origin)
+                    .and_then(|source| source.dir.as_deref())
+                    .map(Path::new);
+                let (copies, warnings) = asset_planner.plan(&settings.assets, item, source_dir);
+                for warning in warnings {
+                    warn_log!("{}", warning);
+                }
+                for copy in copies {
+                    if !copy.source.is_file() {
+                        warn_log!("Asset not found: {}", copy.source.display());
+                        continue;
+                    }
+                    let target = base.join(&copy.target);
+                    if run.dry_run {
+                        success_log!(
+                            "Would copy: {} -> {}",
+                            copy.source.display(),
+                            target.display()
+                        );
+                        continue;
+                    }
+                    let content = fs::read(&copy.source).with_context(|| {
+                        format!("Failed to copy asset {}", copy.source.display())
+                    })?;
+                    if sink.read(&target).as_ref() != Some(&content) {
+                        sink.write(&target, &content)?;
+                        success_log!("Copied: {}", target.display());
+                    }
+                }
+            }
+
+            // Handle output based on strategy
+            match &output_strategy {
+                OutputStrategy::SingleFile(_output_file) => {
+                    // SINGLE-FILE MODE: Accumulate content
+                    if item_count > 0 {
+                        single_file_content.push_str(item_separator);
+                    }
+                    single_file_content.push_str(&body);
+                    item_count += 1;
+                    debug_log!(
+                        "📝 Appended item {} to single output ({} bytes)",
+                        idx,
+                        body.len()
+                    );
+                }
+                OutputStrategy::MultiFile {
+                    directory: output_dir,
+                    ..
+                } => {
+                    // MULTI-FILE MODE: Write individual files using generated filename
+                    let safe = note_filename(&name, settings);
+                    let mut path = output_dir.join(folder).join(&safe);
+                    // Files differing only in extension (note.md, note.canvas) do not collide
+                    path.set_extension(&extension);
+
+                    // Handle filename collisions
+                    let path_str = path.to_string_lossy().to_string();
+                    let collided = seen_names.contains(&path_str);
+                    if settings.unique_names || collided {
+                        let base = path.clone();
+                        let mut n = 0;
+                        while seen_names.contains(&path.to_string_lossy().to_string()) {
+                            n += 1;
+                            path = base.with_file_name(format!(
+                                "{}{}",
+                                base.file_stem().unwrap().to_string_lossy(),
+                                n
+                            ));
+                            if let Some(ext) = base.extension() {
+                                path = path.with_extension(ext);
+                            }
+                        }
+                    }
+                    seen_names.insert(path.to_string_lossy().to_string());
+                    if collided {
+                        collisions.push(serde_json::json!({
+                            "index": idx,
+                            "name": safe,
+                            "path": path.to_string_lossy(),
+                        }));
+                    }
+                    let file = path.strip_prefix(output_dir).unwrap_or(&path);
+                    if run.index_template.is_some() {
+                        index_entries.push(serde_json::json!({
+                            "file": file.to_string_lossy(),
+                            "name": file.with_extension("").to_string_lossy(),
+                            "index": idx,
+                            "item": item,
+                        }));
+                    }
+                    if run.nav.is_some() {
+                        nav_entries.push(nav::NavEntry::new(file, item));
+                    }
+                    let outcome = write_output(sink, &path, &body, run)?;
+                    stats.record(outcome, &path, body.len());
+
+                    if run.dry_run {
+                        success_log!("Would {}: {}", outcome.planned(), path.display());
+                    } else if outcome == WriteOutcome::Unchanged {
+                        debug_log!("⏭️ Unchanged: {}", path.display());
+                    } else if run.archive.is_some() {
+                        // The archive reports its entries once it is finished
+                    } else {
+                        debug_log!("✅ Wrote {} bytes to {}", body.len(), path.display());
+                        success_log!("{}: {}", outcome, path.display());
+                    }
+                    item_count += 1;
+                }
+            }
+            Ok(())
+        };
+
+    // A dry run or --on-error skip/placeholder reports every failed item instead of
+    // stopping at the first
+    let mut failures: Vec<ItemFailure> = Vec::new();
+    // Render time per item for the run report
+    let mut timings = Vec::new();
+    let mut deliver = |idx: usize,
+                       origin: usize,
+                       item: &Value,
+                       (result, elapsed): (Result<Vec<RenderedItem>>, Duration)|
+     -> Result<()> {
+        timings.push(serde_json::json!({
+            "index": idx,
+            "ms": elapsed.as_secs_f64() * 1000.0,
+        }));
+        match result {
+            Ok(rendered) => rendered.into_iter().try_for_each(|mut output| {
+                if let Some(failure) = output.failure.take() {
+                    error_log!("{}", failure);
+                    failures.push(failure);
+                }
+                emit(idx, origin, item, output)
+            }),
+            Err(e) if run.dry_run || run.on_error != OnError::Abort => {
+                let failure = ItemFailure::from_error(e, idx);
+                error_log!("{}", failure);
+                failures.push(failure);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    // An item's outputs with the time it took to render them
+    let render_timed = |unit: &Value, idx: usize, origin: usize, output: &OutputStrategy| {
+        let start = Instant::now();
+        (render_item(unit, idx, origin, output), start.elapsed())
+    };
+
+    // Render batches in parallel and emit each batch in order; errors surface in item order too.
+    // Units are items with their original position, or group/page/root contexts without one.
+    let mut render_all = |units: &[(Option<usize>, &Value)]| -> Result<()> {
+        for (batch_no, batch) in units.chunks(RENDER_BATCH).enumerate() {
+            let offset = batch_no * RENDER_BATCH;
+            let origin = |i: usize, unit_origin: Option<usize>| unit_origin.unwrap_or(offset + i);
+            let rendered: Vec<_> = batch
+                .par_iter()
+                .enumerate()
+                .map(|(i, (unit_origin, unit))| {
+                    render_timed(unit, offset + i, origin(i, *unit_origin), &output_strategy)
+                })
+                .collect();
+            for (i, ((unit_origin, unit), result)) in batch.iter().zip(rendered).enumerate() {
+                deliver(offset + i, origin(i, *unit_origin), unit, result)?;
+            }
+        }
+        Ok(())
+    };
+
+    match converter::RenderMode::of(settings, run, page_stem.as_deref()) {
+        converter::RenderMode::Stream(path) => {
+            converter::stream_items(path, settings, run, |position, item| {
+                let result = render_timed(item, position, position, &output_strategy);
+                deliver(position, position, item, result)
+            })?
+        }
+        converter::RenderMode::SingleContext => render_all(&[(None, data_ref)])?,
+        converter::RenderMode::Pages { size, stem } => {
+            // Links name the files the pages are written to (Markdown pages become HTML)
+            converter::render_pages(&values, size, stem, &mut render_all, |page| {
+                let extension = item_extension(&HbContext::from(page.clone()))?;
+                Ok(match extension.as_str() {
+                    "md" if run.render_html => "html".to_string(),
+                    _ => extension,
+                })
+            })?
+        }
+        converter::RenderMode::Groups(field) => {
+            converter::render_groups(&values, field, &mut render_all)?
+        }
+        converter::RenderMode::Items => converter::render_items(&items, &mut render_all)?,
+    }
+
+    // The single output file, the index of the generated files and site navigation
+    if let OutputStrategy::SingleFile(output_file) = &output_strategy {
+        if run.render_html && item_count > 0 {
+            let title = output_file.file_stem().map_or_else(
+                || source_name.to_string(),
+                |s| s.to_string_lossy().to_string(),
+            );
+            single_file_content = to_html(&single_file_content, &Value::Null, &title)?;
+        }
+        converter::write_single_file(
+            output_file,
+            &single_file_content,
+            item_count,
+            run,
+            sink,
+            &mut stats,
+        )?;
+    }
+    if run.index_template.is_some() {
+        converter::write_index(
+            hb,
+            &output_strategy,
+            index_entries,
+            &aggregates,
+            run,
+            sink,
+            &mut stats,
+        )?;
+    }
+    if let Some(kind) = run.nav {
+        converter::write_nav(kind, &output_strategy, &nav_entries, run, sink)?;
+    }
+
+    if !run.dry_run {
+        sink.finish()?;
+    }
+
+    converter::report_run(run, &stats, &failures, timings, collisions, started)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A date `days` before the run's now, as "YYYY-MM-DD"
+    fn days_ago(days: i64) -> Value {
+        let date = run_now().date_naive() - chrono::Duration::days(days);
+        json!(date.format("%Y-%m-%d").to_string())
+    }
+
+    #[test]
+    fn time_bucket_labels_by_days_ago() {
+        assert_eq!(time_bucket(&days_ago(-3), 7, 30), "Upcoming");
+        assert_eq!(time_bucket(&days_ago(-1), 7, 30), "Upcoming");
+        assert_eq!(time_bucket(&days_ago(0), 7, 30), "Today");
+        assert_eq!(time_bucket(&days_ago(1), 7, 30), "Yesterday");
+        assert_eq!(time_bucket(&days_ago(6), 7, 30), "This week");
+        assert_eq!(time_bucket(&days_ago(7), 7, 30), "This month");
+        assert_eq!(time_bucket(&days_ago(30), 7, 30), "Older");
+        assert_eq!(time_bucket(&days_ago(2), 2, 3), "This month");
+    }
+
+    #[test]
+    fn time_bucket_leaves_unparseable_dates_blank() {
+        assert_eq!(time_bucket(&json!("soon"), 7, 30), "");
+        assert_eq!(time_bucket(&Value::Null, 7, 30), "");
+    }
+
+    #[test]
+    fn single_context_renders_any_root_as_is() {
+        let render = |data: Value, template: &str| {
+            let mut sink = MemorySink::default();
+            Converter::new()
+                .data(data)
+                .template(template)
+                .settings(JsonImportSettings {
+                    single_context: true,
+                    ..Default::default()
+                })
+                .output(Destination::File("all.md".into()))
+                .run_into(&mut sink)
+                .unwrap();
+            sink.text("all.md").unwrap().to_string()
+        };
+        let rows = json!([{"name": "a"}, {"name": "b"}]);
+        assert_eq!(render(rows, "{{#each this}}{{name}};{{/each}}"), "a;b;");
+        assert_eq!(render(json!("text"), "[{{this}}]"), "[text]");
+        assert_eq!(
+            render(json!({"title": "T"}), "{{title}} {{SourceFilename}}"),
+            "T data"
+        );
+    }
+
+    #[test]
+    fn single_context_rejects_top_field() {
+        let mut settings = JsonImportSettings {
+            single_context: true,
+            top_field: "items".into(),
+            ..Default::default()
+        };
+        assert!(settings.prepare().is_err());
+        settings.top_field.clear();
+        assert!(settings.prepare().is_ok());
+    }
+
+    #[test]
+    fn crossref_searches_datasets_and_leaves_lookup_builtin() {
+        let mut hb = Handlebars::new();
+        hb.register_helper("crossref", Box::new(CrossrefHelper));
+        let data = json!({
+            "items": [{"id": 1, "name": "one"}, {"id": "2", "name": "two"}],
+            "lookup": {"users": {"a": {"uid": 7, "name": "ann"}}},
+        });
+        let render = |src: &str| hb.render_template(src, &data).unwrap();
+        assert_eq!(
+            render(r#"{{#with (crossref items "id" 2)}}{{name}}{{/with}}"#),
+            "two"
+        );
+        assert_eq!(
+            render(r#"{{#with (crossref "users" "uid" "7")}}{{name}}{{/with}}"#),
+            "ann"
+        );
+        assert_eq!(
+            render(r#"{{#each (crossref items "id" "x" all=true)}}-{{/each}}"#),
+            ""
+        );
+        assert_eq!(render("{{#with (lookup items 0)}}{{name}}{{/with}}"), "one");
+        assert!(
+            hb.render_template(r#"{{crossref items "id"}}"#, &data)
+                .is_err()
+        );
+    }
+
+    fn tags() -> serde_json::Map<String, Value> {
+        json!({
+            "tag1": "a", "tag2": "", "tag3": "c", "tag10": "j", "tag4": null,
+            "tag01": "padded", "tag_5": "underscore", "tagline": "text", "tag": "bare"
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn gather_reads_an_inclusive_range() {
+        assert_eq!(
+            gather(&tags(), "tag", Some(1), Some(3)),
+            vec![json!("a"), json!("c")]
+        );
+        assert_eq!(
+            gather(&tags(), "tag", Some(3), Some(1)),
+            Vec::<Value>::new()
+        );
+    }
+
+    #[test]
+    fn gather_without_range_reads_every_numbered_key_in_order() {
+        // tag01 reads as index 1, which is the key tag1
+        assert_eq!(
+            gather(&tags(), "tag", None, None),
+            vec![json!("a"), json!("c"), json!("j")]
+        );
+    }
+
+    #[test]
+    fn gather_with_only_from_reads_that_key_and_above() {
+        assert_eq!(
+            gather(&tags(), "tag", Some(3), None),
+            vec![json!("c"), json!("j")]
+        );
+    }
+
+    #[test]
+    fn semver_sort_puts_invalid_versions_last_in_either_direction() {
+        let versions = json!(["1.10.0", "bad", "v1.2.0", "1.2.0-beta.1"]);
+        let versions = versions.as_array().unwrap();
+        assert_eq!(
+            Value::Array(semver_sort(versions, false)),
+            json!(["1.2.0-beta.1", "v1.2.0", "1.10.0", "bad"])
+        );
+        assert_eq!(
+            Value::Array(semver_sort(versions, true)),
+            json!(["1.10.0", "v1.2.0", "1.2.0-beta.1", "bad"])
+        );
+    }
+
+    fn rule(spec: Value) -> FilterRule {
+        let rule: FilterRule = serde_json::from_value(spec).unwrap();
+        rule.validate().unwrap();
+        rule
+    }
+
+    #[test]
+    fn filter_rule_keeps_matching_items() {
+        let item = json!({"n": "10", "tags": ["a", "b"], "title": "Hello world", "draft": null});
+        let keeps = |spec: Value| rule(spec).keeps(&item);
+        assert!(keeps(json!({"field": "n", "op": ">", "value": 9})));
+        assert!(keeps(json!({"field": "n", "value": 10})));
+        assert!(!keeps(json!({"field": "n", "op": "lt", "value": 9})));
+        assert!(keeps(
+            json!({"field": "tags", "op": "contains", "value": "b"})
+        ));
+        assert!(keeps(
+            json!({"field": "title", "op": "contains", "value": "world"})
+        ));
+        assert!(!keeps(json!({"field": "n", "op": "contains", "value": 1})));
+        assert!(keeps(
+            json!({"field": "title", "op": "matches", "value": "^Hel+o"})
+        ));
+        assert!(keeps(json!({"field": "draft", "op": "missing"})));
+        assert!(!keeps(json!({"field": "draft", "op": "exists"})));
+        assert!(keeps(json!({"field": "draft", "op": "!=", "value": true})));
+        assert!(!keeps(
+            json!({"field": "tags", "op": "contains", "value": "a", "exclude": true})
+        ));
+    }
+
+    #[test]
+    fn filter_rule_rejects_unknown_operators_and_bad_patterns() {
+        let invalid = |spec: Value| {
+            serde_json::from_value::<FilterRule>(spec)
+                .unwrap()
+                .validate()
+                .is_err()
+        };
+        assert!(invalid(json!({"field": "n", "op": "~="})));
+        assert!(invalid(
+            json!({"field": "n", "op": "matches", "value": "("})
+        ));
+    }
+
+    #[test]
+    fn write_output_follows_if_exists_and_incremental() {
+        let path = Path::new("out/note.md");
+        let mut sink = MemorySink::default();
+        let mut run = RunOptions::default();
+        let mut write = |body: &str, run: &RunOptions| {
+            write_output(&mut sink, path, body, run).map(|outcome| outcome.key())
+        };
+        assert_eq!(write("one", &run).unwrap(), "created");
+        assert_eq!(write("two", &run).unwrap(), "updated");
+        run.incremental = true;
+        assert_eq!(write("two", &run).unwrap(), "unchanged");
+        run.if_exists = IfExists::Skip;
+        assert_eq!(write("three", &run).unwrap(), "skipped");
+        run.if_exists = IfExists::Error;
+        assert!(write("three", &run).is_err());
+        run.if_exists = IfExists::Backup;
+        run.dry_run = true;
+        assert_eq!(write("three", &run).unwrap(), "backed_up");
+        run.dry_run = false;
+        assert_eq!(write("three", &run).unwrap(), "backed_up");
+        assert_eq!(sink.text(path), Some("three"));
+        assert_eq!(sink.text("out/note.md.bak"), Some("two"));
+        assert_eq!(sink.files.len(), 2);
+    }
+
+    #[test]
+    fn begin_run_resets_the_reference_time_and_warnings() {
+        let before = run_now();
+        warn_log!("left over from an earlier run");
+        std::thread::sleep(Duration::from_millis(5));
+        begin_run();
+        assert!(run_now() > before);
+        assert!(
+            !logging::take_warnings()
+                .iter()
+                .any(|w| w == "left over from an earlier run")
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Preflight template linting.
+//!
+//! Usage: --lint-template (add --strict to exit non-zero on findings), or --check to
+//! validate without rendering
+//!
+//! Catches template mistakes that Handlebars accepts but that usually render wrong:
+//! - reserved context names shadowed by item fields or block params
+//! - reserved names referenced inside context-changing blocks (each/with)
+//! - unknown block helpers
+//! - unbalanced block open/close tags
+//! - empty expressions and subexpressions
+//!
+//! --check adds what needs the full setup: the template compiles, partials and helpers
+//! (built-in, JS and plugin) exist, and fields referenced at the top level of the
+//! template occur in the data.
+
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A single lint finding with an optional template position
+pub struct LintFinding {
+    /// 1-based (line, column) in the template, None for data-level findings
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, col)) => write!(f, "{}:{}: {}", line, col, self.message),
+            None => write!(f, "data: {}", self.message),
+        }
+    }
+}
+
+/// Blocks that change the context, so bare names resolve against the inner value
+const CONTEXT_BLOCKS: &[&str] = &["each", "with"];
+
+/// Lint a template source against the known helper names and the items to be rendered
+pub fn lint_template(
+    src: &str,
+    known_helpers: &HashSet<String>,
+    reserved: &[&str],
+    items: &[&Value],
+) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+
+    // Item fields silently replaced by injected metadata
+    let mut reported = HashSet::new();
+    for item in items {
+        if let Value::Object(obj) = item {
+            for key in obj.keys() {
+                if reserved.contains(&key.as_str()) && reported.insert(key.clone()) {
+                    findings.push(LintFinding {
+                        position: None,
+                        message: format!(
+                            "item field '{}' is shadowed by the injected value of the same name",
+                            key
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    let tag_re = Regex::new(r"(?s)\{\{\{?(.*?)\}?\}\}").expect("lint tag regex");
+    let params_re = Regex::new(r"\bas\s*\|([^|]*)\|").expect("lint block params regex");
+
+    // Open blocks: (name, position, changes_context)
+    let mut stack: Vec<(String, (usize, usize), bool)> = Vec::new();
+
+    for caps in tag_re.captures_iter(src) {
+        let whole = caps.get(0).unwrap();
+        let pos = line_col(src, whole.start());
+        let inner = caps[1].trim_matches('~').trim();
+
+        // Comments and partials are not inspected
+        if inner.starts_with('!') || inner.starts_with('>') || inner.starts_with("#>") {
+            continue;
+        }
+
+        if inner.is_empty() || inner == "#" || inner == "/" {
+            findings.push(LintFinding {
+                position: Some(pos),
+                message: format!("empty expression '{}'", whole.as_str()),
+            });
+            continue;
+        }
+
+        if inner.contains("()") {
+            findings.push(LintFinding {
+                position: Some(pos),
+                message: "empty subexpression '()'".to_string(),
+            });
+        }
+
+        if let Some(open) = inner.strip_prefix('#') {
+            if let Some(decorator) = open.strip_prefix('*') {
+                // Inline partial definitions close with {{/inline}}
+                stack.push((first_token(decorator).to_string(), pos, false));
+                continue;
+            }
+            let name = first_token(open);
+            if !known_helpers.contains(name) {
+                findings.push(LintFinding {
+                    position: Some(pos),
+                    message: format!("unknown block helper '#{}'", name),
+                });
+            }
+            if let Some(params) = params_re.captures(open) {
+                for param in params[1].split_whitespace() {
+                    if reserved.contains(&param) {
+                        findings.push(LintFinding {
+                            position: Some(pos),
+                            message: format!(
+                                "block param '{}' shadows the injected value of the same name",
+                                param
+                            ),
+                        });
+                    }
+                }
+            }
+            stack.push((name.to_string(), pos, CONTEXT_BLOCKS.contains(&name)));
+            continue;
+        }
+
+        if let Some(close) = inner.strip_prefix('/') {
+            let name = first_token(close);
+            match stack.pop() {
+                Some((open, _, _)) if open == name => {}
+                Some((open, (line, col), _)) => findings.push(LintFinding {
+                    position: Some(pos),
+                    message: format!(
+                        "'{{{{/{}}}}}' does not close '{{{{#{}}}}}' opened at {}:{}",
+                        name, open, line, col
+                    ),
+                }),
+                None => findings.push(LintFinding {
+                    position: Some(pos),
+                    message: format!("'{{{{/{}}}}}' has no matching open block", name),
+                }),
+            }
+            continue;
+        }
+
+        // Bare references to reserved names inside each/with miss the injected values
+        if stack.iter().any(|(_, _, ctx)| *ctx) {
+            for token in inner.split(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+                let root = token.split(['.', '/']).next().unwrap_or("");
+                if reserved.contains(&root) {
+                    findings.push(LintFinding {
+                        position: Some(pos),
+                        message: format!(
+                            "'{}' inside a context-changing block resolves against the block value; use '@root.{}'",
+                            root, token
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    for (name, pos, _) in stack {
+        findings.push(LintFinding {
+            position: Some(pos),
+            message: format!("'{{{{#{}}}}}' is never closed", name),
+        });
+    }
+
+    findings
+}
+
+/// First whitespace-delimited token of a tag body
+fn first_token(s: &str) -> &str {
+    s.split_whitespace().next().unwrap_or("")
+}
+
+/// Convert a byte offset into a 1-based (line, column) pair
+fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset];
+    let line = before.matches('\n').count() + 1;
+    let col = before.rfind('\n').map_or(offset, |nl| offset - nl - 1) + 1;
+    (line, col)
+}
+
+/// Blocks that keep the context, so field references inside still name item fields
+const CONDITIONAL_BLOCKS: &[&str] = &["if", "unless"];
+
+/// Items sampled when checking field references
+const FIELD_SAMPLE: usize = 100;
+
+/// Full template check: the lint findings plus compilation, unknown partials and
+/// helpers and, when `items` is given, fields missing from every sampled item
+pub fn check_template(
+    src: &str,
+    known_helpers: &HashSet<String>,
+    partials: &HashSet<String>,
+    reserved: &[&str],
+    items: Option<&[&Value]>,
+) -> Vec<LintFinding> {
+    let mut findings = lint_template(src, known_helpers, reserved, items.unwrap_or(&[]));
+    if let Err(e) = handlebars::Template::compile(src) {
+        findings.push(LintFinding {
+            position: e.pos(),
+            message: format!("template does not compile: {}", e.reason()),
+        });
+        return findings;
+    }
+
+    let tag_re = Regex::new(r"(?s)\{\{\{?(.*?)\}?\}\}").expect("lint tag regex");
+    let inline_re = Regex::new(r#"\{\{~?#\*inline\s+["']([^"']+)["']"#).expect("inline regex");
+    let inline: HashSet<&str> = inline_re
+        .captures_iter(src)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .collect();
+    let sample = items.map(|items| &items[..items.len().min(FIELD_SAMPLE)]);
+
+    // Open blocks, with whether they keep the item as context
+    let mut stack: Vec<bool> = Vec::new();
+    for caps in tag_re.captures_iter(src) {
+        let whole = caps.get(0).unwrap();
+        let pos = Some(line_col(src, whole.start()));
+        let inner = caps[1].trim_matches('~').trim();
+        if inner.starts_with('!') || inner == "^" || inner.starts_with('/') {
+            if inner.starts_with('/') {
+                stack.pop();
+            }
+            continue;
+        }
+
+        // Partials: {{> name}} and partial blocks {{#> name}}
+        if let Some(rest) = inner.strip_prefix("#>").or_else(|| inner.strip_prefix('>')) {
+            let name = first_token(rest).trim_matches(['"', '\'']);
+            let dynamic = name.starts_with('(') || name.starts_with('@');
+            if !dynamic && !partials.contains(name) && !inline.contains(name) {
+                findings.push(LintFinding {
+                    position: pos,
+                    message: format!("unknown partial '{}'", name),
+                });
+            }
+            if inner.starts_with('#') {
+                stack.push(stack.last().copied().unwrap_or(true));
+            }
+            continue;
+        }
+
+        let (expr, opens) = match inner.strip_prefix('#').or_else(|| inner.strip_prefix('^')) {
+            Some(_) if inner[1..].starts_with('*') => {
+                // Inline partials render in the context of their callers
+                stack.push(false);
+                continue;
+            }
+            Some(open) => (open, true),
+            None => (inner.strip_prefix("else").unwrap_or(inner), false),
+        };
+        let expr = expr.split(" as |").next().unwrap_or(expr);
+        let at_root = stack.iter().all(|&keeps| keeps);
+        let tokens = tokens(expr);
+
+        for (i, token) in tokens.iter().enumerate() {
+            let after_paren = i > 0 && tokens[i - 1] == "(";
+            let is_head = i == 0 && !opens;
+            if after_paren || (is_head && tokens.len() > 1) {
+                if !known_helpers.contains(*token) {
+                    findings.push(LintFinding {
+                        position: pos,
+                        message: format!("unknown helper '{}'", token),
+                    });
+                }
+                continue;
+            }
+            if i == 0 && (opens || known_helpers.contains(*token)) {
+                continue;
+            }
+            let value = token.split_once('=').map_or(*token, |(_, value)| value);
+            if let (Some(sample), true) = (sample, at_root)
+                && let Some(field) = field_path(value)
+                && !reserved.contains(&field[0].as_str())
+                && !sample.iter().any(|item| has_path(item, &field))
+            {
+                findings.push(LintFinding {
+                    position: pos,
+                    message: format!(
+                        "field '{}' is not in the data (checked {} item(s))",
+                        field.join("."),
+                        sample.len()
+                    ),
+                });
+            }
+        }
+
+        if opens {
+            let name = tokens.first().copied().unwrap_or("");
+            stack.push(at_root && CONDITIONAL_BLOCKS.contains(&name));
+        }
+    }
+    findings
+}
+
+/// Words, string literals and parentheses of an expression
+fn tokens(expr: &str) -> Vec<&str> {
+    let token_re =
+        Regex::new(r#"[^\s()=]+=("[^"]*"|'[^']*'|[^\s()]+)|"[^"]*"|'[^']*'|\(|\)|[^\s()]+"#)
+            .expect("lint token regex");
+    token_re.find_iter(expr).map(|m| m.as_str()).collect()
+}
+
+/// Segments of a context path ("owner.name", "this.tags.[0]"); None for literals, data
+/// variables (@index), parent paths and the context itself
+fn field_path(token: &str) -> Option<Vec<String>> {
+    let first = token.chars().next()?;
+    if first == '"'
+        || first == '\''
+        || first == '@'
+        || first == '('
+        || first == ')'
+        || first.is_ascii_digit()
+        || first == '-'
+        || matches!(
+            token,
+            "true" | "false" | "null" | "undefined" | "this" | "."
+        )
+        || token.contains("..")
+    {
+        return None;
+    }
+    let path = token
+        .strip_prefix("this.")
+        .or_else(|| token.strip_prefix("this/"))
+        .or_else(|| token.strip_prefix("./"))
+        .unwrap_or(token);
+    let segments: Vec<String> = path
+        .split(['.', '/'])
+        .map(|segment| segment.trim_matches(['[', ']']).to_string())
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    (!segments.is_empty()).then_some(segments)
+}
+
+/// Whether a path resolves in a value (object keys, array indexes)
+fn has_path(value: &Value, path: &[String]) -> bool {
+    let mut current = value;
+    for segment in path {
+        current = match current {
+            Value::Object(map) => match map.get(segment) {
+                Some(next) => next,
+                None => return false,
+            },
+            Value::Array(items) => match segment.parse::<usize>().ok().and_then(|i| items.get(i)) {
+                Some(next) => next,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    true
+}
@@ -0,0 +1,125 @@
+//! Diagnostics: leveled messages on stderr, as text or JSON lines (--log-level,
+//! --log-format, --quiet, --verbose). Stdout only ever carries rendered output.
+//!
+//! ```text
+//! {"level":"info","message":"Created: notes/a.md","time":"2026-03-01T12:00:00.000+01:00"}
+//! ```
+//!
+//! Warnings are also kept for the run report, whatever the level.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Severity of a message; a level shows itself and everything more severe
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Level {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+/// How messages are written (--log-format)
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// Plain lines
+    #[default]
+    Text,
+    /// One JSON object per line with `time`, `level` and `message`
+    Json,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Set the level and format for the rest of the process
+pub fn init(level: Level, format: Format) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store(format == Format::Json, Ordering::Relaxed);
+}
+
+/// Whether messages of a level are shown
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Write a message to stderr if its level is shown
+pub fn log(level: Level, args: fmt::Arguments<'_>) {
+    if level == Level::Warn {
+        WARNINGS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(args.to_string());
+    }
+    if !enabled(level) {
+        return;
+    }
+    if JSON.load(Ordering::Relaxed) {
+        let line = serde_json::json!({
+            "time": chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            "level": level.name(),
+            "message": args.to_string(),
+        });
+        eprintln!("{}", line);
+        return;
+    }
+    match level {
+        Level::Error => eprintln!("Error: {}", args),
+        Level::Warn => eprintln!("⚠️ {}", args),
+        Level::Info | Level::Debug => eprintln!("{}", args),
+    }
+}
+
+/// Warnings logged since the last call
+pub fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Details shown with --verbose (or --log-level debug)
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+/// Progress and results of the run
+macro_rules! info_log {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Something the user should look at; the run goes on (listed in the run report)
+macro_rules! warn_log {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+/// A file that was written (or would be, in a dry run)
+macro_rules! success_log {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Failures, shown even with --quiet
+macro_rules! error_log {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, format_args!($($arg)*))
+    };
+}
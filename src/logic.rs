@@ -0,0 +1,220 @@
+//! Comparison and logic helpers for subexpressions: `{{#if (looseEq status "open")}}`.
+//!
+//! The Handlebars built-ins `eq`, `gt`, `and`, ... keep their strict meaning. These
+//! variants suit imported data: `looseEq`, `looseNe`, `looseGt`, `looseGte`, `looseLt`
+//! and `looseLte` compare numbers and numeric strings as numbers (a CSV "10" equals
+//! JSON 10, and is greater than "9"), and `all`/`any` take any number of arguments.
+//! Two more helpers:
+//!
+//! ```text
+//! {{#if (contains tags "urgent")}}      array element, substring or object key
+//! {{default nickname name "anonymous"}} first argument that is not null or ""
+//! ```
+
+use crate::re_err;
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, JsonTruthy, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Numeric value of a number or numeric string
+fn num(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Equality where a number matches the same number written as a string
+pub fn loose_eq(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a, b) {
+        (Value::Number(_), Value::String(_)) | (Value::String(_), Value::Number(_)) => {
+            matches!((num(a), num(b)), (Some(x), Some(y)) if x == y)
+        }
+        _ => false,
+    }
+}
+
+/// Order numerically when both sides are numbers (or numeric strings), else as text
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (num(a), num(b)) {
+        (Some(x), Some(y)) => x.partial_cmp(&y),
+        _ => match (a, b) {
+            (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+            _ => None,
+        },
+    }
+}
+
+/// Whether `needle` is an element (array), substring (string) or key (object) of `haystack`
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match haystack {
+        Value::Array(items) => items.iter().any(|item| loose_eq(item, needle)),
+        Value::String(text) => match needle {
+            Value::String(part) => text.contains(part.as_str()),
+            Value::Null => false,
+            other => text.contains(&other.to_string()),
+        },
+        Value::Object(map) => needle.as_str().is_some_and(|key| map.contains_key(key)),
+        _ => false,
+    }
+}
+
+fn params<'a>(h: &'a Helper<'_>) -> Vec<&'a Value> {
+    h.params().iter().map(|p| p.value()).collect()
+}
+
+/// The two operands of a comparison
+fn pair<'a>(h: &'a Helper<'_>) -> Result<(&'a Value, &'a Value), RenderError> {
+    match (h.param(0), h.param(1)) {
+        (Some(a), Some(b)) => Ok((a.value(), b.value())),
+        _ => Err(re_err(format!("{}: expected two arguments", h.name()))),
+    }
+}
+
+fn eq(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(loose_eq(a, b)))
+}
+
+fn ne(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(!loose_eq(a, b)))
+}
+
+fn gt(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(compare(a, b) == Some(Ordering::Greater)))
+}
+
+fn gte(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(matches!(
+        compare(a, b),
+        Some(Ordering::Greater | Ordering::Equal)
+    )))
+}
+
+fn lt(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(compare(a, b) == Some(Ordering::Less)))
+}
+
+fn lte(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (a, b) = pair(h)?;
+    Ok(Value::Bool(matches!(
+        compare(a, b),
+        Some(Ordering::Less | Ordering::Equal)
+    )))
+}
+
+fn all(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(Value::Bool(params(h).iter().all(|v| v.is_truthy(false))))
+}
+
+fn any(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(Value::Bool(params(h).iter().any(|v| v.is_truthy(false))))
+}
+
+fn contains_helper(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let (haystack, needle) = pair(h)?;
+    Ok(Value::Bool(contains(haystack, needle)))
+}
+
+fn default(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(params(h)
+        .into_iter()
+        .find(|v| !v.is_null() && v.as_str() != Some(""))
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+/// Helper computing a value from its arguments
+struct LogicHelper(fn(&Helper<'_>) -> Result<Value, RenderError>);
+
+impl HelperDef for LogicHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived((self.0)(h)?))
+    }
+}
+
+/// The comparison and logic helpers with their names
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![
+        ("looseEq", Box::new(LogicHelper(eq))),
+        ("looseNe", Box::new(LogicHelper(ne))),
+        ("looseGt", Box::new(LogicHelper(gt))),
+        ("looseGte", Box::new(LogicHelper(gte))),
+        ("looseLt", Box::new(LogicHelper(lt))),
+        ("looseLte", Box::new(LogicHelper(lte))),
+        ("all", Box::new(LogicHelper(all))),
+        ("any", Box::new(LogicHelper(any))),
+        ("contains", Box::new(LogicHelper(contains_helper))),
+        ("default", Box::new(LogicHelper(default))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn loose_eq_matches_numbers_written_as_strings() {
+        assert!(loose_eq(&json!(10), &json!("10")));
+        assert!(loose_eq(&json!(" 2.5 "), &json!(2.5)));
+        assert!(loose_eq(&json!("a"), &json!("a")));
+        assert!(!loose_eq(&json!("10"), &json!("10.0")));
+        assert!(!loose_eq(&json!("x"), &json!(0)));
+        assert!(!loose_eq(&Value::Null, &json!("")));
+        assert!(!loose_eq(&json!(true), &json!("true")));
+    }
+
+    #[test]
+    fn compare_orders_numeric_strings_as_numbers() {
+        assert_eq!(compare(&json!("10"), &json!("9")), Some(Ordering::Greater));
+        assert_eq!(compare(&json!(3), &json!("3.0")), Some(Ordering::Equal));
+        assert_eq!(
+            compare(&json!("apple"), &json!("banana")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare(&json!("10"), &json!("b")), Some(Ordering::Less));
+        assert_eq!(compare(&json!(1), &json!("b")), None);
+        assert_eq!(compare(&Value::Null, &json!(1)), None);
+    }
+
+    #[test]
+    fn contains_checks_elements_substrings_and_keys() {
+        assert!(contains(&json!(["a", 10]), &json!("10")));
+        assert!(!contains(&json!(["a"]), &json!("b")));
+        assert!(contains(&json!("urgent task"), &json!("urgent")));
+        assert!(contains(&json!("v12"), &json!(12)));
+        assert!(!contains(&json!("text"), &Value::Null));
+        assert!(contains(&json!({"k": null}), &json!("k")));
+        assert!(!contains(&json!({"k": 1}), &json!(1)));
+        assert!(!contains(&json!(5), &json!(5)));
+    }
+
+    #[test]
+    fn builtins_keep_their_names() {
+        let names: Vec<&str> = helpers().into_iter().map(|(name, _)| name).collect();
+        for builtin in ["eq", "ne", "gt", "gte", "lt", "lte", "and", "or", "not"] {
+            assert!(
+                !names.contains(&builtin),
+                "{} replaces the built-in",
+                builtin
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Dynamic Lua helper loading via mlua (Lua 5.4).
+//!
+//! Enabled with --features lua-helpers
+//! Usage: --lua-helpers path/to/helpers.lua
+//!
+//! Like JS helper files: every global function the script defines becomes a helper,
+//! called with the helper's params. A string result is written as is, nil as nothing,
+//! anything else as JSON. `helperConfig` holds the --helper-config object.
+
+use crate::HelperInfo;
+use anyhow::Result;
+use handlebars::Handlebars;
+use std::path::Path;
+
+#[cfg(feature = "lua-helpers")]
+use anyhow::Context;
+#[cfg(feature = "lua-helpers")]
+use handlebars::{
+    Context as HbContext, Helper, Output, RenderContext, RenderError, RenderErrorReason,
+};
+#[cfg(feature = "lua-helpers")]
+use mlua::{Function, Lua, LuaSerdeExt, MultiValue, Value as LuaValue};
+#[cfg(feature = "lua-helpers")]
+use serde_json::Value;
+#[cfg(feature = "lua-helpers")]
+use std::collections::HashSet;
+#[cfg(feature = "lua-helpers")]
+use std::path::PathBuf;
+#[cfg(feature = "lua-helpers")]
+use std::sync::{Arc, Mutex};
+
+/// Registry for helpers defined in Lua files
+pub struct LuaHelperRegistry {
+    /// One interpreter per loaded file, with the file and the helpers it defines
+    #[cfg(feature = "lua-helpers")]
+    states: Vec<(Arc<Mutex<Lua>>, PathBuf, Vec<String>)>,
+    /// Configuration for helpers (--helper-config), an object
+    #[cfg(feature = "lua-helpers")]
+    config: Value,
+}
+
+impl LuaHelperRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "lua-helpers")]
+            states: Vec::new(),
+            #[cfg(feature = "lua-helpers")]
+            config: Value::Object(serde_json::Map::new()),
+        }
+    }
+
+    /// Stub implementation when lua-helpers feature is disabled
+    #[cfg(not(feature = "lua-helpers"))]
+    pub fn set_config(&mut self, _config: serde_json::Value) {}
+
+    /// Configuration given to files loaded afterwards as the `helperConfig` global
+    #[cfg(feature = "lua-helpers")]
+    pub fn set_config(&mut self, config: Value) {
+        self.config = config;
+    }
+
+    /// Stub implementation when lua-helpers feature is disabled
+    #[cfg(not(feature = "lua-helpers"))]
+    pub fn load_lua_helpers(&mut self, _path: &Path) -> Result<Vec<String>> {
+        anyhow::bail!("Lua helpers require: cargo build --features lua-helpers")
+    }
+
+    /// Stub implementation when lua-helpers feature is disabled
+    #[cfg(not(feature = "lua-helpers"))]
+    pub fn register_with_handlebars(&self, _hb: &mut Handlebars<'_>) {}
+
+    /// Stub implementation when lua-helpers feature is disabled
+    #[cfg(not(feature = "lua-helpers"))]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        Vec::new()
+    }
+
+    /// Run a Lua file and collect the global functions it defines
+    #[cfg(feature = "lua-helpers")]
+    pub fn load_lua_helpers(&mut self, lua_path: &Path) -> Result<Vec<String>> {
+        let code = std::fs::read_to_string(lua_path)
+            .with_context(|| format!("Failed to read Lua: {}", lua_path.display()))?;
+
+        let lua = Lua::new();
+        let discovered = {
+            let globals = lua.globals();
+            globals.set(
+                "helperConfig",
+                lua.to_value_with(&self.config, to_lua_options())?,
+            )?;
+
+            // Whatever exists before the script runs is the standard library
+            let builtin: HashSet<String> = globals
+                .clone()
+                .pairs::<String, LuaValue>()
+                .filter_map(|pair| pair.ok().map(|(key, _)| key))
+                .collect();
+
+            lua.load(&code)
+                .set_name(lua_path.to_string_lossy())
+                .exec()
+                .map_err(|e| anyhow::anyhow!("{}", e.to_string().trim_end()))?;
+
+            let mut found: Vec<String> = globals
+                .pairs::<String, LuaValue>()
+                .filter_map(|pair| pair.ok())
+                .filter(|(key, value)| value.is_function() && !builtin.contains(key))
+                .map(|(key, _)| key)
+                .collect();
+            found.sort();
+            found
+        };
+
+        self.states.push((
+            Arc::new(Mutex::new(lua)),
+            lua_path.to_path_buf(),
+            discovered.clone(),
+        ));
+        Ok(discovered)
+    }
+
+    /// Register the discovered Lua helpers with Handlebars
+    #[cfg(feature = "lua-helpers")]
+    pub fn register_with_handlebars(&self, hb: &mut Handlebars<'_>) {
+        for (lua, _, names) in &self.states {
+            for name in names {
+                let lua = lua.clone();
+                let lua_name = name.clone();
+                let helper = move |h: &Helper<'_>,
+                                   _: &Handlebars<'_>,
+                                   _: &HbContext,
+                                   _: &mut RenderContext<'_, '_>,
+                                   out: &mut dyn Output|
+                      -> Result<(), RenderError> {
+                    let lua = lua.lock().unwrap_or_else(|e| e.into_inner());
+                    let text = call_lua(&lua, &lua_name, h).map_err(|e| {
+                        RenderErrorReason::Other(format!("Helper '{}': {}", lua_name, e))
+                    })?;
+                    out.write(&text)?;
+                    Ok(())
+                };
+                hb.register_helper(name, Box::new(helper));
+            }
+        }
+    }
+
+    /// The loaded Lua helpers for --list-helpers, with the params and the `--` comment
+    /// of the line each function is defined on
+    #[cfg(feature = "lua-helpers")]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        let params = regex::Regex::new(r"function[^(]*\(([^)]*)\)").expect("Lua params regex");
+        let mut list = Vec::new();
+        for (lua, path, names) in &self.states {
+            let code = std::fs::read_to_string(path).unwrap_or_default();
+            let lua = lua.lock().unwrap_or_else(|e| e.into_inner());
+            for name in names {
+                let line = lua
+                    .globals()
+                    .get::<_, Function>(name.as_str())
+                    .ok()
+                    .and_then(|func| func.info().line_defined)
+                    .and_then(|line| line.checked_sub(1));
+                let signature = line
+                    .and_then(|line| code.lines().nth(line))
+                    .and_then(|text| params.captures(text))
+                    .map(|caps| crate::helper_signature(name, &caps[1]));
+                list.push(HelperInfo {
+                    name: name.clone(),
+                    source: path.display().to_string(),
+                    signature,
+                    description: line.and_then(|line| crate::comment_above(&code, line, "--")),
+                });
+            }
+        }
+        list
+    }
+}
+
+/// JSON null becomes nil, so missing fields test false in Lua
+#[cfg(feature = "lua-helpers")]
+fn to_lua_options() -> mlua::SerializeOptions {
+    mlua::SerializeOptions::new()
+        .serialize_none_to_null(false)
+        .serialize_unit_to_null(false)
+}
+
+/// Call a global Lua function with the helper's params and render its result
+#[cfg(feature = "lua-helpers")]
+fn call_lua(lua: &Lua, name: &str, h: &Helper<'_>) -> mlua::Result<String> {
+    let func: Function = lua.globals().get(name)?;
+    let args = h
+        .params()
+        .iter()
+        .map(|param| lua.to_value_with(param.value(), to_lua_options()))
+        .collect::<mlua::Result<Vec<_>>>()?;
+    let result: LuaValue = func.call(MultiValue::from_vec(args))?;
+    Ok(match result {
+        LuaValue::Nil => String::new(),
+        LuaValue::String(s) => s.to_str()?.to_string(),
+        other => serde_json::to_string(&lua.from_value::<Value>(other)?)
+            .map_err(mlua::Error::external)?,
+    })
+}
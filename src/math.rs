@@ -0,0 +1,285 @@
+//! Built-in arithmetic and number formatting helpers.
+//!
+//! ```text
+//! {{add price tax}}  {{sub a b}}  {{mul qty price}}  {{div total count}}
+//! {{round ratio 2}}  {{sum items "price"}}  {{sum scores}}
+//! {{numberFormat 1234567.891 2}}               1,234,567.89
+//! {{numberFormat amount 2 thousands="." point=","}}
+//! {{numberFormat 0.256 1 percent=true}}        25.6%
+//! ```
+//!
+//! Numeric strings (as CSV cells often are) count as numbers. Results that are whole
+//! numbers render without a fractional part. With --locale, `numberFormat` defaults to
+//! the locale's separators.
+
+use crate::i18n::Locale;
+use crate::{path, re_err};
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Numeric value of a JSON number or numeric string
+fn num(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(f64::from(u8::from(*b))),
+        _ => None,
+    }
+}
+
+/// JSON number for a result, integral when it has no fractional part
+fn number(value: f64) -> Value {
+    if value.is_finite() && value.fract() == 0.0 && value.abs() < 9.0e15 {
+        Value::from(value as i64)
+    } else {
+        serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
+
+/// Required numeric argument
+fn arg(h: &Helper<'_>, idx: usize, name: &str) -> Result<f64, RenderError> {
+    let value = h.param(idx).map(|p| p.value());
+    value.and_then(num).ok_or_else(|| {
+        re_err(format!(
+            "{}: argument {} is not a number ({})",
+            name,
+            idx + 1,
+            value.map_or("missing".to_string(), Value::to_string)
+        ))
+    })
+}
+
+fn add(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(number(arg(h, 0, "add")? + arg(h, 1, "add")?))
+}
+
+fn sub(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(number(arg(h, 0, "sub")? - arg(h, 1, "sub")?))
+}
+
+fn mul(h: &Helper<'_>) -> Result<Value, RenderError> {
+    Ok(number(arg(h, 0, "mul")? * arg(h, 1, "mul")?))
+}
+
+fn div(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let divisor = arg(h, 1, "div")?;
+    if divisor == 0.0 {
+        return Err(re_err("div: division by zero"));
+    }
+    Ok(number(arg(h, 0, "div")? / divisor))
+}
+
+/// {{round x [decimals]}}
+fn round(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let value = arg(h, 0, "round")?;
+    let decimals = h.param(1).and_then(|p| num(p.value())).unwrap_or(0.0) as i32;
+    let factor = 10f64.powi(decimals);
+    Ok(number((value * factor).round() / factor))
+}
+
+/// {{sum array [field]}}: total of the numeric values (or of a field of each object);
+/// other values are skipped
+fn sum(h: &Helper<'_>) -> Result<Value, RenderError> {
+    let Some(Value::Array(items)) = h.param(0).map(|p| p.value()) else {
+        return Ok(number(0.0));
+    };
+    let field = h.param(1).and_then(|p| p.value().as_str());
+    let total = items
+        .iter()
+        .filter_map(|item| match field {
+            Some(field) => path::select(item, field).as_ref().and_then(num),
+            None => num(item),
+        })
+        .sum();
+    Ok(number(total))
+}
+
+/// Format with fixed decimals and grouped thousands
+fn format_number(value: f64, decimals: usize, thousands: &str, point: &str) -> String {
+    let fixed = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = fixed.split_once('.').unwrap_or((&fixed, ""));
+    let mut out = String::with_capacity(fixed.len() + fixed.len() / 3);
+    if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    for (i, digit) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            out.push_str(thousands);
+        }
+        out.push(digit);
+    }
+    if !frac_part.is_empty() {
+        out.push_str(point);
+        out.push_str(frac_part);
+    }
+    out
+}
+
+/// {{numberFormat x [decimals] [thousands=","] [point="."] [percent=true]}}; decimals
+/// default to 0 for whole numbers and percentages, otherwise 2. Non-numbers render as is
+fn number_format(h: &Helper<'_>, locale: &Locale) -> Result<Value, RenderError> {
+    let Some(raw) = h.param(0).map(|p| p.value()) else {
+        return Ok(Value::String(String::new()));
+    };
+    let Some(mut value) = num(raw) else {
+        return Ok(match raw {
+            Value::Null => Value::String(String::new()),
+            other => other.clone(),
+        });
+    };
+    let names = locale.names();
+    let hash_str = |key: &str, default: &'static str| -> String {
+        h.hash_get(key)
+            .and_then(|p| p.value().as_str().map(String::from))
+            .unwrap_or_else(|| default.to_string())
+    };
+    let percent = h
+        .hash_get("percent")
+        .is_some_and(|p| p.value().as_bool() == Some(true));
+    if percent {
+        value *= 100.0;
+    }
+    let decimals = match h.param(1).or_else(|| h.hash_get("decimals")) {
+        Some(p) => num(p.value()).unwrap_or(0.0).max(0.0) as usize,
+        None if percent || value.fract() == 0.0 => 0,
+        None => 2,
+    };
+    let mut out = format_number(
+        value,
+        decimals,
+        &hash_str("thousands", names.thousands),
+        &hash_str("point", names.point),
+    );
+    if percent {
+        out.push('%');
+    }
+    Ok(Value::String(out))
+}
+
+/// Numeric helper computing a value from its arguments
+struct MathHelper(fn(&Helper<'_>) -> Result<Value, RenderError>);
+
+impl HelperDef for MathHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived((self.0)(h)?))
+    }
+}
+
+/// {{numberFormat}}, with the separators of the locale
+struct NumberFormatHelper(Arc<Locale>);
+
+impl HelperDef for NumberFormatHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived(number_format(h, &self.0)?))
+    }
+}
+
+/// The math helpers with their names, formatting numbers for a locale
+pub fn helpers(locale: &Arc<Locale>) -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![
+        ("add", Box::new(MathHelper(add))),
+        ("sub", Box::new(MathHelper(sub))),
+        ("mul", Box::new(MathHelper(mul))),
+        ("div", Box::new(MathHelper(div))),
+        ("round", Box::new(MathHelper(round))),
+        ("sum", Box::new(MathHelper(sum))),
+        (
+            "numberFormat",
+            Box::new(NumberFormatHelper(Arc::clone(locale))),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, locale: Locale) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        for (name, helper) in helpers(&Arc::new(locale)) {
+            hb.register_helper(name, helper);
+        }
+        let data = json!({
+            "items": [{"price": "1.5"}, {"price": 2}, {"name": "free"}],
+            "scores": [1, "x", 2, null]
+        });
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn arithmetic_takes_numeric_strings_and_keeps_whole_results_integral() {
+        let out = |template| render(template, Locale::default()).unwrap();
+        assert_eq!(
+            out(r#"{{add 2 "3"}}|{{sub true 1}}|{{mul 1.5 2}}|{{div 7 2}}"#),
+            "5|0|3|3.5"
+        );
+        assert_eq!(
+            out("{{round 3.14159 2}}|{{round 2.5}}|{{round 1234 -2}}"),
+            "3.14|3|1200"
+        );
+        assert_eq!(
+            out(r#"{{sum items "price"}}|{{sum scores}}|{{sum missing}}"#),
+            "3.5|3|0"
+        );
+        assert_eq!(number(f64::NAN), Value::Null);
+        assert_eq!(number(1e16), json!(1e16));
+    }
+
+    #[test]
+    fn arithmetic_rejects_missing_or_non_numeric_arguments() {
+        let err = |template| render(template, Locale::default()).unwrap_err().to_string();
+        assert!(err(r#"{{add "a" 1}}"#).contains(r#"add: argument 1 is not a number ("a")"#));
+        assert!(err("{{mul 2}}").contains("mul: argument 2 is not a number (missing)"));
+        assert!(err("{{div 1 0}}").contains("division by zero"));
+        assert!(err("{{round items}}").contains("round: argument 1"));
+    }
+
+    #[test]
+    fn number_format_groups_digits_with_locale_separators() {
+        let out = |template, locale| render(template, locale).unwrap();
+        let en = Locale::default;
+        assert_eq!(out("{{numberFormat 1234567.891 2}}", en()), "1,234,567.89");
+        assert_eq!(
+            out("{{numberFormat 1234}}|{{numberFormat 1234.5}}", en()),
+            "1,234|1,234.50"
+        );
+        assert_eq!(
+            out("{{numberFormat -0.001 2}}|{{numberFormat -999.5 0}}", en()),
+            "0.00|-1,000"
+        );
+        assert_eq!(
+            out(
+                "{{numberFormat 0.256 1 percent=true}}|{{numberFormat 0.5 percent=true}}",
+                en()
+            ),
+            "25.6%|50%"
+        );
+        assert_eq!(
+            out(r#"{{numberFormat 1234.5 2 thousands=" " point=","}}"#, en()),
+            "1 234,50"
+        );
+        assert_eq!(
+            out(
+                r#"{{numberFormat "n/a"}}|{{numberFormat missing}}|{{numberFormat 5 -1}}"#,
+                en()
+            ),
+            "n/a||5"
+        );
+        let de = Locale::new("de", None).unwrap();
+        assert_eq!(out("{{numberFormat 1234.5 decimals=1}}", de), "1.234,5");
+    }
+}
@@ -0,0 +1,175 @@
+//! Updating existing output files in place instead of overwriting them.
+//!
+//! Managed regions: an existing file containing
+//!
+//! ```text
+//! <!-- json2md:begin -->
+//! ...generated...
+//! <!-- json2md:end -->
+//! ```
+//!
+//! only has the text between the markers replaced; everything outside is kept. If the
+//! rendered output has markers itself, its regions replace the existing ones in order,
+//! otherwise the whole rendered output fills the (first) region.
+//!
+//! Frontmatter merge (--merge-frontmatter): the rendered YAML frontmatter keys are
+//! merged into the existing file's frontmatter (new values win, other keys stay) and
+//! the existing body is left untouched.
+
+use crate::input::split_frontmatter;
+use anyhow::{Context, Result};
+use serde_yaml::Mapping;
+
+/// Opening marker of a managed region
+pub const BEGIN_MARKER: &str = "<!-- json2md:begin -->";
+/// Closing marker of a managed region
+pub const END_MARKER: &str = "<!-- json2md:end -->";
+
+/// Byte ranges of the region contents (between the markers), in order
+fn regions(text: &str) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(begin) = text[pos..].find(BEGIN_MARKER) {
+        let start = pos + begin + BEGIN_MARKER.len();
+        let Some(end) = text[start..].find(END_MARKER) else {
+            break;
+        };
+        found.push((start, start + end));
+        pos = start + end + END_MARKER.len();
+    }
+    found
+}
+
+/// Replace the managed regions of `existing` with the rendered output;
+/// None when `existing` has no regions
+pub fn merge_regions(existing: &str, rendered: &str) -> Option<String> {
+    let targets = regions(existing);
+    if targets.is_empty() {
+        return None;
+    }
+    let sources: Vec<&str> = match regions(rendered).as_slice() {
+        [] => vec![rendered.trim_matches('\n')],
+        found => found
+            .iter()
+            .map(|&(start, end)| rendered[start..end].trim_matches('\n'))
+            .collect(),
+    };
+
+    let mut merged = String::with_capacity(existing.len() + rendered.len());
+    let mut pos = 0;
+    for (i, &(start, end)) in targets.iter().enumerate() {
+        merged.push_str(&existing[pos..start]);
+        match sources.get(i) {
+            Some(content) => {
+                merged.push('\n');
+                merged.push_str(content);
+                merged.push('\n');
+            }
+            // More regions in the file than in the output: leave the extras alone
+            None => merged.push_str(&existing[start..end]),
+        }
+        pos = end;
+    }
+    merged.push_str(&existing[pos..]);
+    Some(merged)
+}
+
+/// Merge the rendered frontmatter into `existing`, keeping its body; None when the
+/// rendered output has no frontmatter
+pub fn merge_frontmatter(existing: &str, rendered: &str) -> Result<Option<String>> {
+    let Some((new_yaml, _)) = split_frontmatter(rendered) else {
+        return Ok(None);
+    };
+    let new: Mapping = parse_mapping(new_yaml).context("Invalid rendered frontmatter")?;
+    let (mut fields, body) = match split_frontmatter(existing) {
+        Some((yaml, body)) => (
+            parse_mapping(yaml).context("Invalid frontmatter in existing file")?,
+            body,
+        ),
+        None => (Mapping::new(), existing),
+    };
+
+    let before = fields.clone();
+    for (key, value) in new {
+        fields.insert(key, value);
+    }
+    if split_frontmatter(existing).is_some() && fields == before {
+        // Nothing new: keep the file byte-for-byte (formatting and comments included)
+        return Ok(Some(existing.to_string()));
+    }
+    let yaml = serde_yaml::to_string(&fields)?;
+    Ok(Some(format!("---\n{}---\n{}", yaml, body)))
+}
+
+/// Parse frontmatter text as a mapping (empty frontmatter is an empty mapping)
+fn parse_mapping(yaml: &str) -> Result<Mapping> {
+    if yaml.trim().is_empty() {
+        return Ok(Mapping::new());
+    }
+    Ok(serde_yaml::from_str(yaml)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_regions_replace_only_the_marked_text() {
+        let existing = "# Notes\n<!-- json2md:begin -->\nold\n<!-- json2md:end -->\nkept\n";
+        assert_eq!(
+            merge_regions(existing, "\nnew body\n").unwrap(),
+            "# Notes\n<!-- json2md:begin -->\nnew body\n<!-- json2md:end -->\nkept\n"
+        );
+
+        // Rendered regions fill the existing ones in order; extra file regions stay
+        let existing = "a<!-- json2md:begin -->1<!-- json2md:end -->b\
+                        <!-- json2md:begin -->2<!-- json2md:end -->c\
+                        <!-- json2md:begin -->3<!-- json2md:end -->";
+        let rendered = "<!-- json2md:begin -->X<!-- json2md:end -->ignored\
+                        <!-- json2md:begin -->Y<!-- json2md:end -->";
+        assert_eq!(
+            merge_regions(existing, rendered).unwrap(),
+            "a<!-- json2md:begin -->\nX\n<!-- json2md:end -->b\
+             <!-- json2md:begin -->\nY\n<!-- json2md:end -->c\
+             <!-- json2md:begin -->3<!-- json2md:end -->"
+        );
+
+        assert_eq!(merge_regions("no markers", "new"), None);
+        // An unclosed marker is not a region
+        assert_eq!(merge_regions("<!-- json2md:begin -->open", "new"), None);
+    }
+
+    #[test]
+    fn frontmatter_merge_updates_keys_and_keeps_the_body() {
+        let existing = "---\ntitle: Old\n# my note\ntags: [a]\n---\nHand-written body\n";
+        assert_eq!(
+            merge_frontmatter(existing, "---\ntitle: New\nviews: 3\n---\ngenerated body\n")
+                .unwrap()
+                .unwrap(),
+            "---\ntitle: New\ntags:\n- a\nviews: 3\n---\nHand-written body\n"
+        );
+        // Nothing new keeps the file byte-for-byte, comments included
+        assert_eq!(
+            merge_frontmatter(existing, "---\ntitle: Old\n---\n")
+                .unwrap()
+                .unwrap(),
+            existing
+        );
+        // A file without frontmatter gets one in front of its text
+        assert_eq!(
+            merge_frontmatter("Just text\n", "---\nid: 1\n---\nbody")
+                .unwrap()
+                .unwrap(),
+            "---\nid: 1\n---\nJust text\n"
+        );
+        assert_eq!(
+            merge_frontmatter("---\n---\nx", "---\n---\ny")
+                .unwrap()
+                .unwrap(),
+            "---\n---\nx"
+        );
+        assert_eq!(merge_frontmatter(existing, "no frontmatter").unwrap(), None);
+        assert!(merge_frontmatter(existing, "---\n[1, 2]\n---\n").is_err());
+        assert!(merge_frontmatter("---\na: [1\n---\n", "---\nb: 2\n---\n").is_err());
+    }
+}
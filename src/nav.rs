@@ -0,0 +1,305 @@
+//! Site navigation for generated pages (--nav mkdocs|mdbook).
+//!
+//! MkDocs: the top-level `nav:` block of mkdocs.yml (by default next to the output
+//! directory, which is the docs_dir) is replaced; the rest of the file is kept as is.
+//! mdBook: SUMMARY.md in the output directory (the book's src/) is rewritten, or only
+//! its json2md managed region when it has one.
+//!
+//! Pages are titled by their item's `title` field (else the file name) and grouped into
+//! sections by folder.
+
+use crate::merge;
+use anyhow::Result;
+use serde_json::Value;
+use serde_yaml::{Mapping, Value as Yaml};
+use std::path::{Path, PathBuf};
+
+/// Navigation format to maintain
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum NavKind {
+    Mkdocs,
+    Mdbook,
+}
+
+/// A generated page: path relative to the output directory and its title
+pub struct NavEntry {
+    pub file: PathBuf,
+    pub title: String,
+}
+
+impl NavEntry {
+    /// Entry for a generated file, titled from the item
+    pub fn new(file: &Path, item: &Value) -> Self {
+        let title = item
+            .get("title")
+            .and_then(Value::as_str)
+            .filter(|t| !t.trim().is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| {
+                file.file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+        Self {
+            file: file.to_path_buf(),
+            title,
+        }
+    }
+}
+
+/// Page or folder in the navigation tree
+enum Node {
+    Page { title: String, path: String },
+    Section { title: String, children: Vec<Node> },
+}
+
+/// Group entries by folder, keeping the order files were generated in
+fn tree(entries: &[NavEntry]) -> Vec<Node> {
+    let mut root = Vec::new();
+    for entry in entries {
+        let folders: Vec<String> = entry
+            .file
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let mut level = &mut root;
+        for folder in folders {
+            let pos = level
+                .iter()
+                .position(|n| matches!(n, Node::Section { title, .. } if *title == folder));
+            let pos = pos.unwrap_or_else(|| {
+                level.push(Node::Section {
+                    title: folder,
+                    children: Vec::new(),
+                });
+                level.len() - 1
+            });
+            let Node::Section { children, .. } = &mut level[pos] else {
+                unreachable!("position matched a section");
+            };
+            level = children;
+        }
+        level.push(Node::Page {
+            title: entry.title.clone(),
+            // Nav paths always use forward slashes
+            path: entry
+                .file
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        });
+    }
+    root
+}
+
+impl NavKind {
+    /// Default navigation file for an output directory
+    pub fn default_file(self, out_dir: &Path) -> PathBuf {
+        match self {
+            NavKind::Mkdocs => out_dir
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join("mkdocs.yml"),
+            NavKind::Mdbook => out_dir.join("SUMMARY.md"),
+        }
+    }
+
+    /// New content of the navigation file (`existing` is its current content, if any)
+    pub fn update(self, existing: Option<&str>, entries: &[NavEntry]) -> Result<String> {
+        let nodes = tree(entries);
+        match self {
+            NavKind::Mkdocs => {
+                let mut nav = Mapping::new();
+                nav.insert("nav".into(), Yaml::Sequence(mkdocs_items(&nodes)));
+                let block = serde_yaml::to_string(&nav)?;
+                Ok(match existing {
+                    Some(text) => replace_nav_block(text, &block),
+                    None => format!("site_name: Documentation\n{}", block),
+                })
+            }
+            NavKind::Mdbook => {
+                let mut list = String::new();
+                summary_items(&nodes, 0, &mut list);
+                Ok(
+                    match existing.and_then(|text| merge::merge_regions(text, &list)) {
+                        Some(merged) => merged,
+                        None => format!("# Summary\n\n{}", list),
+                    },
+                )
+            }
+        }
+    }
+}
+
+fn mkdocs_items(nodes: &[Node]) -> Vec<Yaml> {
+    nodes
+        .iter()
+        .map(|node| {
+            let mut entry = Mapping::new();
+            match node {
+                Node::Page { title, path } => {
+                    entry.insert(title.as_str().into(), path.as_str().into())
+                }
+                Node::Section { title, children } => entry.insert(
+                    title.as_str().into(),
+                    Yaml::Sequence(mkdocs_items(children)),
+                ),
+            };
+            Yaml::Mapping(entry)
+        })
+        .collect()
+}
+
+/// Swap the top-level `nav:` block of mkdocs.yml, appending one if there is none
+fn replace_nav_block(text: &str, block: &str) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let Some(start) = lines
+        .iter()
+        .position(|l| l.starts_with("nav:") || l.starts_with("\"nav\":"))
+    else {
+        let sep = if text.is_empty() || text.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return format!("{}{}{}", text, sep, block);
+    };
+    // The block runs until the next top-level key (indented lines, comments and
+    // unindented "- " sequence items belong to it)
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| {
+            !(l.trim().is_empty()
+                || l.starts_with([' ', '\t', '#'])
+                || l.starts_with("- ")
+                || l.trim_end() == "-")
+        })
+        .map_or(lines.len(), |n| start + 1 + n);
+    let mut out = lines[..start].concat();
+    out.push_str(block);
+    out.push_str(&lines[end..].concat());
+    out
+}
+
+fn summary_items(nodes: &[Node], depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    for node in nodes {
+        match node {
+            Node::Page { title, path } => {
+                let target = if path.contains(' ') {
+                    format!("<{}>", path)
+                } else {
+                    path.clone()
+                };
+                out.push_str(&format!("{}- [{}]({})\n", indent, link_text(title), target));
+            }
+            // Folders are draft chapters holding their pages
+            Node::Section { title, children } => {
+                out.push_str(&format!("{}- [{}]()\n", indent, link_text(title)));
+                summary_items(children, depth + 1, out);
+            }
+        }
+    }
+}
+
+/// Escape brackets so a title cannot end the link text early
+fn link_text(title: &str) -> String {
+    title.replace('[', "\\[").replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries() -> Vec<NavEntry> {
+        vec![
+            NavEntry::new(Path::new("intro.md"), &json!({"title": "Intro [draft]"})),
+            NavEntry::new(Path::new("guide/setup.md"), &json!({"title": " "})),
+            NavEntry::new(Path::new("api/ref one.md"), &json!({"title": "Reference"})),
+            NavEntry::new(Path::new("guide/usage.md"), &json!({"title": 3})),
+        ]
+    }
+
+    #[test]
+    fn mkdocs_nav_block_is_replaced_and_the_rest_kept() {
+        let existing = concat!(
+            "site_name: Docs\n",
+            "nav:\n  - Old: old.md\n# old comment\n- Loose: x.md\n",
+            "theme: material\n"
+        );
+        assert_eq!(
+            NavKind::Mkdocs.update(Some(existing), &entries()).unwrap(),
+            concat!(
+                "site_name: Docs\n",
+                "nav:\n",
+                "- Intro [draft]: intro.md\n",
+                "- guide:\n",
+                "  - setup: guide/setup.md\n",
+                "  - usage: guide/usage.md\n",
+                "- api:\n",
+                "  - Reference: api/ref one.md\n",
+                "theme: material\n"
+            )
+        );
+        assert_eq!(
+            NavKind::Mkdocs
+                .update(Some("site_name: Docs"), &entries()[..1])
+                .unwrap(),
+            "site_name: Docs\nnav:\n- Intro [draft]: intro.md\n"
+        );
+        assert_eq!(
+            NavKind::Mkdocs.update(None, &[]).unwrap(),
+            "site_name: Documentation\nnav: []\n"
+        );
+    }
+
+    #[test]
+    fn mdbook_summary_is_rewritten_or_merged_into_its_region() {
+        let list = concat!(
+            "- [Intro \\[draft\\]](intro.md)\n",
+            "- [guide]()\n",
+            "  - [setup](guide/setup.md)\n",
+            "  - [usage](guide/usage.md)\n",
+            "- [api]()\n",
+            "  - [Reference](<api/ref one.md>)\n"
+        );
+        assert_eq!(
+            NavKind::Mdbook.update(None, &entries()).unwrap(),
+            format!("# Summary\n\n{}", list)
+        );
+        let summary = |region: &str| {
+            format!(
+                "# Summary\n\n[Preface](preface.md)\n{}\n{}{}\n",
+                merge::BEGIN_MARKER,
+                region,
+                merge::END_MARKER
+            )
+        };
+        assert_eq!(
+            NavKind::Mdbook
+                .update(Some(&summary("")), &entries())
+                .unwrap(),
+            summary(list)
+        );
+    }
+
+    #[test]
+    fn default_files_sit_next_to_or_inside_the_output_directory() {
+        assert_eq!(
+            NavKind::Mkdocs.default_file(Path::new("site/docs")),
+            Path::new("site/mkdocs.yml")
+        );
+        assert_eq!(
+            NavKind::Mkdocs.default_file(Path::new("/")),
+            Path::new("./mkdocs.yml")
+        );
+        assert_eq!(
+            NavKind::Mdbook.default_file(Path::new("book/src")),
+            Path::new("book/src/SUMMARY.md")
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Obsidian vault output (--obsidian or the `obsidian` setting) and link helpers.
+//!
+//! Obsidian cannot link to notes whose names contain `# ^ [ ] |`, on top of the
+//! characters the filesystem rejects, so vault filenames and `wikilink`/`embed` targets
+//! are sanitized with the same rules and links always match the generated files:
+//!
+//! ```text
+//! {{wikilink related}}          [[Other note]]
+//! {{wikilink related "see"}}    [[Other note|see]]
+//! {{embed image 300}}           ![[cover.png|300]]
+//! {{tag category}}              #project/active   (arrays give one tag each)
+//! ```
+
+use serde_json::Value;
+
+/// Characters Obsidian does not allow in note names
+const FORBIDDEN: &[char] = &[
+    '*', '"', '\\', '/', '<', '>', ':', '|', '?', '#', '^', '[', ']',
+];
+
+/// Make a note name valid in an Obsidian vault; `/` is kept when paths are allowed
+pub fn sanitize(name: &str, allow_paths: bool) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '/' if allow_paths => '/',
+            c if FORBIDDEN.contains(&c) || c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Hidden (leading dot) and trailing dots/spaces break sync on some platforms
+    replaced
+        .split('/')
+        .map(|segment| segment.trim().trim_start_matches('.').trim_end_matches('.'))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Text of a helper argument (null is empty)
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `[[target|display]]`-style link body, or empty when there is no target
+fn link(prefix: &str, target: &Value, display: Option<&Value>) -> String {
+    let target = sanitize(&text(target), true);
+    if target.is_empty() {
+        return String::new();
+    }
+    // The display text ends at `|` or `]]`
+    let display = display
+        .map(text)
+        .map(|d| d.replace(['|', '[', ']'], " ").trim().to_string())
+        .filter(|d| !d.is_empty());
+    match display {
+        Some(display) => format!("{}[[{}|{}]]", prefix, target, display),
+        None => format!("{}[[{}]]", prefix, target),
+    }
+}
+
+/// `[[note]]` or `[[note|alias]]`
+pub fn wikilink(target: &Value, alias: Option<&Value>) -> String {
+    link("", target, alias)
+}
+
+/// `![[file]]` or `![[file|size]]`
+pub fn embed(target: &Value, size: Option<&Value>) -> String {
+    link("!", target, size)
+}
+
+/// One tag: whitespace becomes `-`, characters Obsidian does not accept are dropped,
+/// `/` nests; None for empty or purely numeric tags (Obsidian ignores those)
+fn tag(value: &str) -> Option<String> {
+    let mut tag = String::with_capacity(value.len());
+    for c in value.trim().trim_start_matches('#').chars() {
+        if c.is_alphanumeric() || matches!(c, '_' | '-' | '/') {
+            tag.push(c);
+        } else if c.is_whitespace() && !tag.ends_with('-') {
+            tag.push('-');
+        }
+    }
+    let tag = tag.trim_matches(['/', '-']).to_string();
+    if tag.is_empty() || tag.chars().all(|c| c.is_ascii_digit() || c == '/') {
+        return None;
+    }
+    Some(format!("#{}", tag))
+}
+
+/// `#tag` for a value, or space-separated tags for an array
+pub fn tags(value: &Value) -> String {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| tag(&text(item)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => tag(&text(other)).unwrap_or_default(),
+    }
+}
+
+/// Vault preset: Obsidian-safe filenames plus `tags` and `aliases` properties taken
+/// from the item fields of the same name (frontmatter keys in the settings win)
+pub fn apply(settings: &mut crate::JsonImportSettings) {
+    let mut frontmatter = indexmap::IndexMap::new();
+    frontmatter.insert("tags".to_string(), Value::from("{{tags}}"));
+    frontmatter.insert("aliases".to_string(), Value::from("{{aliases}}"));
+    for (key, value) in std::mem::take(&mut settings.frontmatter) {
+        frontmatter.insert(key, value);
+    }
+    settings.frontmatter = frontmatter;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn names_lose_characters_obsidian_cannot_link() {
+        assert_eq!(sanitize("C# [draft] | v1^2", false), "C_ _draft_ _ v1_2");
+        assert_eq!(sanitize("a/b: c", false), "a_b_ c");
+        assert_eq!(sanitize(" .hidden/notes. /x", true), "hidden/notes/x");
+        assert_eq!(sanitize("tab\there", false), "tab_here");
+        assert_eq!(sanitize("", true), "");
+    }
+
+    #[test]
+    fn links_and_embeds_match_sanitized_names() {
+        assert_eq!(wikilink(&json!("Other: note"), None), "[[Other_ note]]");
+        assert_eq!(
+            wikilink(&json!("dir/Note"), Some(&json!("see [this]|it"))),
+            "[[dir/Note|see  this  it]]"
+        );
+        assert_eq!(wikilink(&json!("Note"), Some(&json!(" "))), "[[Note]]");
+        assert_eq!(
+            embed(&json!("cover.png"), Some(&json!(300))),
+            "![[cover.png|300]]"
+        );
+        assert_eq!(wikilink(&json!(42), None), "[[42]]");
+        assert_eq!(wikilink(&Value::Null, Some(&json!("x"))), "");
+        assert_eq!(embed(&json!("#"), None), "![[_]]");
+    }
+
+    #[test]
+    fn tags_are_cleaned_and_numeric_ones_dropped() {
+        assert_eq!(tags(&json!("#Project/Active")), "#Project/Active");
+        assert_eq!(tags(&json!("  big  idea! ")), "#big-idea");
+        assert_eq!(
+            tags(&json!(["a b", 2024, "", "/x/", "2024/05", null])),
+            "#a-b #x"
+        );
+        assert_eq!(tags(&json!(1984)), "");
+    }
+
+    #[test]
+    fn preset_adds_tags_and_aliases_without_overriding_settings() {
+        let mut settings = crate::JsonImportSettings::default();
+        settings
+            .frontmatter
+            .insert("title".to_string(), json!("{{name}}"));
+        settings
+            .frontmatter
+            .insert("tags".to_string(), json!("{{labels}}"));
+        apply(&mut settings);
+        let keys: Vec<&str> = settings.frontmatter.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["tags", "aliases", "title"]);
+        assert_eq!(settings.frontmatter["tags"], json!("{{labels}}"));
+        assert_eq!(settings.frontmatter["aliases"], json!("{{aliases}}"));
+    }
+}
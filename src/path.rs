@@ -0,0 +1,308 @@
+//! Field paths used by `objfield`, `top_field` and friends.
+//!
+//! Plain dot paths ("user.profile.name") behave as before; JSONPath-style steps
+//! are also understood:
+//! - `users[0].name`, `users[-1]`, `items.0`   array indices (negative from the end)
+//! - `items[*].id`, `tags.*`                    wildcards over arrays and objects
+//! - `items[?(@.active)]`                       filter by truthy field
+//! - `items[?(@.status == 'published')]`        filter by comparison (== != < <= > >=)
+//! - `['key with.dots']`                        quoted keys
+//! - a leading `$` or `$.` is accepted and ignored
+//!
+//! Paths containing a wildcard or filter select several values and yield an array.
+
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// One step of a parsed path
+#[derive(Debug)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Filter(Predicate),
+}
+
+/// Filter predicate: `@.field` alone tests truthiness, otherwise compares with a literal
+#[derive(Debug)]
+struct Predicate {
+    field: String,
+    cmp: Option<(String, Value)>,
+}
+
+/// Select the value(s) at `path` in `root`
+pub fn select(root: &Value, path: &str) -> Option<Value> {
+    let steps = parse(path)?;
+    let multi = steps
+        .iter()
+        .any(|step| matches!(step, Step::Wildcard | Step::Filter(_)));
+
+    let mut current = vec![root];
+    for step in &steps {
+        current = current
+            .into_iter()
+            .flat_map(|value| apply(value, step))
+            .collect();
+        if current.is_empty() && !multi {
+            return None;
+        }
+    }
+
+    if multi {
+        Some(Value::Array(current.into_iter().cloned().collect()))
+    } else {
+        current.first().map(|v| (*v).clone())
+    }
+}
+
+/// Apply one step to a value
+fn apply<'a>(value: &'a Value, step: &Step) -> Vec<&'a Value> {
+    match (step, value) {
+        (Step::Key(key), Value::Object(obj)) => obj.get(key).into_iter().collect(),
+        // Numeric dot segments index arrays ("items.0")
+        (Step::Key(key), Value::Array(arr)) => key
+            .parse::<i64>()
+            .ok()
+            .and_then(|i| index(arr, i))
+            .into_iter()
+            .collect(),
+        (Step::Index(i), Value::Array(arr)) => index(arr, *i).into_iter().collect(),
+        (Step::Wildcard, Value::Array(arr)) => arr.iter().collect(),
+        (Step::Wildcard, Value::Object(obj)) => obj.values().collect(),
+        (Step::Filter(pred), Value::Array(arr)) => arr.iter().filter(|v| pred.test(v)).collect(),
+        (Step::Filter(pred), Value::Object(obj)) => obj.values().filter(|v| pred.test(v)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn index(arr: &[Value], i: i64) -> Option<&Value> {
+    let idx = if i < 0 { arr.len() as i64 + i } else { i };
+    usize::try_from(idx).ok().and_then(|idx| arr.get(idx))
+}
+
+impl Predicate {
+    fn test(&self, item: &Value) -> bool {
+        let field = if self.field.is_empty() {
+            Some(item.clone())
+        } else {
+            select(item, &self.field)
+        };
+        match (&self.cmp, field) {
+            (None, Some(v)) => truthy(&v),
+            (None, None) => false,
+            (Some((op, literal)), field) => {
+                compare_op(&field.unwrap_or(Value::Null), op, literal).unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// Evaluate `value op literal` for op in == != < <= > >= (or eq ne lt lte gt gte);
+/// None for an unknown operator
+pub fn compare_op(value: &Value, op: &str, literal: &Value) -> Option<bool> {
+    let ord = compare(value, literal);
+    Some(match op {
+        "==" | "eq" => ord == Some(Ordering::Equal),
+        "!=" | "ne" => ord != Some(Ordering::Equal),
+        "<" | "lt" => ord == Some(Ordering::Less),
+        "<=" | "lte" => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        ">" | "gt" => ord == Some(Ordering::Greater),
+        ">=" | "gte" => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+        _ => return None,
+    })
+}
+
+/// Handlebars-like truthiness
+fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// Compare numbers numerically (numeric strings included, e.g. from CSV), everything else
+/// by equality or string order
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    let num = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    if let (Some(x), Some(y)) = (num(a), num(b))
+        && (a.is_number() || b.is_number())
+    {
+        return x.partial_cmp(&y);
+    }
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Parse a path into steps; None on malformed brackets
+fn parse(path: &str) -> Option<Vec<Step>> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let chars: Vec<char> = path.chars().collect();
+    let mut steps = Vec::new();
+    let mut key = String::new();
+    let mut i = 0;
+
+    let flush = |key: &mut String, steps: &mut Vec<Step>| {
+        if !key.is_empty() {
+            let k = std::mem::take(key);
+            steps.push(if k == "*" {
+                Step::Wildcard
+            } else {
+                Step::Key(k)
+            });
+        }
+    };
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' => flush(&mut key, &mut steps),
+            '[' => {
+                flush(&mut key, &mut steps);
+                let close = find_close(&chars, i)?;
+                let inner: String = chars[i + 1..close].iter().collect();
+                steps.push(parse_bracket(inner.trim())?);
+                i = close;
+            }
+            c => key.push(c),
+        }
+        i += 1;
+    }
+    flush(&mut key, &mut steps);
+    Some(steps)
+}
+
+/// Index of the `]` matching the `[` at `open`, skipping quoted text and nested brackets
+fn find_close(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, &c) in chars.iter().enumerate().skip(open) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse the inside of `[...]`
+fn parse_bracket(inner: &str) -> Option<Step> {
+    if inner == "*" {
+        return Some(Step::Wildcard);
+    }
+    if let Ok(i) = inner.parse::<i64>() {
+        return Some(Step::Index(i));
+    }
+    if let Some(quoted) = unquote(inner) {
+        return Some(Step::Key(quoted.to_string()));
+    }
+    let expr = inner.strip_prefix('?')?.trim();
+    let expr = expr.strip_prefix('(')?.strip_suffix(')')?.trim();
+    parse_predicate(expr).map(Step::Filter)
+}
+
+/// Parse `@.field [op literal]`
+fn parse_predicate(expr: &str) -> Option<Predicate> {
+    const OPS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+    let (lhs, cmp) = match OPS
+        .iter()
+        .filter_map(|op| expr.find(op).map(|pos| (pos, *op)))
+        .min_by_key(|(pos, op)| (*pos, std::cmp::Reverse(op.len())))
+    {
+        Some((pos, op)) => {
+            let literal = parse_literal(expr[pos + op.len()..].trim())?;
+            (expr[..pos].trim(), Some((op.to_string(), literal)))
+        }
+        None => (expr, None),
+    };
+    let field = lhs.strip_prefix('@')?;
+    let field = field.strip_prefix('.').unwrap_or(field);
+    Some(Predicate {
+        field: field.to_string(),
+        cmp,
+    })
+}
+
+fn parse_literal(text: &str) -> Option<Value> {
+    if let Some(s) = unquote(text) {
+        return Some(Value::String(s.to_string()));
+    }
+    serde_json::from_str(text).ok()
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    ['\'', '"'].iter().find_map(|&q| {
+        text.strip_prefix(q)
+            .and_then(|rest| rest.strip_suffix(q))
+            .filter(|_| text.len() >= 2)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data() -> Value {
+        json!({
+            "user": {"profile": {"name": "Ann"}},
+            "items": [
+                {"id": 1, "status": "published", "active": true},
+                {"id": 2, "status": "draft", "active": false},
+                {"id": 3, "status": "published"}
+            ],
+            "tags": {"a": "x", "b": "y"},
+            "key.with.dots": 5
+        })
+    }
+
+    #[test]
+    fn select_follows_keys_and_indices() {
+        let data = data();
+        assert_eq!(select(&data, "user.profile.name"), Some(json!("Ann")));
+        assert_eq!(select(&data, "$.items[0].id"), Some(json!(1)));
+        assert_eq!(select(&data, "items.1.id"), Some(json!(2)));
+        assert_eq!(select(&data, "items[-1].id"), Some(json!(3)));
+        assert_eq!(select(&data, "['key.with.dots']"), Some(json!(5)));
+        assert_eq!(select(&data, "user.missing"), None);
+        assert_eq!(select(&data, "items[9]"), None);
+    }
+
+    #[test]
+    fn select_collects_wildcards_and_filters_into_arrays() {
+        let data = data();
+        assert_eq!(select(&data, "items[*].id"), Some(json!([1, 2, 3])));
+        assert_eq!(select(&data, "tags.*"), Some(json!(["x", "y"])));
+        assert_eq!(select(&data, "items[?(@.active)].id"), Some(json!([1])));
+        assert_eq!(
+            select(&data, "items[?(@.status == 'published')].id"),
+            Some(json!([1, 3]))
+        );
+        assert_eq!(select(&data, "items[?(@.id > 1)].id"), Some(json!([2, 3])));
+        assert_eq!(select(&data, "items[*].missing"), Some(json!([])));
+    }
+
+    #[test]
+    fn select_rejects_malformed_brackets() {
+        assert_eq!(select(&data(), "items[0"), None);
+    }
+}
@@ -1,3 +1,42 @@
+//! Rust plugin ABI (--rs-plugin).
+//!
+//! A plugin is a cdylib exporting two C functions:
+//!
+//! ```
+//! use json2md::plugin::{HelperCall, PluginHost, PLUGIN_ABI_VERSION};
+//!
+//! #[unsafe(no_mangle)]
+//! pub extern "C" fn json2md_plugin_abi_version() -> u32 {
+//!     PLUGIN_ABI_VERSION
+//! }
+//!
+//! #[unsafe(no_mangle)]
+//! pub unsafe extern "C" fn json2md_plugin_register(host: *const PluginHost) -> i32 {
+//!     unsafe { (*host).register("shout", shout) }
+//! }
+//!
+//! unsafe extern "C" fn shout(call: *const HelperCall) -> i32 {
+//!     let call = unsafe { &*call };
+//!     let args: serde_json::Value = serde_json::from_slice(call.args()).unwrap_or_default();
+//!     call.write(args["params"][0].as_str().unwrap_or("").to_uppercase().as_bytes());
+//!     0
+//! }
+//! # assert_eq!(json2md_plugin_abi_version(), PLUGIN_ABI_VERSION);
+//! ```
+//!
+//! While registering, `host.config()` gives the --helper-config object as JSON.
+//!
+//! Only C types cross the library boundary, so a plugin does not have to be built with
+//! the same rustc or handlebars version. The host refuses a plugin whose
+//! `json2md_plugin_abi_version` differs from its own, or whose optional
+//! `json2md_plugin_requires` asks for capabilities the host lacks.
+//!
+//! Libraries exporting only the older `create_helpers` (a `PluginFactory` returning
+//! Rust trait objects) still load, with a warning: they only work when built with
+//! exactly the same compiler and dependencies.
+
+use std::ffi::c_void;
+
 use handlebars::{Handlebars, Helper, RenderContext, Output, RenderError, Context as HbContext};
 
 pub trait HelperPlugin: Send + Sync {
@@ -7,15 +46,102 @@ pub trait HelperPlugin: Send + Sync {
 
 pub type PluginFactory = fn() -> Box<dyn HelperPlugin>;
 
-pub fn make_helper<F>(func: F) -> Box<dyn for<'a> Fn(
+pub type BoxedHelper = Box<dyn for<'a> Fn(
     &Helper<'a>,
     &Handlebars<'a>,
     &HbContext,
     &mut RenderContext<'a, '_>,
     &mut dyn Output
-) -> Result<(), RenderError> + Send + Sync>
+) -> Result<(), RenderError> + Send + Sync>;
+
+pub fn make_helper<F>(func: F) -> BoxedHelper
 where
     F: Fn(&Helper<'_>, &Handlebars<'_>, &HbContext, &mut RenderContext<'_, '_>, &mut dyn Output) -> Result<(), RenderError> + Send + Sync + 'static,
 {
     Box::new(func)
-}
\ No newline at end of file
+}
+
+/// Version of the C plugin ABI; bumped on any incompatible change to the types below
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Capability: the call JSON carries the helper's hash arguments (`hash`)
+pub const CAP_HASH_ARGS: u64 = 1;
+/// Capability: the call JSON carries the current template context (`context`)
+pub const CAP_CONTEXT: u64 = 1 << 1;
+/// Capability: `PluginHost` carries the --helper-config object
+pub const CAP_CONFIG: u64 = 1 << 2;
+/// Capabilities of this host
+pub const HOST_CAPABILITIES: u64 = CAP_HASH_ARGS | CAP_CONTEXT | CAP_CONFIG;
+
+/// `json2md_plugin_abi_version`: the ABI version the plugin was built for
+pub type AbiVersionFn = unsafe extern "C" fn() -> u32;
+/// `json2md_plugin_requires` (optional): capabilities the plugin cannot work without
+pub type RequiresFn = unsafe extern "C" fn() -> u64;
+/// `json2md_plugin_register`: register helpers through the host; non-zero fails the load
+pub type RegisterFn = unsafe extern "C" fn(host: *const PluginHost) -> i32;
+
+/// A helper behind the C ABI. Returns 0 with the rendered text written to the call,
+/// or non-zero with an error message written instead.
+pub type CHelperFn = unsafe extern "C" fn(call: *const HelperCall) -> i32;
+
+/// What the host offers a plugin while it registers
+#[repr(C)]
+pub struct PluginHost {
+    /// ABI version of the host (`PLUGIN_ABI_VERSION`)
+    pub abi_version: u32,
+    /// Capabilities of the host (`CAP_*` flags)
+    pub capabilities: u64,
+    /// Helper configuration as a UTF-8 JSON object (with `CAP_CONFIG`)
+    pub(crate) config: *const u8,
+    pub(crate) config_len: usize,
+    /// Host state passed back to `register_helper`
+    pub(crate) registry: *mut c_void,
+    /// Register a helper under a UTF-8 name; returns 0 on success
+    pub(crate) register_helper: unsafe extern "C" fn(
+        registry: *mut c_void,
+        name: *const u8,
+        name_len: usize,
+        helper: CHelperFn,
+    ) -> i32,
+}
+
+impl PluginHost {
+    /// The helper configuration (--helper-config) as JSON, "{}" when none was given;
+    /// `None` from a host without `CAP_CONFIG`
+    pub fn config(&self) -> Option<&[u8]> {
+        (self.capabilities & CAP_CONFIG != 0)
+            .then(|| unsafe { std::slice::from_raw_parts(self.config, self.config_len) })
+    }
+
+    /// Register a helper under a name; returns 0 on success. Only call it from
+    /// `json2md_plugin_register`, with the host passed there.
+    pub fn register(&self, name: &str, helper: CHelperFn) -> i32 {
+        unsafe { (self.register_helper)(self.registry, name.as_ptr(), name.len(), helper) }
+    }
+}
+
+/// One helper call: the arguments as JSON and a place to write the result
+///
+/// The arguments are a UTF-8 JSON object with `name` and `params` (an array), plus
+/// `hash` and `context` when the host has those capabilities.
+#[repr(C)]
+pub struct HelperCall {
+    pub(crate) args: *const u8,
+    pub(crate) args_len: usize,
+    /// Host buffer receiving the output
+    pub(crate) out: *mut c_void,
+    /// Append bytes to the output; may be called several times
+    pub(crate) write: unsafe extern "C" fn(out: *mut c_void, data: *const u8, len: usize),
+}
+
+impl HelperCall {
+    /// The call's JSON arguments
+    pub fn args(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.args, self.args_len) }
+    }
+
+    /// Append to the output (rendered text, or the error message)
+    pub fn write(&self, data: &[u8]) {
+        unsafe { (self.write)(self.out, data.as_ptr(), data.len()) }
+    }
+}
@@ -0,0 +1,282 @@
+//! Python helper loading via an embedded interpreter (pyo3).
+//!
+//! Enabled with --features python-helpers
+//! Usage: --python-helpers path/to/helpers.py
+//!
+//! The file runs as a module named after it, with its directory on `sys.path` so it
+//! can import its neighbours. Every top-level function it defines (names starting
+//! with `_` excepted) becomes a helper. Params arrive as Python objects (dict, list,
+//! str, int, float, bool, None); a str result is written as is, None as nothing and
+//! other results as JSON (or `str()` when they have no JSON form). `helperConfig`
+//! holds the --helper-config object.
+
+use crate::HelperInfo;
+use anyhow::Result;
+use handlebars::Handlebars;
+use std::path::Path;
+
+#[cfg(feature = "python-helpers")]
+use anyhow::Context;
+#[cfg(feature = "python-helpers")]
+use handlebars::{
+    Context as HbContext, Helper, Output, RenderContext, RenderError, RenderErrorReason,
+};
+#[cfg(feature = "python-helpers")]
+use pyo3::IntoPyObjectExt;
+#[cfg(feature = "python-helpers")]
+use pyo3::prelude::*;
+#[cfg(feature = "python-helpers")]
+use pyo3::types::{PyBool, PyDict, PyFloat, PyFunction, PyInt, PyList, PyString, PyTuple};
+#[cfg(feature = "python-helpers")]
+use serde_json::Value;
+
+/// Registry for helpers defined in Python files
+pub struct PythonHelperRegistry {
+    /// Helper functions by name, in load order
+    #[cfg(feature = "python-helpers")]
+    helpers: Vec<(String, Py<PyAny>)>,
+    /// Configuration for helpers (--helper-config), an object
+    #[cfg(feature = "python-helpers")]
+    config: Value,
+}
+
+impl PythonHelperRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "python-helpers")]
+            helpers: Vec::new(),
+            #[cfg(feature = "python-helpers")]
+            config: Value::Object(serde_json::Map::new()),
+        }
+    }
+
+    /// Stub implementation when python-helpers feature is disabled
+    #[cfg(not(feature = "python-helpers"))]
+    pub fn set_config(&mut self, _config: serde_json::Value) {}
+
+    /// Configuration given to files loaded afterwards as the `helperConfig` global
+    #[cfg(feature = "python-helpers")]
+    pub fn set_config(&mut self, config: Value) {
+        self.config = config;
+    }
+
+    /// Stub implementation when python-helpers feature is disabled
+    #[cfg(not(feature = "python-helpers"))]
+    pub fn load_python_helpers(&mut self, _path: &Path) -> Result<Vec<String>> {
+        anyhow::bail!("Python helpers require: cargo build --features python-helpers")
+    }
+
+    /// Stub implementation when python-helpers feature is disabled
+    #[cfg(not(feature = "python-helpers"))]
+    pub fn register_with_handlebars(&self, _hb: &mut Handlebars<'_>) {}
+
+    /// Stub implementation when python-helpers feature is disabled
+    #[cfg(not(feature = "python-helpers"))]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        Vec::new()
+    }
+
+    /// Run a Python file as a module and collect its top-level functions
+    #[cfg(feature = "python-helpers")]
+    pub fn load_python_helpers(&mut self, py_path: &Path) -> Result<Vec<String>> {
+        let code = std::fs::read_to_string(py_path)
+            .with_context(|| format!("Failed to read Python: {}", py_path.display()))?;
+        let module_name = py_path.file_stem().map_or_else(
+            || "helpers".to_string(),
+            |s| s.to_string_lossy().to_string(),
+        );
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| -> Result<Vec<String>> {
+            let module = pyo3::types::PyModule::new(py, &module_name)?;
+            module.setattr("__file__", py_path.to_string_lossy().as_ref())?;
+            module.setattr("helperConfig", json_to_py(py, &self.config)?)?;
+            if let Some(dir) = py_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                py.import("sys")?
+                    .getattr("path")?
+                    .call_method1("insert", (0, dir.to_string_lossy().as_ref()))?;
+            }
+
+            // Compiled under the file's name so errors and tracebacks point at it
+            let builtins = py.import("builtins")?;
+            let compiled = builtins.getattr("compile")?.call1((
+                code,
+                py_path.to_string_lossy().as_ref(),
+                "exec",
+            ))?;
+            builtins.getattr("exec")?.call1((compiled, module.dict()))?;
+
+            let mut found = Vec::new();
+            for (key, value) in module.dict().iter() {
+                let name: String = key.extract()?;
+                let defined_here = value
+                    .getattr("__module__")
+                    .and_then(|m| m.extract::<String>())
+                    .is_ok_and(|m| m == module_name);
+                if name.starts_with('_') || !value.is_instance_of::<PyFunction>() || !defined_here {
+                    continue;
+                }
+                found.push(name.clone());
+                self.helpers.push((name, value.unbind()));
+            }
+            found.sort();
+            Ok(found)
+        })
+    }
+
+    /// Register the discovered Python helpers with Handlebars
+    #[cfg(feature = "python-helpers")]
+    pub fn register_with_handlebars(&self, hb: &mut Handlebars<'_>) {
+        for (name, func) in &self.helpers {
+            let func = Python::with_gil(|py| func.clone_ref(py));
+            let py_name = name.clone();
+            let helper = move |h: &Helper<'_>,
+                               _: &Handlebars<'_>,
+                               _: &HbContext,
+                               _: &mut RenderContext<'_, '_>,
+                               out: &mut dyn Output|
+                  -> Result<(), RenderError> {
+                let text =
+                    Python::with_gil(|py| call_python(py, func.bind(py), h)).map_err(|e| {
+                        RenderErrorReason::Other(format!("Helper '{}': {}", py_name, e))
+                    })?;
+                out.write(&text)?;
+                Ok(())
+            };
+            hb.register_helper(name, Box::new(helper));
+        }
+    }
+
+    /// The loaded Python helpers for --list-helpers, with their `inspect.signature` and
+    /// the first paragraph of their docstring (or the `#` comment above them)
+    #[cfg(feature = "python-helpers")]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        // The interpreter only starts once a file loads
+        if self.helpers.is_empty() {
+            return Vec::new();
+        }
+        Python::with_gil(|py| {
+            let inspect = py.import("inspect").ok();
+            self.helpers
+                .iter()
+                .map(|(name, func)| {
+                    let func = func.bind(py);
+                    let code = func.getattr("__code__").ok();
+                    let file: Option<String> = code
+                        .as_ref()
+                        .and_then(|code| code.getattr("co_filename").ok()?.extract().ok());
+                    let line: Option<usize> = code
+                        .as_ref()
+                        .and_then(|code| code.getattr("co_firstlineno").ok()?.extract().ok());
+                    let signature = inspect
+                        .as_ref()
+                        .and_then(|inspect| inspect.call_method1("signature", (func,)).ok())
+                        .and_then(|sig| sig.str().ok())
+                        .map(|sig| format!("{}{}", name, sig));
+                    let doc: Option<String> = inspect
+                        .as_ref()
+                        .and_then(|inspect| inspect.call_method1("getdoc", (func,)).ok())
+                        .and_then(|doc| doc.extract().ok());
+                    let description = match doc {
+                        Some(doc) => doc
+                            .split("\n\n")
+                            .next()
+                            .map(|first| first.split_whitespace().collect::<Vec<_>>().join(" ")),
+                        None => file.as_ref().zip(line).and_then(|(file, line)| {
+                            let code = std::fs::read_to_string(file).ok()?;
+                            crate::comment_above(&code, line.checked_sub(1)?, "#")
+                        }),
+                    };
+                    HelperInfo {
+                        name: name.clone(),
+                        source: file.unwrap_or_default(),
+                        signature,
+                        description,
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+/// Call a Python function with the helper's params and render its result
+#[cfg(feature = "python-helpers")]
+fn call_python(py: Python<'_>, func: &Bound<'_, PyAny>, h: &Helper<'_>) -> PyResult<String> {
+    let args = h
+        .params()
+        .iter()
+        .map(|param| json_to_py(py, param.value()))
+        .collect::<PyResult<Vec<_>>>()?;
+    let result = func.call1(PyTuple::new(py, args)?)?;
+    if result.is_none() {
+        return Ok(String::new());
+    }
+    if let Ok(s) = result.downcast::<PyString>() {
+        return Ok(s.to_str()?.to_string());
+    }
+    match py_to_json(&result) {
+        Some(value) => Ok(value.to_string()),
+        None => Ok(result.str()?.to_str()?.to_string()),
+    }
+}
+
+/// Convert a JSON value to the matching Python object
+#[cfg(feature = "python-helpers")]
+fn json_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Null => Ok(py.None().into_bound(py)),
+        Value::Bool(b) => b.into_bound_py_any(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_bound_py_any(py),
+            (None, Some(u)) => u.into_bound_py_any(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_bound_py_any(py),
+        },
+        Value::String(s) => s.into_bound_py_any(py),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| json_to_py(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyList::new(py, items)?.into_any())
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, item) in map {
+                dict.set_item(key, json_to_py(py, item)?)?;
+            }
+            Ok(dict.into_any())
+        }
+    }
+}
+
+/// Convert a Python result to JSON; `None` for objects without a JSON form
+#[cfg(feature = "python-helpers")]
+fn py_to_json(obj: &Bound<'_, PyAny>) -> Option<Value> {
+    if obj.is_none() {
+        Some(Value::Null)
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        Some(Value::Bool(b.is_true()))
+    } else if obj.is_instance_of::<PyInt>() {
+        obj.extract::<i64>()
+            .ok()
+            .map(Value::from)
+            .or_else(|| obj.extract::<u64>().ok().map(Value::from))
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        serde_json::Number::from_f64(f.value()).map(Value::Number)
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        s.to_str().ok().map(|s| Value::String(s.to_string()))
+    } else if let Ok(list) = obj.downcast::<PyList>() {
+        list.iter().map(|item| py_to_json(&item)).collect()
+    } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        tuple.iter().map(|item| py_to_json(&item)).collect()
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut map = serde_json::Map::new();
+        for (key, item) in dict.iter() {
+            map.insert(key.str().ok()?.to_string(), py_to_json(&item)?);
+        }
+        Some(Value::Object(map))
+    } else {
+        None
+    }
+}
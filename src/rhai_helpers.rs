@@ -0,0 +1,222 @@
+//! Rhai helper scripts: pure-Rust scripting that needs no native engine.
+//!
+//! Enabled by default (feature rhai-helpers)
+//! Usage: --rhai-helpers path/to/helpers.rhai
+//!
+//! Every public function the script defines becomes a helper, usable in templates and
+//! in json_name/folder_template filename templates alike. Params arrive as Rhai values
+//! (maps, arrays, strings, numbers, bools, `()` for null); missing params are `()` and
+//! extra ones are dropped. A string result is written as is, `()` as nothing and other
+//! results as JSON. The --helper-config object is the constant `helperConfig`.
+
+use crate::HelperInfo;
+use anyhow::Result;
+use handlebars::Handlebars;
+use std::path::Path;
+
+#[cfg(feature = "rhai-helpers")]
+use anyhow::Context;
+#[cfg(feature = "rhai-helpers")]
+use handlebars::{
+    Context as HbContext, Helper, Output, RenderContext, RenderError, RenderErrorReason,
+};
+#[cfg(feature = "rhai-helpers")]
+use rhai::{AST, CallFnOptions, Dynamic, Engine, FnAccess, Scope};
+#[cfg(feature = "rhai-helpers")]
+use serde_json::Value;
+#[cfg(feature = "rhai-helpers")]
+use std::sync::Arc;
+
+/// A compiled script with the engine that runs it
+#[cfg(feature = "rhai-helpers")]
+struct RhaiScript {
+    engine: Engine,
+    ast: AST,
+    config: Dynamic,
+    /// Helper functions with how many params each takes
+    helpers: Vec<(String, usize)>,
+}
+
+/// Registry for helpers defined in Rhai scripts
+pub struct RhaiHelperRegistry {
+    /// Loaded scripts
+    #[cfg(feature = "rhai-helpers")]
+    scripts: Vec<Arc<RhaiScript>>,
+    /// Configuration for helpers (--helper-config), an object
+    #[cfg(feature = "rhai-helpers")]
+    config: Value,
+}
+
+impl RhaiHelperRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "rhai-helpers")]
+            scripts: Vec::new(),
+            #[cfg(feature = "rhai-helpers")]
+            config: Value::Object(serde_json::Map::new()),
+        }
+    }
+
+    /// Stub implementation when rhai-helpers feature is disabled
+    #[cfg(not(feature = "rhai-helpers"))]
+    pub fn set_config(&mut self, _config: serde_json::Value) {}
+
+    /// Configuration given to scripts loaded afterwards as the `helperConfig` constant
+    #[cfg(feature = "rhai-helpers")]
+    pub fn set_config(&mut self, config: Value) {
+        self.config = config;
+    }
+
+    /// Stub implementation when rhai-helpers feature is disabled
+    #[cfg(not(feature = "rhai-helpers"))]
+    pub fn load_rhai_helpers(&mut self, _path: &Path) -> Result<Vec<String>> {
+        anyhow::bail!("Rhai helpers require: cargo build --features rhai-helpers")
+    }
+
+    /// Stub implementation when rhai-helpers feature is disabled
+    #[cfg(not(feature = "rhai-helpers"))]
+    pub fn register_with_handlebars(&self, _hb: &mut Handlebars<'_>) {}
+
+    /// Stub implementation when rhai-helpers feature is disabled
+    #[cfg(not(feature = "rhai-helpers"))]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        Vec::new()
+    }
+
+    /// Compile a Rhai script, run its top-level statements and collect its functions
+    #[cfg(feature = "rhai-helpers")]
+    pub fn load_rhai_helpers(&mut self, rhai_path: &Path) -> Result<Vec<String>> {
+        let code = std::fs::read_to_string(rhai_path)
+            .with_context(|| format!("Failed to read Rhai: {}", rhai_path.display()))?;
+
+        let engine = Engine::new();
+        let config = rhai::serde::to_dynamic(&self.config).map_err(|e| anyhow::anyhow!("{}", e))?;
+        // Compiled with the configuration constant, which the optimizer carries into
+        // function bodies
+        let mut ast = engine
+            .compile_with_scope(&script_scope(&config), &code)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        ast.set_source(rhai_path.to_string_lossy().as_ref());
+        engine
+            .run_ast_with_scope(&mut script_scope(&config), &ast)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let mut found: Vec<(String, usize)> = ast
+            .iter_functions()
+            .filter(|f| f.access == FnAccess::Public && !f.name.starts_with('_'))
+            .map(|f| (f.name.to_string(), f.params.len()))
+            .collect();
+        found.sort();
+        found.dedup_by(|a, b| a.0 == b.0);
+        let names = found.iter().map(|(name, _)| name.clone()).collect();
+
+        let script = RhaiScript {
+            engine,
+            ast,
+            config,
+            helpers: found,
+        };
+        self.scripts.push(Arc::new(script));
+        Ok(names)
+    }
+
+    /// Register the discovered Rhai helpers with Handlebars
+    #[cfg(feature = "rhai-helpers")]
+    pub fn register_with_handlebars(&self, hb: &mut Handlebars<'_>) {
+        for script in &self.scripts {
+            for (name, arity) in &script.helpers {
+                let script = script.clone();
+                let rhai_name = name.clone();
+                let arity = *arity;
+                let helper = move |h: &Helper<'_>,
+                                   _: &Handlebars<'_>,
+                                   _: &HbContext,
+                                   _: &mut RenderContext<'_, '_>,
+                                   out: &mut dyn Output|
+                      -> Result<(), RenderError> {
+                    let text = call_rhai(&script, &rhai_name, arity, h).map_err(|e| {
+                        RenderErrorReason::Other(format!("Helper '{}': {}", rhai_name, e))
+                    })?;
+                    out.write(&text)?;
+                    Ok(())
+                };
+                hb.register_helper(name, Box::new(helper));
+            }
+        }
+    }
+
+    /// The loaded Rhai helpers for --list-helpers, with their param names and the
+    /// comment above each `fn`
+    #[cfg(feature = "rhai-helpers")]
+    pub fn describe_helpers(&self) -> Vec<HelperInfo> {
+        let mut list = Vec::new();
+        for script in &self.scripts {
+            let source = script.ast.source().unwrap_or_default();
+            let code = std::fs::read_to_string(source).unwrap_or_default();
+            for (name, _) in &script.helpers {
+                let params = script
+                    .ast
+                    .iter_functions()
+                    .find(|f| f.name == name.as_str())
+                    .map(|f| f.params.join(", "));
+                let definition =
+                    regex::Regex::new(&format!(r"(?m)^[ \t]*fn\s+{}\s*\(", regex::escape(name)))
+                        .expect("Rhai definition regex");
+                list.push(HelperInfo {
+                    name: name.clone(),
+                    source: source.to_string(),
+                    signature: params.map(|params| crate::helper_signature(name, &params)),
+                    description: crate::definition_line(&code, &definition)
+                        .and_then(|line| crate::comment_above(&code, line, "//")),
+                });
+            }
+        }
+        list
+    }
+}
+
+/// Scope a script runs in: the helper configuration as a constant
+#[cfg(feature = "rhai-helpers")]
+fn script_scope(config: &Dynamic) -> Scope<'static> {
+    let mut scope = Scope::new();
+    scope.push_constant("helperConfig", config.clone());
+    scope
+}
+
+/// Call a script function with the helper's params and render its result
+#[cfg(feature = "rhai-helpers")]
+fn call_rhai(
+    script: &RhaiScript,
+    name: &str,
+    arity: usize,
+    h: &Helper<'_>,
+) -> Result<String, String> {
+    let mut args = h
+        .params()
+        .iter()
+        .map(|param| rhai::serde::to_dynamic(param.value()).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<Dynamic>, String>>()?;
+    args.resize(arity, Dynamic::UNIT);
+
+    let options = CallFnOptions::new().eval_ast(false);
+    let result: Dynamic = script
+        .engine
+        .call_fn_with_options(
+            options,
+            &mut script_scope(&script.config),
+            &script.ast,
+            name,
+            args,
+        )
+        .map_err(|e| e.to_string())?;
+    if result.is_unit() {
+        Ok(String::new())
+    } else if result.is_string() {
+        Ok(result.into_string().unwrap_or_default())
+    } else {
+        rhai::serde::from_dynamic::<Value>(&result)
+            .map(|value| value.to_string())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,149 @@
+//! Output sinks: where generated files go. The conversion only reads and writes
+//! through an `OutputSink`, so outputs can land on disk, in memory, in an archive,
+//! on stdout or in a callback.
+//!
+//! ```
+//! use json2md::{Converter, MemorySink};
+//!
+//! let mut sink = MemorySink::default();
+//! Converter::new()
+//!     .data(serde_json::json!({"name": "hello", "title": "Hi"}))
+//!     .template("# {{title}}")
+//!     .run_into(&mut sink)?;
+//! assert_eq!(sink.files.len(), 1);
+//! for (path, body) in &sink.files {
+//!     assert_eq!(path.extension().unwrap(), "md");
+//!     assert_eq!(body, b"# Hi");
+//! }
+//! # anyhow::Ok(())
+//! ```
+//!
+//! Any `FnMut(&Path, &[u8]) -> anyhow::Result<()>` closure is a sink too.
+
+use crate::archive::ArchiveWriter;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Destination of the generated files
+pub trait OutputSink {
+    /// Current content at a path, used to merge frontmatter and regions, to leave
+    /// identical files alone (--incremental) and for --if-exists
+    fn read(&mut self, _path: &Path) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Store a file, replacing earlier content at the same path
+    fn write(&mut self, path: &Path, body: &[u8]) -> Result<()>;
+
+    /// Called once after the last file of a run
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<F> OutputSink for F
+where
+    F: FnMut(&Path, &[u8]) -> Result<()>,
+{
+    fn write(&mut self, path: &Path, body: &[u8]) -> Result<()> {
+        self(path, body)
+    }
+}
+
+/// Files on disk, creating parent directories as needed
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSink;
+
+impl OutputSink for FileSink {
+    fn read(&mut self, path: &Path) -> Option<Vec<u8>> {
+        fs::read(path).ok()
+    }
+
+    fn write(&mut self, path: &Path, body: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, body).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Files kept in memory by path, for tests and applications that post-process them
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink {
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemorySink {
+    /// Content at a path as text, if it was written and is UTF-8
+    pub fn text(&self, path: impl AsRef<Path>) -> Option<&str> {
+        self.files
+            .get(path.as_ref())
+            .and_then(|body| std::str::from_utf8(body).ok())
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn read(&mut self, path: &Path) -> Option<Vec<u8>> {
+        self.files.get(path).cloned()
+    }
+
+    fn write(&mut self, path: &Path, body: &[u8]) -> Result<()> {
+        self.files.insert(path.to_path_buf(), body.to_vec());
+        Ok(())
+    }
+}
+
+/// Every file written to stdout, one after the other
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write(&mut self, _path: &Path, body: &[u8]) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(body)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// A .zip or .tar.gz (--archive); entries are named relative to a base directory
+pub struct ArchiveSink {
+    path: PathBuf,
+    base: PathBuf,
+    writer: Option<ArchiveWriter>,
+}
+
+impl ArchiveSink {
+    /// Create the archive; the format follows the extension (.zip, .tar.gz or .tgz)
+    pub fn create(path: &Path, base: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            base: base.to_path_buf(),
+            writer: Some(ArchiveWriter::create(path)?),
+        })
+    }
+}
+
+impl OutputSink for ArchiveSink {
+    fn write(&mut self, path: &Path, body: &[u8]) -> Result<()> {
+        let writer = self
+            .writer
+            .as_mut()
+            .with_context(|| format!("Archive already finished: {}", self.path.display()))?;
+        writer.add(path.strip_prefix(&self.base).unwrap_or(path), body)?;
+        debug_log!("📦 Added {} to archive", path.display());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            let entries = writer.entries;
+            writer.finish()?;
+            success_log!("Created: {} ({} files)", self.path.display(), entries);
+        }
+        Ok(())
+    }
+}
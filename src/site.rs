@@ -0,0 +1,79 @@
+//! Static site generator presets (--site hugo|jekyll|zola).
+//!
+//! A preset fills in settings the site generator expects: the content folder, slug
+//! filenames (Jekyll posts get the `YYYY-MM-DD-` prefix) and `title`, `date`, `draft`
+//! and `slug` frontmatter. Output paths are relative to --out-dir, which should be the
+//! site root. Any of these keys in the settings file wins over the preset; frontmatter
+//! keys are merged one by one (set a key to null to drop it).
+
+use crate::JsonImportSettings;
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+/// Title: the item's `title`, else its `name`
+const TITLE: &str = "{{#if title}}{{title}}{{else}}{{name}}{{/if}}";
+/// Slug: the item's `slug`, else the slugified title
+const SLUG: &str =
+    "{{#if slug}}{{slug}}{{else if title}}{{slugify title}}{{else}}{{slugify name}}{{/if}}";
+
+/// Static site generator targeted by a preset
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Site {
+    Hugo,
+    Jekyll,
+    Zola,
+}
+
+impl Site {
+    /// Content folder, relative to the site root
+    fn folder(self) -> &'static str {
+        match self {
+            Site::Hugo => "content/posts",
+            Site::Jekyll => "_posts",
+            Site::Zola => "content/blog",
+        }
+    }
+
+    /// Filename template
+    fn file_name(self) -> String {
+        match self {
+            // Jekyll only publishes posts named YYYY-MM-DD-title
+            Site::Jekyll => format!(
+                "{{{{#if date}}}}{{{{dateFormat date \"%Y-%m-%d\"}}}}-{{{{/if}}}}{}",
+                SLUG
+            ),
+            Site::Hugo | Site::Zola => SLUG.to_string(),
+        }
+    }
+
+    /// Frontmatter keys, in output order
+    fn frontmatter(self) -> IndexMap<String, Value> {
+        let mut fields = IndexMap::new();
+        if self == Site::Jekyll {
+            fields.insert("layout".to_string(), Value::from("post"));
+        }
+        fields.insert("title".to_string(), Value::from(TITLE));
+        fields.insert("date".to_string(), Value::from("{{date}}"));
+        // Jekyll has no draft flag (drafts live in _drafts/)
+        if self != Site::Jekyll {
+            fields.insert("draft".to_string(), Value::from("{{draft}}"));
+        }
+        fields.insert("slug".to_string(), Value::from(SLUG));
+        fields
+    }
+
+    /// Apply the preset to every setting the settings file does not set itself
+    pub fn apply(self, settings: &mut JsonImportSettings, configured: &Map<String, Value>) {
+        if !configured.contains_key("folder_template") {
+            settings.folder_template = self.folder().to_string();
+        }
+        if !configured.contains_key("json_name") {
+            settings.json_name = self.file_name();
+        }
+        let mut frontmatter = self.frontmatter();
+        for (key, value) in std::mem::take(&mut settings.frontmatter) {
+            frontmatter.insert(key, value);
+        }
+        settings.frontmatter = frontmatter;
+    }
+}
@@ -0,0 +1,264 @@
+//! Built-in string helpers.
+//!
+//! ```text
+//! {{upper name}}  {{lower name}}  {{titlecase name}}  {{trim name}}  {{trim name "-_"}}
+//! {{truncate summary 80}}  {{truncate summary 80 suffix="..."}}
+//! {{padStart id 5 "0"}}  {{substring name 0 3}}
+//! {{slugify title}}  {{camelCase title}}  {{snake_case title}}
+//! ```
+//!
+//! Non-string values are used as their JSON text and null as the empty string. Lengths
+//! and positions count characters, not bytes.
+
+use handlebars::{HelperDef, handlebars_helper};
+use serde_json::Value;
+
+/// Text of a helper argument
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Lowercase slug: letters and digits kept, everything else collapsed to '-'
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Words of an identifier or phrase: split at non-alphanumerics and lower→upper steps
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        } else if c.is_lowercase()
+            && current.chars().count() >= 2
+            && current.chars().rev().take(2).all(char::is_uppercase)
+        {
+            // End of an acronym: "HTTPServer" is "HTTP" + "Server"
+            let last = current.pop().expect("current has two characters");
+            words.push(std::mem::take(&mut current));
+            current.push(last);
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// First character uppercased, the rest lowercased
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Every whitespace-separated word capitalized, spacing kept
+fn titlecase(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() {
+            out.push_str(&capitalize(&std::mem::take(&mut word)));
+            out.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    out.push_str(&capitalize(&word));
+    out
+}
+
+fn camel_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                word.to_lowercase()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect()
+}
+
+fn snake_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Trim whitespace, or the given characters
+fn trim(text: &str, chars: Option<&str>) -> String {
+    match chars {
+        Some(chars) => text.trim_matches(|c| chars.contains(c)).to_string(),
+        None => text.trim().to_string(),
+    }
+}
+
+/// At most `len` characters, with `suffix` appended when something was cut
+fn truncate(text: &str, len: usize, suffix: &str) -> String {
+    if text.chars().count() <= len {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(len).collect();
+    out.push_str(suffix);
+    out
+}
+
+/// Left-pad to `width` characters with `pad` (repeated as needed)
+fn pad_start(text: &str, width: usize, pad: &str) -> String {
+    let missing = width.saturating_sub(text.chars().count());
+    if missing == 0 || pad.is_empty() {
+        return text.to_string();
+    }
+    let mut out: String = pad.chars().cycle().take(missing).collect();
+    out.push_str(text);
+    out
+}
+
+/// Characters from `start` up to `end` (exclusive, default the end); out-of-range
+/// positions are clamped
+fn substring(text: &str, start: u64, end: Option<u64>) -> String {
+    let start = start as usize;
+    let end = end.map_or(usize::MAX, |e| e as usize);
+    text.chars()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect()
+}
+
+handlebars_helper!(hb_upper: |value: Json| text(value).to_uppercase());
+handlebars_helper!(hb_lower: |value: Json| text(value).to_lowercase());
+handlebars_helper!(hb_titlecase: |value: Json| titlecase(&text(value)));
+handlebars_helper!(hb_trim: |value: Json, *args| {
+    trim(&text(value), args.get(1).and_then(|v| v.as_str()))
+});
+handlebars_helper!(hb_truncate: |value: Json, len: u64, {suffix: str = "…"}| {
+    truncate(&text(value), len as usize, suffix)
+});
+handlebars_helper!(hb_pad_start: |value: Json, width: u64, *args| {
+    pad_start(&text(value), width as usize, args.get(2).and_then(|v| v.as_str()).unwrap_or(" "))
+});
+handlebars_helper!(hb_slugify: |value: Json| slugify(&text(value)));
+handlebars_helper!(hb_camel_case: |value: Json| camel_case(&text(value)));
+handlebars_helper!(hb_snake_case: |value: Json| snake_case(&text(value)));
+handlebars_helper!(hb_substring: |value: Json, start: u64, *args| {
+    substring(&text(value), start, args.get(2).and_then(|v| v.as_u64()))
+});
+
+/// The string helpers with their names
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![
+        ("upper", Box::new(hb_upper)),
+        ("lower", Box::new(hb_lower)),
+        ("titlecase", Box::new(hb_titlecase)),
+        ("trim", Box::new(hb_trim)),
+        ("truncate", Box::new(hb_truncate)),
+        ("padStart", Box::new(hb_pad_start)),
+        ("slugify", Box::new(hb_slugify)),
+        ("camelCase", Box::new(hb_camel_case)),
+        ("snake_case", Box::new(hb_snake_case)),
+        ("substring", Box::new(hb_substring)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use handlebars::Handlebars;
+    use serde_json::json;
+
+    fn render(template: &str, data: Value) -> Result<String, handlebars::RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(handlebars::no_escape);
+        for (name, helper) in helpers() {
+            hb.register_helper(name, helper);
+        }
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn case_helpers_split_words_and_acronyms() {
+        assert_eq!(slugify("  Hello, Wörld! 2024 "), "hello-wörld-2024");
+        assert_eq!(slugify("---"), "");
+        assert_eq!(
+            words("HTTPServer_errorCode 42"),
+            ["HTTP", "Server", "error", "Code", "42"]
+        );
+        assert_eq!(camel_case("user-ID value"), "userIdValue");
+        assert_eq!(snake_case("parseHTTPResponse"), "parse_http_response");
+        assert_eq!(titlecase("the  qUICK fox"), "The  Quick Fox");
+        assert_eq!(camel_case(""), "");
+    }
+
+    #[test]
+    fn length_helpers_count_characters_and_clamp() {
+        assert_eq!(truncate("héllo", 5, "…"), "héllo");
+        assert_eq!(truncate("héllo world", 5, "…"), "héllo…");
+        assert_eq!(truncate("abc", 0, ""), "");
+        assert_eq!(pad_start("7", 3, "0"), "007");
+        assert_eq!(pad_start("1234", 3, "0"), "1234");
+        assert_eq!(pad_start("7", 4, "ab"), "aba7");
+        assert_eq!(pad_start("7", 4, ""), "7");
+        assert_eq!(substring("héllo", 1, Some(3)), "él");
+        assert_eq!(substring("héllo", 3, Some(1)), "");
+        assert_eq!(substring("héllo", 9, None), "");
+        assert_eq!(trim("--a-b--", Some("-")), "a-b");
+    }
+
+    #[test]
+    fn helpers_take_any_value_and_reject_bad_params() {
+        let data = json!({"name": "ada lovelace", "n": 12.5, "none": null, "list": [1]});
+        assert_eq!(
+            render(
+                "{{upper name}}|{{titlecase name}}|{{upper n}}|[{{lower none}}]|{{lower list}}",
+                data.clone()
+            )
+            .unwrap(),
+            "ADA LOVELACE|Ada Lovelace|12.5|[]|[1]"
+        );
+        let template = concat!(
+            r#"{{truncate name 3}}|{{truncate name 3 suffix="..."}}|"#,
+            r#"{{padStart n 6 "*"}}|{{substring name 4}}|{{trim "xax" "x"}}"#
+        );
+        assert_eq!(
+            render(template, data.clone()).unwrap(),
+            "ada…|ada...|**12.5|lovelace|a"
+        );
+        assert_eq!(render("{{slugify missing}}", data.clone()).unwrap(), "");
+        assert!(render("{{truncate name}}", data.clone()).is_err());
+        assert!(render(r#"{{truncate name "3"}}"#, data.clone()).is_err());
+        assert!(render("{{substring name -1}}", data).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! `table` helper: Markdown table from an array of objects.
+//!
+//! ```text
+//! {{table items}}                      every field, in order of first appearance
+//! {{table items "name,price"}}         chosen columns (dot paths work: "owner.name")
+//! {{table items "name:Product,price:Price (€)"}}   columns with their own headers
+//! ```
+//!
+//! Pipes are escaped and line breaks become `<br>`, so any value stays inside its cell.
+//! Columns holding only numbers are right-aligned, and cells are padded so the source
+//! table lines up.
+
+use crate::path;
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value;
+
+/// Column: dot path into each row and its header text
+struct Column {
+    path: String,
+    header: String,
+}
+
+/// Columns from "a,b:Header" or an array of names; all fields of the rows when absent
+fn columns(spec: Option<&Value>, rows: &[Value]) -> Vec<Column> {
+    let parse = |entry: &str| {
+        let entry = entry.trim();
+        let (path, header) = entry.split_once(':').unwrap_or((entry, entry));
+        Column {
+            path: path.trim().to_string(),
+            header: header.trim().to_string(),
+        }
+    };
+    match spec {
+        Some(Value::String(list)) => list
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(parse)
+            .collect(),
+        Some(Value::Array(list)) => list.iter().filter_map(Value::as_str).map(parse).collect(),
+        _ => {
+            let mut names: Vec<String> = Vec::new();
+            for row in rows {
+                if let Value::Object(map) = row {
+                    for key in map.keys() {
+                        if !names.contains(key) {
+                            names.push(key.clone());
+                        }
+                    }
+                }
+            }
+            names.iter().map(|name| parse(name)).collect()
+        }
+    }
+}
+
+/// Cell text: scalars as is, arrays of scalars joined with ", ", other values as JSON
+fn cell(value: Option<&Value>) -> String {
+    let text = match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) if items.iter().all(|v| !v.is_array() && !v.is_object()) => items
+            .iter()
+            .map(|v| cell(Some(v)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(other) => other.to_string(),
+    };
+    escape_cell(&text)
+}
+
+/// Keep a value inside its cell: escape pipes, turn line breaks into <br>
+fn escape_cell(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+}
+
+/// Render the table (empty for no rows or no columns)
+fn markdown_table(rows: &[Value], spec: Option<&Value>) -> String {
+    let columns = columns(spec, rows);
+    if rows.is_empty() || columns.is_empty() {
+        return String::new();
+    }
+    let values: Vec<Vec<Option<Value>>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|c| path::select(row, &c.path)).collect())
+        .collect();
+    let cells: Vec<Vec<String>> = values
+        .iter()
+        .map(|row| row.iter().map(|v| cell(v.as_ref())).collect())
+        .collect();
+    let headers: Vec<String> = columns.iter().map(|c| escape_cell(&c.header)).collect();
+
+    // Numeric columns (every non-empty value is a number) are right-aligned
+    let numeric: Vec<bool> = (0..columns.len())
+        .map(|i| {
+            let present: Vec<&Value> = values
+                .iter()
+                .filter_map(|row| row[i].as_ref())
+                .filter(|v| !v.is_null())
+                .collect();
+            !present.is_empty() && present.iter().all(|v| v.is_number())
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([headers[i].chars().count(), 3])
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+
+    let pad = |text: &str, i: usize| {
+        let fill = " ".repeat(widths[i] - text.chars().count());
+        if numeric[i] {
+            format!("{}{}", fill, text)
+        } else {
+            format!("{}{}", text, fill)
+        }
+    };
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row.iter().enumerate().map(|(i, c)| pad(c, i)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut out = line(&headers);
+    let rule: Vec<String> = widths
+        .iter()
+        .enumerate()
+        .map(|(i, &w)| {
+            if numeric[i] {
+                format!("{}:", "-".repeat(w - 1))
+            } else {
+                "-".repeat(w)
+            }
+        })
+        .collect();
+    out.push_str(&format!("| {} |\n", rule.join(" | ")));
+    for row in &cells {
+        out.push_str(&line(row));
+    }
+    out
+}
+
+/// {{table rows [columns]}}
+struct TableHelper;
+
+impl HelperDef for TableHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let table = match h.param(0).map(|p| p.value()) {
+            Some(Value::Array(rows)) => markdown_table(rows, h.param(1).map(|p| p.value())),
+            // A single object is a one-row table
+            Some(row @ Value::Object(_)) => {
+                markdown_table(std::slice::from_ref(row), h.param(1).map(|p| p.value()))
+            }
+            _ => String::new(),
+        };
+        Ok(ScopedJson::Derived(Value::String(table)))
+    }
+}
+
+/// The table helper with its name
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![("table", Box::new(TableHelper))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, data: Value) -> Result<String, RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(handlebars::no_escape);
+        for (name, helper) in helpers() {
+            hb.register_helper(name, helper);
+        }
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn tables_pad_cells_and_right_align_numeric_columns() {
+        let rows = json!([
+            {"name": "Tea", "price": 3, "tags": ["hot", "green"]},
+            {"name": "Cake | slice", "price": 12.5, "note": "two\nlines"}
+        ]);
+        assert_eq!(
+            markdown_table(rows.as_array().unwrap(), None),
+            concat!(
+                "| name          | price | tags       | note         |\n",
+                "| ------------- | ----: | ---------- | ------------ |\n",
+                "| Tea           |     3 | hot, green |              |\n",
+                "| Cake \\| slice |  12.5 |            | two<br>lines |\n",
+            )
+        );
+    }
+
+    #[test]
+    fn columns_can_be_chosen_renamed_and_nested() {
+        let data = json!({"rows": [{"owner": {"name": "Ada"}, "n": "7", "meta": {"a": 1}}]});
+        assert_eq!(
+            render(r#"{{table rows "owner.name:Owner, n"}}"#, data.clone()).unwrap(),
+            "| Owner | n   |\n| ----- | --- |\n| Ada   | 7   |\n"
+        );
+        assert_eq!(
+            markdown_table(data["rows"].as_array().unwrap(), Some(&json!(["meta"]))),
+            "| meta    |\n| ------- |\n| {\"a\":1} |\n"
+        );
+    }
+
+    #[test]
+    fn missing_or_empty_input_renders_nothing() {
+        let data = json!({"none": [], "text": "x", "row": {"a": 1}});
+        assert_eq!(
+            render(
+                "[{{table none}}][{{table text}}][{{table missing}}]",
+                data.clone()
+            )
+            .unwrap(),
+            "[][][]"
+        );
+        assert_eq!(render(r#"{{table none "a"}}"#, data.clone()).unwrap(), "");
+        assert_eq!(render(r#"{{table row ","}}"#, data.clone()).unwrap(), "");
+        assert_eq!(
+            render("{{table row}}", data).unwrap(),
+            "|   a |\n| --: |\n|   1 |\n"
+        );
+    }
+}
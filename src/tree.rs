@@ -0,0 +1,202 @@
+//! `eachTree` block helper: depth-first walk over nested data (folders, org charts,
+//! comment threads).
+//!
+//! ```text
+//! {{#eachTree folders children="items"}}
+//! {{@indent}}- {{name}}{{#if @leaf}} ({{size}} bytes){{/if}}
+//! {{/eachTree}}
+//! ```
+//!
+//! The first argument is a node or an array of nodes; each node's children are under
+//! `children` (the default key, dot paths work). Every node is rendered with itself as
+//! context and these variables:
+//!
+//! - `@depth`: 0 for the top level
+//! - `@path`: positions from the top, e.g. "0.2.1"
+//! - `@indent`: `indent` (default two spaces) repeated `@depth` times
+//! - `@index`, `@first`, `@last`: position among its siblings
+//! - `@leaf`: whether it has no children
+//!
+//! `as |node|` names the node; `{{else}}` renders when there are no nodes.
+
+use crate::path;
+use handlebars::{
+    BlockContext, BlockParams, Context, Handlebars, Helper, HelperDef, HelperResult, Output,
+    RenderContext, Renderable, to_json,
+};
+use serde_json::Value;
+
+/// Nodes of a tree level: the items of an array, or a single node
+fn nodes(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(items) => items,
+        Value::Null => &[],
+        node => std::slice::from_ref(node),
+    }
+}
+
+/// Where the walk is: tree options and the position of the current level
+struct Walk<'a> {
+    children: &'a str,
+    indent: &'a str,
+    depth: usize,
+    path: &'a str,
+}
+
+/// Render the nodes of a level, each followed by its subtree
+fn render_level<'reg: 'rc, 'rc>(
+    walk: &Walk<'_>,
+    level: &[Value],
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let Some(template) = h.template() else {
+        return Ok(());
+    };
+    for (i, node) in level.iter().enumerate() {
+        let children = path::select(node, walk.children).unwrap_or(Value::Null);
+        let children = nodes(&children);
+        let node_path = if walk.path.is_empty() {
+            i.to_string()
+        } else {
+            format!("{}.{}", walk.path, i)
+        };
+
+        let mut block = BlockContext::new();
+        block.set_base_value(node.clone());
+        block.set_local_var("depth", to_json(walk.depth));
+        block.set_local_var("path", to_json(&node_path));
+        block.set_local_var("indent", to_json(walk.indent.repeat(walk.depth)));
+        block.set_local_var("index", to_json(i));
+        block.set_local_var("first", to_json(i == 0));
+        block.set_local_var("last", to_json(i + 1 == level.len()));
+        block.set_local_var("leaf", to_json(children.is_empty()));
+        if let Some(name) = h.block_param() {
+            let mut params = BlockParams::new();
+            params.add_value(name, node.clone())?;
+            block.set_block_params(params);
+        }
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result?;
+
+        let below = Walk {
+            depth: walk.depth + 1,
+            path: &node_path,
+            ..*walk
+        };
+        render_level(&below, children, h, r, ctx, rc, out)?;
+    }
+    Ok(())
+}
+
+/// {{#eachTree nodes [children="children"] [indent="  "]}}…{{/eachTree}}
+struct EachTreeHelper;
+
+impl HelperDef for EachTreeHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let root = h.param(0).map_or(&Value::Null, |p| p.value());
+        let top = nodes(root);
+        if top.is_empty() {
+            return match h.inverse() {
+                Some(inverse) => inverse.render(r, ctx, rc, out),
+                None => Ok(()),
+            };
+        }
+        let hash_str = |key: &str| h.hash_get(key).and_then(|p| p.value().as_str());
+        let walk = Walk {
+            children: hash_str("children").unwrap_or("children"),
+            indent: hash_str("indent").unwrap_or("  "),
+            depth: 0,
+            path: "",
+        };
+        render_level(&walk, top, h, r, ctx, rc, out)
+    }
+}
+
+/// The tree helper with its name
+pub fn helpers() -> Vec<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+    vec![("eachTree", Box::new(EachTreeHelper))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> Result<String, handlebars::RenderError> {
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(handlebars::no_escape);
+        for (name, helper) in helpers() {
+            hb.register_helper(name, helper);
+        }
+        let data = json!({
+            "root": {"name": "/", "children": [
+                {"name": "docs", "children": [{"name": "a.md"}, {"name": "b.md"}]},
+                {"name": "README", "children": []}
+            ]},
+            "org": [{"name": "CEO", "team": {"members": [{"name": "CTO"}]}}],
+            "text": "plain"
+        });
+        hb.render_template(template, &data)
+    }
+
+    #[test]
+    fn walks_depth_first_with_position_variables() {
+        let outline = concat!(
+            "{{#eachTree root}}{{@indent}}{{name}} {{@path}}",
+            "{{#if @leaf}} leaf{{/if}}\n{{/eachTree}}"
+        );
+        assert_eq!(
+            render(outline).unwrap(),
+            "/ 0\n  docs 0.0\n    a.md 0.0.0 leaf\n    b.md 0.0.1 leaf\n  README 0.1 leaf\n"
+        );
+        let named = concat!(
+            r#"{{#eachTree root.children indent="-" as |n|}}"#,
+            "{{@indent}}{{n.name}}{{#if @last}}!{{/if}}{{@depth}} {{/eachTree}}"
+        );
+        assert_eq!(render(named).unwrap(), "docs0 -a.md1 -b.md!1 README!0 ");
+    }
+
+    #[test]
+    fn children_key_takes_a_dot_path() {
+        assert_eq!(
+            render(r#"{{#eachTree org children="team.members"}}{{name}}@{{@depth}} {{/eachTree}}"#)
+                .unwrap(),
+            "CEO@0 CTO@1 "
+        );
+        // Children under another key are not found: every node is a leaf
+        assert_eq!(
+            render(r#"{{#eachTree org}}{{name}}{{@leaf}} {{/eachTree}}"#).unwrap(),
+            "CEOtrue "
+        );
+    }
+
+    #[test]
+    fn missing_nodes_render_the_else_block() {
+        assert_eq!(
+            render("{{#eachTree missing}}x{{else}}empty{{/eachTree}}").unwrap(),
+            "empty"
+        );
+        assert_eq!(
+            render("{{#eachTree root.children.[1].children}}x{{/eachTree}}").unwrap(),
+            ""
+        );
+        assert_eq!(
+            render("{{#eachTree text}}[{{this}}]{{/eachTree}}").unwrap(),
+            "[plain]"
+        );
+        assert_eq!(render("{{eachTree root}}").unwrap(), "");
+    }
+}
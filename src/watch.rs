@@ -0,0 +1,85 @@
+//! Watch mode: re-run the conversion when an input file changes.
+//!
+//! Usage: --watch (data files, template, settings, helpers, vars and lookups)
+//!
+//! Parent directories are watched rather than the files themselves, so editors that
+//! save by writing a new file and renaming it over the old one are still noticed.
+//! Bursts of events are debounced into a single re-run, and only a changed modification
+//! time counts (reading the inputs during a run must not trigger another one).
+
+use anyhow::{Context, Result};
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{DebounceEventResult, new_debouncer};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// Quiet period after the last change before re-running
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Block forever, calling `rerun` whenever one of `files` changes
+pub fn watch(files: &[PathBuf], mut rerun: impl FnMut()) -> Result<()> {
+    let watched: HashSet<PathBuf> = files.iter().map(|f| absolute(f)).collect();
+    let dirs: HashSet<PathBuf> = watched
+        .iter()
+        .filter_map(|f| f.parent().map(Path::to_path_buf))
+        .collect();
+
+    let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(DEBOUNCE, tx).context("Failed to start file watcher")?;
+    for dir in &dirs {
+        debouncer
+            .watcher()
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+    let mut stamps: HashMap<PathBuf, Option<SystemTime>> =
+        watched.iter().map(|f| (f.clone(), modified(f))).collect();
+    info_log!(
+        "👀 Watching {} file(s), press Ctrl+C to stop",
+        watched.len()
+    );
+
+    for result in rx {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                error_log!("watch failed: {}", e);
+                continue;
+            }
+        };
+        let changed: Vec<_> = events
+            .iter()
+            .map(|event| absolute(&event.path))
+            .filter(|path| watched.contains(path))
+            .filter(|path| {
+                let stamp = modified(path);
+                stamps.insert(path.clone(), stamp) != Some(stamp)
+            })
+            .collect();
+        if let Some(path) = changed.first() {
+            info_log!("🔄 Changed: {}", path.display());
+            rerun();
+        }
+    }
+    Ok(())
+}
+
+/// Modification time, None while the file is missing (e.g. mid-save)
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|meta| meta.modified()).ok()
+}
+
+/// Canonical path where possible, so event paths and watched paths compare equal
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        let name = path.file_name().map(PathBuf::from).unwrap_or_default();
+        path.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .canonicalize()
+            .map_or_else(|_| path.to_path_buf(), |dir| dir.join(name))
+    })
+}